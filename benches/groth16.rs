@@ -1,7 +1,10 @@
 use ark_crypto_primitives::snark::SNARK;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use ark_circom::{read_zkey, CircomReduction, WitnessCalculator};
+use ark_circom::{
+    prepare_verifying_key, prove, read_zkey, verify, verify_with_prepared, CircomConfig,
+    CircomReduction, WitnessCalculator,
+};
 use ark_std::rand::thread_rng;
 
 use ark_bn254::{Bn254, Fr};
@@ -17,7 +20,7 @@ fn bench_groth(c: &mut Criterion, num_validators: u32, num_constraints: u32) {
         i, j
     );
     let mut file = File::open(path).unwrap();
-    let (params, matrices) = read_zkey(&mut file).unwrap();
+    let (params, matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
     let num_inputs = matrices.num_instance_variables;
     let num_constraints = matrices.num_constraints;
 
@@ -80,6 +83,31 @@ fn bench_groth(c: &mut Criterion, num_validators: u32, num_constraints: u32) {
     });
 }
 
+fn bench_verify(c: &mut Criterion) {
+    let mut file = File::open("./test-vectors/test.zkey").unwrap();
+    let (params, _) = read_zkey::<Bn254, _>(&mut file).unwrap();
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )
+    .unwrap();
+    let inputs = vec![
+        ("a".to_string(), vec![3.into()]),
+        ("b".to_string(), vec![11.into()]),
+    ];
+    let (proof, public_inputs) = prove(cfg, inputs, &params).unwrap();
+
+    let mut group = c.benchmark_group("groth verify");
+    group.bench_function("fresh key", |b| {
+        b.iter(|| assert!(verify(&params.vk, &proof, &public_inputs).unwrap()))
+    });
+    let pvk = prepare_verifying_key(&params.vk);
+    group.bench_function("prepared key", |b| {
+        b.iter(|| assert!(verify_with_prepared(&pvk, &proof, &public_inputs).unwrap()))
+    });
+    group.finish();
+}
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "bench-complex-all")] {
         const MIN_NUM_VARIABLES_POWER: u32 = 3;
@@ -94,12 +122,12 @@ cfg_if::cfg_if! {
                 }
             }
         }
-        criterion_group!(benches, groth_all);
+        criterion_group!(benches, groth_all, bench_verify);
     } else {
       fn groth(c: &mut Criterion) {
         bench_groth(c, 10000, 10000);
       }
-      criterion_group!(benches, groth);
+      criterion_group!(benches, groth, bench_verify);
     }
 }
 
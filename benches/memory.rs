@@ -0,0 +1,32 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use ark_circom::WitnessCalculator;
+use num_bigint::BigInt;
+
+const NUM_ELEMENTS: usize = 10_000;
+
+fn write_fr(c: &mut Criterion) {
+    let mut wtns = WitnessCalculator::new("./test-vectors/mycircuit.wasm").unwrap();
+    let values = (0..NUM_ELEMENTS)
+        .map(|i| BigInt::from(i) << 128)
+        .collect::<Vec<_>>();
+    let ptr = wtns.memory.alloc_fr_array(values.len()).unwrap() as usize;
+    let fr_size = wtns.memory.fr_size();
+
+    c.bench_function(&format!("write_fr {}", NUM_ELEMENTS), |b| {
+        b.iter(|| {
+            for (i, value) in values.iter().enumerate() {
+                wtns.memory
+                    .write_fr(ptr + i * fr_size, black_box(value))
+                    .unwrap();
+            }
+        })
+    });
+
+    c.bench_function(&format!("write_fr_array {}", NUM_ELEMENTS), |b| {
+        b.iter(|| wtns.memory.write_fr_array(ptr, black_box(&values)).unwrap())
+    });
+}
+
+criterion_group!(benches, write_fr);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use ark_circom::{WitnessCalculator, WitnessCalculatorModule};
+use num_bigint::BigInt;
+
+const WASM: &str = "./test-vectors/smtverifier10.wasm";
+
+const COMPLEX_WASM: &str = "./test-vectors/complex-circuit/complex-circuit-10000-10000.wasm";
+
+// Stubs a Circom 2 module whose witness has `size` values, so that only reading the
+// witness out of the shared memory is measured
+fn witness_wat(size: u32) -> String {
+    format!(
+        r#"(module
+    (func (export "getVersion") (result i32) i32.const 2)
+    (func (export "getFieldNumLen32") (result i32) i32.const 8)
+    (func (export "getRawPrime"))
+    (func (export "readSharedRWMemory") (param i32) (result i32) i32.const 7)
+    (func (export "init") (param i32))
+    (func (export "getWitness") (param i32))
+    (func (export "getWitnessSize") (result i32) i32.const {}))"#,
+        size
+    )
+}
+
+fn instantiate(c: &mut Criterion) {
+    c.bench_function("witness calculator from_file", |b| {
+        b.iter(|| black_box(WitnessCalculator::from_file(WASM).unwrap()))
+    });
+
+    let module = WitnessCalculatorModule::from_file(WASM).unwrap();
+    c.bench_function("witness calculator module instantiate", |b| {
+        b.iter(|| black_box(module.instantiate().unwrap()))
+    });
+}
+
+fn read_witness(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read circom 2 witness");
+    group.sample_size(10);
+    for size in [100_000, 1_000_000] {
+        let mut wtns = WitnessCalculator::from_bytes(witness_wat(size).as_bytes()).unwrap();
+        group.bench_function(size.to_string(), |b| {
+            b.iter(|| black_box(wtns.calculate_witness(vec![], false).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn reserve_memory(c: &mut Criterion) {
+    for pages in [None, Some(4000)] {
+        let module = WitnessCalculatorModule::from_file(COMPLEX_WASM).unwrap();
+        let name = match pages {
+            Some(pages) => format!("complex circuit witness with {} pages", pages),
+            None => "complex circuit witness with default pages".to_string(),
+        };
+        // a fresh instance each time, whose memory hasn't grown yet
+        c.bench_function(&name, |b| {
+            b.iter(|| {
+                let mut wtns = module.instantiate().unwrap();
+                if let Some(pages) = pages {
+                    wtns.reserve_memory(pages).unwrap();
+                }
+                let inputs = vec![("a".to_string(), vec![BigInt::from(3)])];
+                black_box(wtns.calculate_witness(inputs, false).unwrap())
+            })
+        });
+    }
+}
+
+fn sanity_check(c: &mut Criterion) {
+    let mut wtns = WitnessCalculator::new(COMPLEX_WASM).unwrap();
+    for sanity_check in [true, false] {
+        let name = format!("complex circuit witness with sanity check {}", sanity_check);
+        c.bench_function(&name, |b| {
+            b.iter(|| {
+                let inputs = vec![("a".to_string(), vec![BigInt::from(3)])];
+                black_box(wtns.calculate_witness(inputs, sanity_check).unwrap())
+            })
+        });
+    }
+}
+
+criterion_group!(
+    benches,
+    instantiate,
+    read_witness,
+    reserve_memory,
+    sanity_check
+);
+criterion_main!(benches);
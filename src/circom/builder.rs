@@ -1,14 +1,83 @@
-use std::{fs::File, path::Path};
+use std::{io::Cursor, path::Path};
 
 use ark_ff::PrimeField;
 
-use super::{CircomCircuit, R1CS};
+use super::{CircomCircuit, SymbolTable, R1CS};
 
-use num_bigint::BigInt;
+use num_bigint::{BigInt, BigUint};
 use std::collections::HashMap;
 
-use crate::{circom::R1CSFile, witness::WitnessCalculator};
-use color_eyre::Result;
+use crate::{
+    circom::R1CSFile,
+    witness::{Compiler, ModuleCache, WitnessCalculator},
+};
+use crate::{Error, Result};
+use serde_json::Value;
+use wasmer::Engine;
+
+/// The inputs pushed to a [`CircomBuilder`] don't match the circuit's input signals, as
+/// returned by [`CircomBuilder::validate_inputs`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "missing inputs [{}], unexpected inputs [{}], inputs of the wrong length [{}]",
+    .missing.join(", "),
+    .extra.join(", "),
+    format_lengths(.lengths)
+)]
+pub struct InputValidationError {
+    /// The input signals without a value, in the order of their wires
+    pub missing: Vec<String>,
+    /// The pushed inputs which aren't input signals of the circuit, sorted by name
+    pub extra: Vec<String>,
+    /// The pushed inputs with another number of values than their signal, as
+    /// `(name, expected, actual)`, sorted by name
+    pub lengths: Vec<(String, usize, usize)>,
+}
+
+/// A value which can be pushed as a Circom input, see [`CircomBuilder::push_input`]:
+/// a primitive integer, a `BigInt` or `BigUint`, or a decimal or `0x`-prefixed
+/// hexadecimal string.
+pub trait IntoCircomInput {
+    fn into_bigint(self) -> BigInt;
+}
+
+macro_rules! impl_into_circom_input {
+    ($($t:ty),*) => {
+        $(
+            impl IntoCircomInput for $t {
+                fn into_bigint(self) -> BigInt {
+                    self.into()
+                }
+            }
+        )*
+    };
+}
+
+impl_into_circom_input!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, BigInt, BigUint
+);
+
+/// Panics if the string isn't an integer, see [`CircomBuilder::push_input_str`] for
+/// strings which may not be.
+impl IntoCircomInput for &str {
+    fn into_bigint(self) -> BigInt {
+        parse_bigint(self).unwrap_or_else(|| panic!("{} is not an integer", self))
+    }
+}
+
+impl IntoCircomInput for String {
+    fn into_bigint(self) -> BigInt {
+        self.as_str().into_bigint()
+    }
+}
+
+fn format_lengths(lengths: &[(String, usize, usize)]) -> String {
+    let lengths: Vec<String> = lengths
+        .iter()
+        .map(|(name, expected, actual)| format!("{}: {} instead of {}", name, actual, expected))
+        .collect();
+    lengths.join(", ")
+}
 
 #[derive(Clone, Debug)]
 pub struct CircomBuilder<F: PrimeField> {
@@ -16,23 +85,82 @@ pub struct CircomBuilder<F: PrimeField> {
     pub inputs: HashMap<String, Vec<BigInt>>,
 }
 
+/// A circuit's witness calculator along with its R1CS. The R1CS is only needed to
+/// generate the constraints, e.g. for a setup or to check a witness. Computing
+/// witnesses only needs the WASM, with a [`WitnessCalculator`], which
+/// [`prove_with_matrices`](crate::prove_with_matrices) proves with a zkey.
 // Add utils for creating this from files / directly from bytes
 #[derive(Clone, Debug)]
 pub struct CircomConfig<F: PrimeField> {
     pub r1cs: R1CS<F>,
     pub wtns: WitnessCalculator,
+    /// Whether the WASM checks e.g. that the inputs are in the field and that the
+    /// circuit's asserts hold while calculating the witness. On by default, turning
+    /// it off speeds up the calculation of trusted circuits.
     pub sanity_check: bool,
+    /// The circuit's symbol table, e.g. loaded with [`SymbolTable::from_file`], which
+    /// names and sizes the input signals for [`CircomBuilder::validate_inputs`] and
+    /// [`CircomBuilder::push_input_array`]. None by default.
+    pub symbols: Option<SymbolTable>,
 }
 
 impl<F: PrimeField> CircomConfig<F> {
     pub fn new(wtns: impl AsRef<Path>, r1cs: impl AsRef<Path>) -> Result<Self> {
-        let wtns = WitnessCalculator::new(wtns).unwrap();
-        let reader = File::open(r1cs)?;
-        let r1cs = R1CSFile::new(reader)?.into();
+        Self::from_bytes(&std::fs::read(wtns)?, &std::fs::read(r1cs)?)
+    }
+
+    /// Creates the config from the in-memory WASM and R1CS files, e.g. embedded
+    /// with `include_bytes!`
+    ///
+    /// The R1CS format doesn't record the Circom version which produced it, so mixed
+    /// up artifacts are detected by comparing the witness size with the R1CS' wires.
+    pub fn from_bytes(wasm: &[u8], r1cs: &[u8]) -> Result<Self> {
+        Self::from_calculator(WitnessCalculator::from_bytes(wasm)?, r1cs)
+    }
+
+    /// Same as [`CircomConfig::from_bytes`], but compiles the WASM with the given compiler
+    pub fn from_bytes_with_compiler(wasm: &[u8], r1cs: &[u8], compiler: Compiler) -> Result<Self> {
+        Self::from_calculator(
+            WitnessCalculator::from_bytes_with_compiler(wasm, compiler)?,
+            r1cs,
+        )
+    }
+
+    /// Same as [`CircomConfig::from_bytes`], but compiles the WASM with a caller
+    /// configured engine, see [`WitnessCalculatorModule::from_bytes_with_engine`]
+    ///
+    /// [`WitnessCalculatorModule::from_bytes_with_engine`]: crate::WitnessCalculatorModule::from_bytes_with_engine
+    pub fn from_bytes_with_engine(wasm: &[u8], r1cs: &[u8], engine: &Engine) -> Result<Self> {
+        Self::from_calculator(
+            WitnessCalculator::from_bytes_with_engine(wasm, engine)?,
+            r1cs,
+        )
+    }
+
+    /// Same as [`CircomConfig::from_bytes`], but reuses the compiled WASM module from
+    /// the cache if the same circuit was loaded before
+    pub fn from_bytes_cached(cache: &ModuleCache, wasm: &[u8], r1cs: &[u8]) -> Result<Self> {
+        Self::from_calculator(cache.calculator(wasm)?, r1cs)
+    }
+
+    fn from_calculator(wtns: WitnessCalculator, r1cs: &[u8]) -> Result<Self> {
+        let r1cs: R1CSFile<F> = R1CSFile::new(Cursor::new(r1cs))?;
+
+        let witness_size = wtns.witness_size()?;
+        if witness_size != r1cs.header.n_wires {
+            return Err(Error::WitnessSizeMismatch {
+                circom_version: wtns.circom_version,
+                witness_size,
+                n_wires: r1cs.header.n_wires,
+            });
+        }
+
+        let r1cs = r1cs.into();
         Ok(Self {
             wtns,
             r1cs,
-            sanity_check: false,
+            sanity_check: true,
+            symbols: None,
         })
     }
 }
@@ -47,10 +175,93 @@ impl<F: PrimeField> CircomBuilder<F> {
         }
     }
 
-    /// Pushes a Circom input at the specified name.
-    pub fn push_input<T: Into<BigInt>>(&mut self, name: impl ToString, val: T) {
+    /// Pushes a Circom input at the specified name. Pushing the same name again appends
+    /// to its values, which builds up an array input one element at a time. Use
+    /// [`CircomBuilder::set_input`] to replace a scalar input instead.
+    ///
+    /// The value can be any integer, e.g. `u64`, `i64`, `u128` or `BigUint`, or a
+    /// decimal string, see [`IntoCircomInput`]. Negative values are reduced into the
+    /// field, so `-1` is `p - 1`.
+    ///
+    /// # Panics
+    ///
+    /// If the value is a string which isn't an integer. Use
+    /// [`CircomBuilder::push_input_str`] for strings which may not be, e.g. user input.
+    pub fn push_input<T: IntoCircomInput>(&mut self, name: impl ToString, val: T) {
         let values = self.inputs.entry(name.to_string()).or_default();
-        values.push(val.into());
+        values.push(val.into_bigint());
+    }
+
+    /// Sets a scalar Circom input, replacing any value previously pushed for the name.
+    pub fn set_input<T: IntoCircomInput>(&mut self, name: impl ToString, val: T) {
+        self.inputs
+            .insert(name.to_string(), vec![val.into_bigint()]);
+    }
+
+    /// Sets all the elements of an array input at once, replacing any elements set
+    /// before. Fails without modifying them with [`Error::InputLengthMismatch`] if the
+    /// number of elements differs from the size of the circuit's signal, and with
+    /// [`Error::UnknownSignal`] if the circuit has no such input, see
+    /// [`WitnessCalculator::input_signal_size`]. For modules which don't tell the sizes,
+    /// the size is taken from the config's symbol table if any, or else only checked
+    /// against the number of elements set before, if any.
+    pub fn push_input_array<T: IntoCircomInput>(
+        &mut self,
+        name: impl ToString,
+        vals: impl IntoIterator<Item = T>,
+    ) -> Result<()> {
+        let name = name.to_string();
+        let vals = vals
+            .into_iter()
+            .map(IntoCircomInput::into_bigint)
+            .collect::<Vec<_>>();
+        match self.input_signal_size(&name)? {
+            Some(size) if size != vals.len() => {
+                return Err(Error::InputLengthMismatch {
+                    name,
+                    expected: size as u32,
+                    actual: vals.len(),
+                });
+            }
+            Some(_) => {}
+            None => {
+                if let Some(previous) = self.inputs.get(&name) {
+                    if previous.len() != vals.len() {
+                        return Err(Error::InvalidInput(format!(
+                            "{} has {} elements, but {} were given before",
+                            name,
+                            vals.len(),
+                            previous.len()
+                        )));
+                    }
+                }
+            }
+        }
+        self.inputs.insert(name, vals);
+        Ok(())
+    }
+
+    /// Pushes a Circom input given as a decimal or `0x`-prefixed hexadecimal string.
+    pub fn push_input_str(&mut self, name: impl ToString, val: &str) -> Result<()> {
+        let name = name.to_string();
+        let val = parse_bigint(val).ok_or_else(|| invalid_integer(&name, val))?;
+        self.push_input(name, val);
+        Ok(())
+    }
+
+    /// Pushes the inputs of a JSON object, as in snarkjs' `input.json`. Nested arrays
+    /// are flattened in row-major order, and values may be integers or decimal or
+    /// hexadecimal strings.
+    pub fn push_inputs_json(&mut self, value: &Value) -> Result<()> {
+        let inputs = value.as_object().ok_or_else(|| {
+            Error::InvalidInput(format!("Inputs must be a JSON object, got {}", value))
+        })?;
+        for (name, value) in inputs {
+            let mut values = vec![];
+            flatten_json(name, value, &mut values)?;
+            self.inputs.entry(name.clone()).or_default().extend(values);
+        }
+        Ok(())
     }
 
     /// Generates an empty circom circuit with no witness set, to be used for
@@ -59,6 +270,7 @@ impl<F: PrimeField> CircomBuilder<F> {
         let mut circom = CircomCircuit {
             r1cs: self.cfg.r1cs.clone(),
             witness: None,
+            public_inputs: None,
         };
 
         // Disable the wire mapping
@@ -67,16 +279,112 @@ impl<F: PrimeField> CircomBuilder<F> {
         circom
     }
 
+    /// Generates a circom circuit with no witness but the given public signals, i.e. the
+    /// outputs followed by the public inputs, to synthesize the instance on the
+    /// verifier's side. The private signals get placeholder values.
+    pub fn setup_with_public_inputs(&self, public_inputs: Vec<F>) -> Result<CircomCircuit<F>> {
+        let expected = self.cfg.r1cs.num_inputs - 1;
+        if public_inputs.len() != expected {
+            return Err(Error::InvalidInput(format!(
+                "expected {} public signals, got {}",
+                expected,
+                public_inputs.len()
+            )));
+        }
+
+        let mut circom = self.setup();
+        circom.public_inputs = Some(public_inputs);
+        Ok(circom)
+    }
+
+    /// Clears the inputs pushed so far, keeping the config so that the builder can
+    /// be reused for the next proof
+    pub fn reset_inputs(&mut self) {
+        self.inputs.clear();
+    }
+
+    /// Checks that a value was pushed for every input signal of the circuit, and none
+    /// for other names, and that each input has as many values as its signal, without
+    /// running the WASM, which only traps on the first unknown signal and doesn't
+    /// report missing ones by name.
+    ///
+    /// The input signals are the ones named by the config's symbol table, see
+    /// [`R1CS::input_signals`]. Without one, the module doesn't tell the names of its
+    /// inputs, so missing inputs aren't reported, and the pushed ones are only checked
+    /// for modules telling the sizes, see [`WitnessCalculator::input_signal_size`].
+    pub fn validate_inputs(&self) -> Result<(), InputValidationError> {
+        let mut inputs: Vec<_> = self.inputs.iter().collect();
+        inputs.sort_by_key(|(name, _)| *name);
+
+        let mut error = InputValidationError::default();
+        match &self.cfg.symbols {
+            Some(symbols) => {
+                let signals = self.cfg.r1cs.input_signals(symbols);
+                error.missing = signals
+                    .iter()
+                    .filter(|signal| !self.inputs.contains_key(&signal.name))
+                    .map(|signal| signal.name.clone())
+                    .collect();
+                for (name, values) in inputs {
+                    match signals.iter().find(|signal| &signal.name == name) {
+                        Some(signal) => {
+                            let size = signal.dimensions.iter().product();
+                            if values.len() != size {
+                                error.lengths.push((name.clone(), size, values.len()));
+                            }
+                        }
+                        None => error.extra.push(name.clone()),
+                    }
+                }
+            }
+            None => {
+                for (name, values) in inputs {
+                    match self.cfg.wtns.input_signal_size(name) {
+                        Ok(Some(size)) if values.len() != size as usize => {
+                            error
+                                .lengths
+                                .push((name.clone(), size as usize, values.len()));
+                        }
+                        Ok(_) => {}
+                        Err(_) => error.extra.push(name.clone()),
+                    }
+                }
+            }
+        }
+
+        if error == InputValidationError::default() {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    // Returns the number of values of an input signal, as told by the module, or else
+    // by the symbol table, if any
+    fn input_signal_size(&self, name: &str) -> Result<Option<usize>> {
+        if let Some(size) = self.cfg.wtns.input_signal_size(name)? {
+            return Ok(Some(size as usize));
+        }
+        Ok(self.cfg.symbols.as_ref().and_then(|symbols| {
+            self.cfg
+                .r1cs
+                .input_signals(symbols)
+                .into_iter()
+                .find(|signal| signal.name == name)
+                .map(|signal| signal.dimensions.iter().product())
+        }))
+    }
+
     /// Creates the circuit populated with the witness corresponding to the previously
     /// provided inputs
-    pub fn build(mut self) -> Result<CircomCircuit<F>> {
+    pub fn build(&mut self) -> Result<CircomCircuit<F>> {
         let mut circom = self.setup();
 
         // calculate the witness
         let witness = self
             .cfg
             .wtns
-            .calculate_witness_element::<F, _>(self.inputs, self.cfg.sanity_check)?;
+            .calculate_witness_element::<F, _>(self.inputs.clone(), self.cfg.sanity_check)?;
         circom.witness = Some(witness);
 
         // sanity check
@@ -98,3 +406,55 @@ impl<F: PrimeField> CircomBuilder<F> {
         Ok(circom)
     }
 }
+
+fn flatten_json(name: &str, value: &Value, values: &mut Vec<BigInt>) -> Result<()> {
+    match value {
+        Value::Array(elements) => {
+            for element in elements {
+                flatten_json(name, element, values)?;
+            }
+        }
+        Value::Number(number) => {
+            let number = number
+                .as_i64()
+                .map(BigInt::from)
+                .or_else(|| number.as_u64().map(BigInt::from))
+                .ok_or_else(|| invalid_integer(name, number))?;
+            values.push(number);
+        }
+        Value::String(string) => {
+            let number = parse_bigint(string).ok_or_else(|| invalid_integer(name, string))?;
+            values.push(number);
+        }
+        _ => return Err(invalid_integer(name, value)),
+    }
+    Ok(())
+}
+
+fn invalid_integer(name: &str, val: impl std::fmt::Display) -> Error {
+    Error::InvalidInput(format!(
+        "{} is not an integer, a decimal or a 0x-prefixed hex string: {}",
+        name, val
+    ))
+}
+
+pub(crate) fn parse_bigint(val: &str) -> Option<BigInt> {
+    let (negative, digits) = match val.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, val),
+    };
+    let (radix, digits) = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => (16, hex),
+        None => (10, digits),
+    };
+    // parse_bytes also accepts signs and underscores
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let number = BigInt::from(BigUint::parse_bytes(digits.as_bytes(), radix)?);
+
+    Some(if negative { -number } else { number })
+}
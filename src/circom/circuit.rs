@@ -1,85 +1,335 @@
 use ark_relations::r1cs::{
-    ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystemRef, LinearCombination,
+    SynthesisError, Variable,
 };
 
+use ark_ec::pairing::Pairing;
 use ark_ff::PrimeField;
+use ark_groth16::{Groth16, Proof, ProvingKey};
+use ark_std::rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
 
-use super::R1CS;
+use std::{borrow::Borrow, collections::BTreeMap, io::Read};
 
-use color_eyre::Result;
+use super::{CircomReduction, ConstraintVec, Constraints, R1CS};
+use crate::{witness::read_wtns_for_circuit, Error, Result};
 
 #[derive(Clone, Debug)]
 pub struct CircomCircuit<F: PrimeField> {
     pub r1cs: R1CS<F>,
     pub witness: Option<Vec<F>>,
+    /// The public signals, without the leading constant one, for synthesizing the
+    /// instance without a witness, e.g. on the verifier's side. Ignored when the
+    /// witness is set.
+    pub public_inputs: Option<Vec<F>>,
 }
 
 impl<F: PrimeField> CircomCircuit<F> {
+    /// Creates the circuit with the witness of a `.wtns` file, e.g. computed by snarkjs
+    /// or circom's C++ witness generator in another process, so that it can be proven
+    /// without running the WASM.
+    ///
+    /// The witness must be for the circuit of the R1CS, which is checked by its size
+    /// and by the prime of the file matching the field, see [`read_wtns_for_circuit`].
+    pub fn from_wtns<R: Read>(mut r1cs: R1CS<F>, reader: &mut R) -> Result<Self> {
+        let witness = read_wtns_for_circuit::<F, _>(reader)?;
+        if witness.len() != r1cs.num_variables {
+            return Err(Error::InvalidInput(format!(
+                "the witness has {} values, but the R1CS has {} wires",
+                witness.len(),
+                r1cs.num_variables
+            )));
+        }
+
+        // the witness is indexed by wire, same as the one of the witness calculator
+        r1cs.wire_mapping = None;
+        Ok(Self {
+            r1cs,
+            witness: Some(witness),
+            public_inputs: None,
+        })
+    }
+
+    /// Returns the public signals of the witness, i.e. the outputs followed by the
+    /// public inputs, in the order expected by the verifier. This is the instance
+    /// allocated by `generate_constraints`, without the leading constant one.
+    /// Without a witness these are the given public inputs, if any.
     pub fn get_public_inputs(&self) -> Option<Vec<F>> {
         match &self.witness {
-            None => None,
+            None => self.public_inputs.clone(),
             Some(w) => match &self.r1cs.wire_mapping {
                 None => Some(w[1..self.r1cs.num_inputs].to_vec()),
                 Some(m) => Some(m[1..self.r1cs.num_inputs].iter().map(|i| w[*i]).collect()),
             },
         }
     }
-}
 
-impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuit<F> {
-    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        let witness = &self.witness;
-        let wire_mapping = &self.r1cs.wire_mapping;
+    /// Returns the full assignment `[1, public signals.., private signals..]`, in the
+    /// order of the variables allocated by `generate_constraints`, e.g. for provers
+    /// taking the assignment along with [`CircomCircuit::matrices`]. Returns `None` if
+    /// the witness hasn't been computed.
+    pub fn full_assignment(&self) -> Option<Vec<F>> {
+        let witness = self.witness.as_ref()?;
+        let assignment = (0..self.r1cs.num_variables).map(|i| match (i, &self.r1cs.wire_mapping) {
+            (0, _) => F::one(),
+            (i, Some(m)) => witness[m[i]],
+            (i, None) => witness[i],
+        });
+        Some(assignment.collect())
+    }
 
-        // Start from 1 because Arkworks implicitly allocates One for the first input
-        for i in 1..self.r1cs.num_inputs {
-            cs.new_input_variable(|| {
-                Ok(match witness {
-                    None => F::from(1u32),
-                    Some(w) => match wire_mapping {
-                        Some(m) => w[m[i]],
-                        None => w[i],
-                    },
-                })
-            })?;
-        }
+    /// Returns the number of public outputs of the circuit
+    pub fn num_outputs(&self) -> usize {
+        self.r1cs.num_outputs()
+    }
 
-        for i in 0..self.r1cs.num_aux {
-            cs.new_witness_variable(|| {
-                Ok(match witness {
-                    None => F::from(1u32),
-                    Some(w) => match wire_mapping {
-                        Some(m) => w[m[i + self.r1cs.num_inputs]],
-                        None => w[i + self.r1cs.num_inputs],
-                    },
-                })
-            })?;
+    /// Returns the number of public inputs of the circuit, excluding the outputs
+    pub fn num_public_inputs(&self) -> usize {
+        self.r1cs.num_public_inputs()
+    }
+
+    /// Returns the number of private inputs of the circuit
+    pub fn num_private_inputs(&self) -> usize {
+        self.r1cs.num_private_inputs()
+    }
+
+    /// Checks that the proving key has as many wires and public signals as the circuit.
+    /// Circom's optimization levels change the wires, so a zkey generated from the
+    /// R1CS of one level can't prove witnesses computed by the WASM of another. See
+    /// [`CircomCircuit::check_zkey_matrices`] to compare the constraints too.
+    pub fn check_proving_key<E: Pairing<ScalarField = F>>(&self, pk: &ProvingKey<E>) -> Result<()> {
+        // the queries have an entry for every wire, and the IC one for the constant one
+        // followed by the public signals
+        let pk_wires = pk.a_query.len();
+        let pk_public = pk.vk.gamma_abc_g1.len().saturating_sub(1);
+        let (wires, public) = (self.r1cs.num_variables, self.r1cs.num_inputs - 1);
+        if pk_wires != wires || pk_public != public {
+            return Err(Error::ProvingKeyMismatch {
+                pk_wires,
+                pk_public,
+                wires,
+                public,
+            });
         }
+        Ok(())
+    }
 
-        let make_index = |index| {
-            if index < self.r1cs.num_inputs {
-                Variable::Instance(index)
-            } else {
-                Variable::Witness(index - self.r1cs.num_inputs)
+    /// Checks that the constraint matrices of a zkey, as read by
+    /// [`read_zkey`](crate::read_zkey), are the circuit's. Unlike
+    /// [`CircomCircuit::check_proving_key`], this catches a zkey generated from the R1CS
+    /// of another optimization level, or of another circuit, with as many wires and
+    /// public signals. The zkey only records the A and B matrices, as the prover
+    /// computes `Cz` as `Az * Bz`, so C isn't compared.
+    pub fn check_zkey_matrices(&self, matrices: &ConstraintMatrices<F>) -> Result<()> {
+        let constraints = &self.r1cs.constraints;
+        for (matrix, rows, r1cs) in [
+            (
+                'A',
+                &matrices.a,
+                constraints.iter().map(|c| &c.0).collect::<Vec<_>>(),
+            ),
+            ('B', &matrices.b, constraints.iter().map(|c| &c.1).collect()),
+        ] {
+            for (constraint, (row, lc)) in rows.iter().zip(&r1cs).enumerate() {
+                if let Some(wire) = first_difference(lc, row) {
+                    return Err(Error::ConstraintMismatch {
+                        matrix,
+                        constraint,
+                        wire: Some(wire),
+                    });
+                }
             }
-        };
-        let make_lc = |lc_data: &[(usize, F)]| {
-            lc_data.iter().fold(
-                LinearCombination::<F>::zero(),
-                |lc: LinearCombination<F>, (index, coeff)| lc + (*coeff, make_index(*index)),
-            )
+            if rows.len() != r1cs.len() {
+                return Err(Error::ConstraintMismatch {
+                    matrix,
+                    constraint: rows.len().min(r1cs.len()),
+                    wire: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a Groth16 proof with snarkjs' witness map, see [`CircomReduction`].
+    /// Production code should pass a secure rng such as `OsRng`. Fails if the proving
+    /// key doesn't match the circuit, see [`CircomCircuit::check_proving_key`].
+    pub fn prove<E: Pairing<ScalarField = F>>(
+        self,
+        pk: &ProvingKey<E>,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<Proof<E>> {
+        self.check_proving_key(pk)?;
+        Ok(Groth16::<E, CircomReduction>::create_random_proof_with_reduction(self, pk, rng)?)
+    }
+
+    /// Creates a Groth16 proof whose randomness is derived from `seed`, so that the
+    /// same witness and seed always produce the same proof. As anyone knowing the
+    /// seed can recover the witness from the proof, this is only meant for tests.
+    pub fn prove_with_seed<E: Pairing<ScalarField = F>>(
+        self,
+        pk: &ProvingKey<E>,
+        seed: u64,
+    ) -> Result<Proof<E>> {
+        self.prove(pk, &mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Checks that the witness satisfies every constraint, i.e. that `Az * Bz = Cz`,
+    /// returning the index of the first unsatisfied constraint otherwise. This is
+    /// much cheaper than finding out through a failed proof verification. A missing
+    /// witness, or one without a value for some wire, is a
+    /// [`SynthesisError::AssignmentMissing`].
+    pub fn check_witness_satisfaction(&self) -> Result<()> {
+        let witness = self
+            .witness
+            .as_ref()
+            .ok_or(SynthesisError::AssignmentMissing)?;
+        let wire_mapping = &self.r1cs.wire_mapping;
+        let eval = |lc: &ConstraintVec<F>| -> Result<F> {
+            lc.iter()
+                .map(|(index, coeff)| {
+                    let wire = match wire_mapping {
+                        Some(m) => m.get(*index).copied(),
+                        None => Some(*index),
+                    };
+                    let value = wire
+                        .and_then(|wire| witness.get(wire))
+                        .ok_or(SynthesisError::AssignmentMissing)?;
+                    Ok(*coeff * value)
+                })
+                .sum()
         };
 
-        for constraint in &self.r1cs.constraints {
-            cs.enforce_constraint(
-                make_lc(&constraint.0),
-                make_lc(&constraint.1),
-                make_lc(&constraint.2),
-            )?;
+        for (i, (a, b, c)) in self.r1cs.constraints.iter().enumerate() {
+            if eval(a)? * eval(b)? != eval(c)? {
+                return Err(Error::UnsatisfiedConstraint(i));
+            }
         }
-
         Ok(())
     }
+
+    /// Returns the sparse A, B and C matrices of the circuit's constraints, as
+    /// produced by Arkworks' `ConstraintSystem::to_matrices`.
+    ///
+    /// Each row is a constraint, given as `(coefficient, variable)` pairs. Variables
+    /// are indexed as in the R1CS file: the constant one first, then the
+    /// `num_inputs - 1` public signals (outputs, then inputs) and finally the
+    /// private signals. Unlike the zkey's matrices, the rows don't include the
+    /// public input constraints which Arkworks adds when building the QAP.
+    pub fn matrices(&self) -> ConstraintMatrices<F> {
+        let constraints = &self.r1cs.constraints;
+        let a = to_matrix(constraints.iter().map(|c| &c.0));
+        let b = to_matrix(constraints.iter().map(|c| &c.1));
+        let c = to_matrix(constraints.iter().map(|c| &c.2));
+
+        ConstraintMatrices {
+            num_instance_variables: self.r1cs.num_inputs,
+            num_witness_variables: self.r1cs.num_aux,
+            num_constraints: constraints.len(),
+
+            a_num_non_zero: a.iter().map(|lc| lc.len()).sum(),
+            b_num_non_zero: b.iter().map(|lc| lc.len()).sum(),
+            c_num_non_zero: c.iter().map(|lc| lc.len()).sum(),
+
+            a,
+            b,
+            c,
+        }
+    }
+}
+
+// Returns the lowest wire whose coefficient differs between a linear combination of
+// the R1CS and a row of the zkey's matrices
+fn first_difference<F: PrimeField>(lc: &ConstraintVec<F>, row: &[(F, usize)]) -> Option<usize> {
+    let lc: BTreeMap<usize, F> = lc.iter().copied().collect();
+    let row: BTreeMap<usize, F> = row.iter().map(|(coeff, wire)| (*wire, *coeff)).collect();
+    lc.keys()
+        .chain(row.keys())
+        .filter(|wire| lc.get(wire) != row.get(wire))
+        .min()
+        .copied()
+}
+
+fn to_matrix<'a, F: PrimeField>(
+    lcs: impl Iterator<Item = &'a ConstraintVec<F>>,
+) -> Vec<Vec<(F, usize)>> {
+    lcs.map(|lc| lc.iter().map(|(index, coeff)| (*coeff, *index)).collect())
+        .collect()
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for CircomCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        synthesize(
+            cs,
+            &self.r1cs,
+            self.witness.as_deref(),
+            self.public_inputs.as_deref(),
+            self.r1cs.constraints.iter().map(Ok),
+        )
+    }
+}
+
+/// Allocates the circuit's variables and enforces the constraints one at a time, as
+/// they are yielded, so that they can be streamed from the R1CS file. Only the sizes
+/// and the wire mapping of `r1cs` are used.
+pub(super) fn synthesize<F: PrimeField, C: Borrow<Constraints<F>>>(
+    cs: ConstraintSystemRef<F>,
+    r1cs: &R1CS<F>,
+    witness: Option<&[F]>,
+    public_inputs: Option<&[F]>,
+    constraints: impl IntoIterator<Item = Result<C, SynthesisError>>,
+) -> Result<(), SynthesisError> {
+    let wire_mapping = &r1cs.wire_mapping;
+
+    // Start from 1 because Arkworks implicitly allocates One for the first input
+    for i in 1..r1cs.num_inputs {
+        cs.new_input_variable(|| {
+            Ok(match (witness, public_inputs) {
+                (None, None) => F::from(1u32),
+                (None, Some(inputs)) => {
+                    *inputs.get(i - 1).ok_or(SynthesisError::AssignmentMissing)?
+                }
+                (Some(w), _) => match wire_mapping {
+                    Some(m) => w[m[i]],
+                    None => w[i],
+                },
+            })
+        })?;
+    }
+
+    // the constraints refer to the private signals, so they are allocated even
+    // without a witness, with placeholder values
+    for i in 0..r1cs.num_aux {
+        cs.new_witness_variable(|| {
+            Ok(match witness {
+                None => F::from(1u32),
+                Some(w) => match wire_mapping {
+                    Some(m) => w[m[i + r1cs.num_inputs]],
+                    None => w[i + r1cs.num_inputs],
+                },
+            })
+        })?;
+    }
+
+    let make_index = |index| {
+        if index < r1cs.num_inputs {
+            Variable::Instance(index)
+        } else {
+            Variable::Witness(index - r1cs.num_inputs)
+        }
+    };
+    let make_lc = |lc_data: &[(usize, F)]| {
+        lc_data.iter().fold(
+            LinearCombination::<F>::zero(),
+            |lc: LinearCombination<F>, (index, coeff)| lc + (*coeff, make_index(*index)),
+        )
+    };
+
+    for constraint in constraints {
+        let constraint = constraint?;
+        let (a, b, c) = constraint.borrow();
+        cs.enforce_constraint(make_lc(a), make_lc(b), make_lc(c))?;
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -88,6 +338,7 @@ mod tests {
     use crate::{CircomBuilder, CircomConfig};
     use ark_bn254::Fr;
     use ark_relations::r1cs::ConstraintSystem;
+    use num_bigint::{BigInt, BigUint};
 
     #[test]
     fn satisfied() {
@@ -105,4 +356,663 @@ mod tests {
         circom.generate_constraints(cs.clone()).unwrap();
         assert!(cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    fn overwrite_inputs() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 2);
+        builder.set_input("a", 3);
+        builder.set_input("b", 11);
+        assert_eq!(builder.inputs["a"], vec![BigInt::from(3)]);
+
+        let circom = builder.build().unwrap();
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(33u32)]);
+
+        builder.push_input_array("c", [1, 2, 3]).unwrap();
+        builder.push_input_array("c", [4, 5, 6]).unwrap();
+        let c: Vec<BigInt> = (4..=6).map(BigInt::from).collect();
+        assert_eq!(builder.inputs["c"], c);
+        assert!(builder.push_input_array("c", [7, 8]).is_err());
+        assert_eq!(builder.inputs["c"], c);
+    }
+
+    #[cfg(feature = "circom-2")]
+    fn sized_input_config() -> CircomConfig<Fr> {
+        // a module with an `in[2]` input signal, and mycircuit's 4 wires
+        let (msb, lsb) = crate::signal_hash("in");
+        let wat = format!(
+            r#"(module
+            (func (export "getVersion") (result i32) i32.const 2)
+            (func (export "getFieldNumLen32") (result i32) i32.const 1)
+            (func (export "getRawPrime"))
+            (func (export "readSharedRWMemory") (param i32) (result i32) i32.const 7)
+            (func (export "writeSharedRWMemory") (param i32 i32))
+            (func (export "init") (param i32))
+            (func (export "getInputSignalSize") (param i32 i32) (result i32)
+                (if (i32.ne (local.get 0) (i32.const {}))
+                    (then unreachable))
+                (if (i32.ne (local.get 1) (i32.const {}))
+                    (then unreachable))
+                i32.const 2)
+            (func (export "setInputSignal") (param i32 i32 i32))
+            (func (export "getWitness") (param i32))
+            (func (export "getWitnessSize") (result i32) i32.const 4))"#,
+            msb as i32, lsb as i32
+        );
+        let r1cs = std::fs::read("./test-vectors/mycircuit.r1cs").unwrap();
+        CircomConfig::<Fr>::from_bytes(wat.as_bytes(), &r1cs).unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn input_array_size() {
+        let mut builder = CircomBuilder::new(sized_input_config());
+
+        // the first elements set are checked against the circuit too
+        let err = builder.push_input_array("in", [1, 2, 3]).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::InputLengthMismatch { name, expected: 2, actual: 3 } if name == "in"
+            ),
+            "{}",
+            err
+        );
+        assert!(builder.inputs.is_empty());
+        builder.push_input_array("in", [1, 2]).unwrap();
+
+        let err = builder.push_input_array("out", [1]).unwrap_err();
+        assert!(
+            matches!(&err, Error::UnknownSignal { name, .. } if name == "out"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn sanity_check() {
+        let mut paths = vec![("mycircuit.wasm", "mycircuit.r1cs")];
+        #[cfg(feature = "circom-2")]
+        paths.push(("circom2_multiplier2.wasm", "circom2_multiplier2.r1cs"));
+
+        for (wasm, r1cs) in paths {
+            let mut cfg = CircomConfig::<Fr>::new(
+                format!("./test-vectors/{}", wasm),
+                format!("./test-vectors/{}", r1cs),
+            )
+            .unwrap();
+            assert!(cfg.sanity_check);
+
+            let mut witnesses = vec![];
+            for sanity_check in [true, false] {
+                cfg.sanity_check = sanity_check;
+                let mut builder = CircomBuilder::new(cfg.clone());
+                builder.push_input("a", 3);
+                builder.push_input("b", 11);
+                witnesses.push(builder.build().unwrap().witness);
+            }
+            assert_eq!(witnesses[0], witnesses[1]);
+        }
+    }
+
+    #[test]
+    fn public_inputs_only() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let builder = CircomBuilder::new(cfg);
+        assert!(builder.setup_with_public_inputs(vec![]).is_err());
+
+        let circom = builder
+            .setup_with_public_inputs(vec![Fr::from(33u32)])
+            .unwrap();
+        assert!(circom.witness.is_none());
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(33u32)]);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circom.generate_constraints(cs.clone()).unwrap();
+        let cs = cs.borrow().unwrap();
+        assert_eq!(cs.instance_assignment, [Fr::from(1u32), Fr::from(33u32)]);
+        assert_eq!(cs.num_witness_variables, 2);
+        assert_eq!(cs.num_constraints, 1);
+    }
+
+    #[test]
+    fn reset_inputs() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        for (a, b) in [(3u32, 11u32), (5, 7)] {
+            builder.reset_inputs();
+            builder.push_input("a", a);
+            builder.push_input("b", b);
+            assert_eq!(builder.inputs.len(), 2);
+
+            let circom = builder.build().unwrap();
+            assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(a * b)]);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circom.generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        builder.reset_inputs();
+        assert!(builder.inputs.is_empty());
+    }
+
+    #[test]
+    fn public_inputs() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        assert_eq!(builder.setup().get_public_inputs(), None);
+
+        let circom = builder.build().unwrap();
+        let inputs = circom.get_public_inputs().unwrap();
+        assert_eq!(inputs, vec![Fr::from(33u32)]);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circom.generate_constraints(cs.clone()).unwrap();
+        let instance = cs.borrow().unwrap().instance_assignment.clone();
+        assert_eq!(instance[1..], inputs[..]);
+    }
+
+    #[test]
+    fn witness_satisfaction() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+
+        assert!(matches!(
+            builder.setup().check_witness_satisfaction(),
+            Err(crate::Error::Synthesis(SynthesisError::AssignmentMissing))
+        ));
+
+        let mut circom = builder.build().unwrap();
+        circom.check_witness_satisfaction().unwrap();
+
+        // tamper with the output c = a * b
+        circom.witness.as_mut().unwrap()[1] += Fr::from(1u32);
+        assert!(matches!(
+            circom.check_witness_satisfaction(),
+            Err(crate::Error::UnsatisfiedConstraint(0))
+        ));
+
+        // a truncated witness is an error rather than a panic
+        circom.witness.as_mut().unwrap().truncate(2);
+        assert!(matches!(
+            circom.check_witness_satisfaction(),
+            Err(crate::Error::Synthesis(SynthesisError::AssignmentMissing))
+        ));
+    }
+
+    #[test]
+    fn proving_key_mismatch() {
+        let mut file = std::fs::File::open("./test-vectors/test.zkey").unwrap();
+        let (mut pk, _) = crate::read_zkey::<ark_bn254::Bn254, _>(&mut file).unwrap();
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        let circom = builder.build().unwrap();
+        circom.check_proving_key(&pk).unwrap();
+
+        // as if the zkey was generated from a less optimized R1CS with an extra wire
+        pk.a_query.push(pk.a_query[0]);
+        let err = circom.prove_with_seed(&pk, 0).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                crate::Error::ProvingKeyMismatch {
+                    pk_wires: 5,
+                    wires: 4,
+                    ..
+                }
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn zkey_matrices_mismatch() {
+        let mut file = std::fs::File::open("./test-vectors/test.zkey").unwrap();
+        let (_, matrices) = crate::read_zkey::<ark_bn254::Bn254, _>(&mut file).unwrap();
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let circom = CircomBuilder::new(cfg).setup();
+        circom.check_zkey_matrices(&matrices).unwrap();
+
+        // as if the zkey's constraint was on other wires, with the same counts
+        let mut other = matrices.clone();
+        let (coeff, wire) = other.b[0][0];
+        other.b[0][0] = (coeff, wire + 1);
+        let err = circom.check_zkey_matrices(&other).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                crate::Error::ConstraintMismatch {
+                    matrix: 'B',
+                    constraint: 0,
+                    wire: Some(w),
+                } if w == wire
+            ),
+            "{}",
+            err
+        );
+
+        let mut other = matrices.clone();
+        other.a[0][0].0 += Fr::from(1u32);
+        let err = circom.check_zkey_matrices(&other).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                crate::Error::ConstraintMismatch {
+                    matrix: 'A',
+                    constraint: 0,
+                    wire: Some(_),
+                }
+            ),
+            "{}",
+            err
+        );
+
+        let mut other = matrices;
+        other.a.push(vec![]);
+        let err = circom.check_zkey_matrices(&other).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                crate::Error::ConstraintMismatch {
+                    matrix: 'A',
+                    constraint: 1,
+                    wire: None,
+                }
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn full_assignment() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        assert_eq!(builder.setup().full_assignment(), None);
+
+        let circom = builder.build().unwrap();
+        let z = circom.full_assignment().unwrap();
+        assert_eq!(z.len(), circom.r1cs.num_variables);
+        assert_eq!(z[0], Fr::from(1u32));
+        assert_eq!(
+            z[1..circom.r1cs.num_inputs],
+            circom.get_public_inputs().unwrap()
+        );
+
+        let matrices = circom.matrices();
+        let eval = |lc: &[(Fr, usize)]| lc.iter().map(|(coeff, i)| *coeff * z[*i]).sum::<Fr>();
+        for i in 0..matrices.num_constraints {
+            let (a, b, c) = (&matrices.a[i], &matrices.b[i], &matrices.c[i]);
+            assert_eq!(eval(a) * eval(b), eval(c), "constraint {}", i);
+        }
+    }
+
+    #[test]
+    fn matrices() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let circom = CircomBuilder::new(cfg).setup();
+        assert_eq!(circom.num_outputs(), 1);
+        assert_eq!(circom.num_public_inputs(), 0);
+        assert_eq!(circom.num_private_inputs(), 2);
+        let matrices = circom.matrices();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circom.generate_constraints(cs.clone()).unwrap();
+        cs.finalize();
+        let expected = cs.to_matrices().unwrap();
+
+        assert_eq!(matrices.num_instance_variables, 2);
+        assert_eq!(
+            matrices.num_instance_variables,
+            expected.num_instance_variables
+        );
+        assert_eq!(
+            matrices.num_witness_variables,
+            expected.num_witness_variables
+        );
+        assert_eq!(matrices.num_constraints, expected.num_constraints);
+        assert_eq!(matrices.a_num_non_zero, expected.a_num_non_zero);
+        assert_eq!(matrices.b_num_non_zero, expected.b_num_non_zero);
+        assert_eq!(matrices.c_num_non_zero, expected.c_num_non_zero);
+        assert_eq!(matrices.a, expected.a);
+        assert_eq!(matrices.b, expected.b);
+        assert_eq!(matrices.c, expected.c);
+    }
+
+    #[test]
+    fn satisfied_json() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder
+            .push_inputs_json(&serde_json::json!({ "a": "3", "b": 11 }))
+            .unwrap();
+
+        let circom = builder.build().unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circom.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn nested_json_inputs() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        let inputs = serde_json::json!({
+            "a": 1,
+            "b": ["-2", "21888242871839275222246405745257275088548364400416034343698204186575808495616"],
+            "c": [[1, 2], [3, "4"]],
+        });
+        builder.push_inputs_json(&inputs).unwrap();
+
+        assert_eq!(builder.inputs["a"], vec![BigInt::from(1)]);
+        assert_eq!(
+            builder.inputs["b"],
+            vec![
+                BigInt::from(-2),
+                "21888242871839275222246405745257275088548364400416034343698204186575808495616"
+                    .parse()
+                    .unwrap()
+            ]
+        );
+        let c: Vec<BigInt> = (1..=4).map(BigInt::from).collect();
+        assert_eq!(builder.inputs["c"], c);
+
+        assert!(builder
+            .push_inputs_json(&serde_json::json!({ "d": [1.5] }))
+            .is_err());
+        assert!(builder
+            .push_inputs_json(&serde_json::json!({ "d": "abc" }))
+            .is_err());
+        assert!(builder.push_inputs_json(&serde_json::json!([1])).is_err());
+    }
+
+    #[test]
+    fn string_inputs() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input_str("a", "0x03").unwrap();
+        builder.push_input_str("b", "11").unwrap();
+        builder.push_input_str("c", "0x1a2B").unwrap();
+        builder.push_input_str("c", "12345678901234567890").unwrap();
+        builder.push_input_str("c", "-0x10").unwrap();
+        assert_eq!(
+            builder.inputs["c"],
+            vec![
+                BigInt::from(0x1a2b),
+                BigInt::from(12345678901234567890u64),
+                BigInt::from(-16)
+            ]
+        );
+        for invalid in ["", "0x", "0xg1", "12a", "1.5", "--1", "+1", "1_0"] {
+            assert!(builder.push_input_str("c", invalid).is_err(), "{}", invalid);
+        }
+        builder.inputs.remove("c");
+
+        let circom = builder.build().unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circom.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn integer_inputs() {
+        use num_bigint::BigUint;
+
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let output = |push: &dyn Fn(&mut CircomBuilder<Fr>)| {
+            let mut builder = CircomBuilder::new(cfg.clone());
+            builder.push_input("a", 3u8);
+            push(&mut builder);
+            let circom = builder.build().unwrap();
+            circom.check_witness_satisfaction().unwrap();
+            circom.get_public_inputs().unwrap()[0]
+        };
+
+        let expected = Fr::from(3 * 11);
+        assert_eq!(output(&|b| b.push_input("b", 11u64)), expected);
+        assert_eq!(output(&|b| b.push_input("b", 11i64)), expected);
+        assert_eq!(output(&|b| b.push_input("b", 11u128)), expected);
+        assert_eq!(
+            output(&|b| b.push_input("b", BigUint::from(11u32))),
+            expected
+        );
+        assert_eq!(output(&|b| b.push_input("b", "11")), expected);
+        assert_eq!(output(&|b| b.push_input("b", "11".to_string())), expected);
+        assert_eq!(output(&|b| b.push_input_str("b", "11").unwrap()), expected);
+
+        // negative values wrap around the prime
+        assert_eq!(output(&|b| b.push_input("b", -11i64)), -expected);
+        assert_eq!(output(&|b| b.push_input("b", "-11")), -expected);
+        assert_eq!(
+            output(&|b| b.push_input("b", i64::MIN)),
+            Fr::from(3) * Fr::from(i64::MIN)
+        );
+        assert_eq!(
+            output(&|b| b.push_input("b", u128::MAX)),
+            Fr::from(3) * Fr::from(u128::MAX)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "eleven is not an integer")]
+    fn invalid_str_input() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        CircomBuilder::new(cfg).push_input("b", "eleven");
+    }
+
+    #[test]
+    fn validate_inputs() {
+        let mut cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        cfg.symbols = Some(crate::SymbolTable::from_file("./test-vectors/mycircuit.sym").unwrap());
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        builder.validate_inputs().unwrap();
+
+        // missing input
+        builder.reset_inputs();
+        builder.push_input("b", 11);
+        let err = builder.validate_inputs().unwrap_err();
+        assert_eq!(err.missing, ["a"]);
+        assert!(err.extra.is_empty());
+
+        // extra inputs, along with a missing one
+        builder.push_input("c", 33);
+        builder.push_input("A", 3);
+        let err = builder.validate_inputs().unwrap_err();
+        assert_eq!(err.missing, ["a"]);
+        assert_eq!(err.extra, ["A", "c"]);
+
+        // too many values
+        builder.reset_inputs();
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        builder.push_input("b", 12);
+        let err = builder.validate_inputs().unwrap_err();
+        assert_eq!(err.lengths, [("b".to_string(), 1, 2)]);
+        assert_eq!(
+            err.to_string(),
+            "missing inputs [], unexpected inputs [], inputs of the wrong length [b: 2 instead of 1]"
+        );
+        assert!(builder.push_input_array("b", [11, 12]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn validate_inputs_without_symbols() {
+        let mut builder = CircomBuilder::new(sized_input_config());
+        builder.push_input("in", 1);
+        builder.push_input("out", 2);
+        let err = builder.validate_inputs().unwrap_err();
+        assert!(err.missing.is_empty());
+        assert_eq!(err.extra, ["out"]);
+        assert_eq!(err.lengths, [("in".to_string(), 2, 1)]);
+
+        builder.reset_inputs();
+        builder.push_input_array("in", [1, 2]).unwrap();
+        builder.validate_inputs().unwrap();
+    }
+
+    #[test]
+    fn mismatched_artifacts() {
+        let err = CircomConfig::<Fr>::new(
+            "./test-vectors/circom2_multiplier2.wasm",
+            "./test-vectors/complex-circuit/complex-circuit-10000-10000.r1cs",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::Error::WitnessSizeMismatch {
+                circom_version: 2,
+                witness_size: 4,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn satisfied_from_bytes() {
+        let cfg = CircomConfig::<Fr>::from_bytes(
+            include_bytes!("../../test-vectors/mycircuit.wasm"),
+            include_bytes!("../../test-vectors/mycircuit.r1cs"),
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+
+        let circom = builder.build().unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circom.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn satisfied_with_engine() {
+        let engine = wasmer::Engine::default();
+        let cfg = CircomConfig::<Fr>::from_bytes_with_engine(
+            include_bytes!("../../test-vectors/mycircuit.wasm"),
+            include_bytes!("../../test-vectors/mycircuit.r1cs"),
+            &engine,
+        )
+        .unwrap();
+        // the engine outlives the caller's handle
+        drop(engine);
+
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        let circom = builder.build().unwrap();
+        assert_eq!(circom.get_public_inputs().unwrap(), vec![Fr::from(33u32)]);
+    }
+
+    #[test]
+    fn from_wtns() {
+        let r1cs = std::fs::read("./test-vectors/mycircuit.r1cs").unwrap();
+        let r1cs: R1CS<Fr> = crate::circom::R1CSFile::new(std::io::Cursor::new(r1cs))
+            .unwrap()
+            .into();
+        let mut file = std::fs::File::open("./test-vectors/test.zkey").unwrap();
+        let (pk, _) = crate::read_zkey::<ark_bn254::Bn254, _>(&mut file).unwrap();
+
+        // as computed by another tool, for a = 3 and b = 11
+        let prime = BigInt::from(BigUint::from(Fr::MODULUS));
+        let witness = [1, 33, 3, 11].map(BigInt::from);
+        let mut wtns = vec![];
+        crate::write_wtns(&witness, &prime, &mut wtns).unwrap();
+
+        let circom = CircomCircuit::from_wtns(r1cs.clone(), &mut &wtns[..]).unwrap();
+        circom.check_witness_satisfaction().unwrap();
+        let inputs = circom.get_public_inputs().unwrap();
+        assert_eq!(inputs, vec![Fr::from(33u32)]);
+        let proof = circom.prove_with_seed(&pk, 0).unwrap();
+        assert!(crate::verify(&pk.vk, &proof, &inputs).unwrap());
+
+        // the witness of another circuit
+        let mut wtns = vec![];
+        crate::write_wtns(&witness[..3], &prime, &mut wtns).unwrap();
+        let err = CircomCircuit::from_wtns(r1cs.clone(), &mut &wtns[..]).unwrap_err();
+        assert!(matches!(err, crate::Error::InvalidInput(_)), "{}", err);
+
+        // the witness of another curve
+        let mut wtns = vec![];
+        let prime = BigInt::from(BigUint::from(ark_bls12_381::Fr::MODULUS));
+        crate::write_wtns(&witness, &prime, &mut wtns).unwrap();
+        let err = CircomCircuit::from_wtns(r1cs, &mut &wtns[..]).unwrap_err();
+        assert!(
+            matches!(err, crate::Error::WitnessFieldMismatch { .. }),
+            "{}",
+            err
+        );
+    }
 }
@@ -0,0 +1,150 @@
+//! Rendering of constraints in terms of the circuit's named signals, for finding out
+//! why a witness doesn't satisfy them
+use ark_ff::PrimeField;
+use std::fmt::Write;
+
+use super::{ConstraintVec, SymbolTable, R1CS};
+
+impl<F: PrimeField> R1CS<F> {
+    /// Renders the constraint at `index` as an equation `A * B = C` between linear
+    /// combinations of the named signals, followed by the signals' values in the witness
+    /// and the evaluated equation, e.g.
+    ///
+    /// ```text
+    /// constraint 0: (-main.a) * (main.b) = (-main.c)
+    ///   main.a = 3, main.b = 11, main.c = 34
+    ///   -3 * 11 = -33 != -34
+    /// ```
+    ///
+    /// The witness is indexed by wire, as computed by the [`WitnessCalculator`], and so
+    /// are the signals of the symbol table. Signals without a name are rendered as
+    /// `w<wire index>`.
+    ///
+    /// [`WitnessCalculator`]: crate::WitnessCalculator
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no constraint at `index`, or if the witness is too short.
+    pub fn debug_constraint(&self, index: usize, symbols: &SymbolTable, witness: &[F]) -> String {
+        let (a, b, c) = &self.constraints[index];
+        let name = |wire: usize| {
+            symbols
+                .names(wire)
+                .first()
+                .cloned()
+                .unwrap_or_else(|| format!("w{}", wire))
+        };
+        let eval = |lc: &ConstraintVec<F>| -> F {
+            lc.iter().map(|(wire, coeff)| *coeff * witness[*wire]).sum()
+        };
+
+        let mut out = format!(
+            "constraint {}: ({}) * ({}) = ({})\n ",
+            index,
+            render_lc(a, &name),
+            render_lc(b, &name),
+            render_lc(c, &name)
+        );
+
+        // the values of the signals, in order of appearance, skipping the constant one
+        let mut wires: Vec<usize> = vec![];
+        for (wire, _) in a.iter().chain(b).chain(c) {
+            if *wire != 0 && !wires.contains(wire) {
+                wires.push(*wire);
+            }
+        }
+        for (i, wire) in wires.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            let value = signed(witness[*wire]);
+            write!(out, "{} {} = {}", separator, name(*wire), value).unwrap();
+        }
+
+        let (a, b, c) = (eval(a), eval(b), eval(c));
+        let relation = if a * b == c { "=" } else { "!=" };
+        write!(
+            out,
+            "\n  {} * {} = {} {} {}",
+            signed(a),
+            signed(b),
+            signed(a * b),
+            relation,
+            signed(c)
+        )
+        .unwrap();
+        out
+    }
+}
+
+// Renders a linear combination as e.g. `2*main.a - main.b + 1`
+fn render_lc<F: PrimeField>(lc: &ConstraintVec<F>, name: &impl Fn(usize) -> String) -> String {
+    if lc.is_empty() {
+        return "0".to_string();
+    }
+
+    let mut out = String::new();
+    for (i, (wire, coeff)) in lc.iter().enumerate() {
+        let coeff = signed(*coeff);
+        let (negative, coeff) = match coeff.strip_prefix('-') {
+            Some(abs) => (true, abs.to_string()),
+            None => (false, coeff),
+        };
+        let term = match (*wire, coeff.as_str()) {
+            (0, _) => coeff,
+            (wire, "1") => name(wire),
+            (wire, _) => format!("{}*{}", coeff, name(wire)),
+        };
+        match (i, negative) {
+            (0, false) => out.push_str(&term),
+            (0, true) => write!(out, "-{}", term).unwrap(),
+            (_, false) => write!(out, " + {}", term).unwrap(),
+            (_, true) => write!(out, " - {}", term).unwrap(),
+        }
+    }
+    out
+}
+
+// Renders field elements in the upper half of the field as negative numbers, as
+// constraints usually contain small negative coefficients such as `p - 1`
+fn signed<F: PrimeField>(fe: F) -> String {
+    let neg = -fe;
+    if neg.into_bigint() < fe.into_bigint() {
+        format!("-{}", neg)
+    } else {
+        fe.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::R1CSFile;
+    use ark_bn254::Fr;
+    use std::fs::File;
+
+    #[test]
+    fn debug_constraint() {
+        let file = File::open("./test-vectors/mycircuit.r1cs").unwrap();
+        let r1cs: R1CS<Fr> = R1CSFile::new(file).unwrap().into();
+        let symbols = SymbolTable::from_file("./test-vectors/mycircuit.sym").unwrap();
+
+        let mut witness = [1u32, 33, 3, 11].map(Fr::from);
+        assert_eq!(
+            r1cs.debug_constraint(0, &symbols, &witness),
+            "constraint 0: (-main.a) * (main.b) = (-main.c)\n  \
+             main.a = 3, main.b = 11, main.c = 33\n  \
+             -3 * 11 = -33 = -33"
+        );
+
+        witness[1] = Fr::from(34u32);
+        let out = r1cs.debug_constraint(0, &symbols, &witness);
+        assert!(out.ends_with("-3 * 11 = -33 != -34"), "{}", out);
+
+        // without names
+        let out = r1cs.debug_constraint(0, &SymbolTable::default(), &witness);
+        assert!(
+            out.starts_with("constraint 0: (-w2) * (w3) = (-w1)"),
+            "{}",
+            out
+        );
+    }
+}
@@ -1,14 +1,27 @@
 pub mod r1cs_reader;
-pub use r1cs_reader::{R1CSFile, R1CS};
+pub use r1cs_reader::{ConstraintsIter, R1CSFile, R1CSReader, R1CS};
 
 mod circuit;
 pub use circuit::CircomCircuit;
 
+mod streaming;
+pub use streaming::StreamingCircomCircuit;
+
 mod builder;
-pub use builder::{CircomBuilder, CircomConfig};
+#[cfg(feature = "ethereum")]
+pub(crate) use builder::parse_bigint;
+pub use builder::{CircomBuilder, CircomConfig, InputValidationError, IntoCircomInput};
 
 mod qap;
 pub use qap::CircomReduction;
 
+mod symbols;
+pub use symbols::{map_public_outputs, InputSignal, SymbolTable};
+
+mod debug;
+
+mod stats;
+pub use stats::CircuitStats;
+
 pub type Constraints<F> = (ConstraintVec<F>, ConstraintVec<F>, ConstraintVec<F>);
 pub type ConstraintVec<F> = Vec<(usize, F)>;
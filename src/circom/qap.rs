@@ -4,11 +4,20 @@ use ark_poly::EvaluationDomain;
 use ark_relations::r1cs::{ConstraintMatrices, ConstraintSystemRef, SynthesisError};
 use ark_std::{cfg_into_iter, cfg_iter, cfg_iter_mut, vec};
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 /// Implements the witness map used by snarkjs. The arkworks witness map calculates the
 /// coefficients of H through computing (AB-C)/Z in the evaluation domain and going back to the
 /// coefficients domain. snarkjs instead precomputes the Lagrange form of the powers of tau bases
 /// in a domain twice as large and the witness map is computed as the odd coefficients of (AB-C)
 /// in that domain. This serves as HZ when computing the C proof element.
+///
+/// The H query of a snarkjs zkey holds these Lagrange bases, so proofs for keys read with
+/// [`read_zkey`](crate::read_zkey) must be created with this reduction, e.g. through
+/// [`CircomCircuit::prove`](crate::CircomCircuit::prove), to verify with the key's
+/// verifier. Arkworks' default `LibsnarkReduction` produces proofs which don't verify.
+/// Keys generated in Rust for this reduction aren't compatible with the default one either.
 pub struct CircomReduction;
 
 impl R1CSToQAP for CircomReduction {
@@ -8,7 +8,7 @@ use std::io::{Error, ErrorKind};
 use ark_serialize::{SerializationError, SerializationError::IoError};
 use ark_std::io::{Read, Seek, SeekFrom};
 
-use std::collections::HashMap;
+use std::{collections::HashMap, marker::PhantomData};
 
 type IoResult<T> = Result<T, SerializationError>;
 
@@ -19,10 +19,33 @@ pub struct R1CS<F> {
     pub num_inputs: usize,
     pub num_aux: usize,
     pub num_variables: usize,
+    /// Number of public outputs, which come first among the public signals
+    pub(crate) num_outputs: usize,
+    /// Number of public inputs, which follow the outputs among the public signals
+    pub(crate) num_public_inputs: usize,
+    /// Number of private inputs, which follow the public signals among the wires
+    pub(crate) num_private_inputs: usize,
     pub constraints: Vec<Constraints<F>>,
     pub wire_mapping: Option<Vec<usize>>,
 }
 
+impl<F> R1CS<F> {
+    /// Returns the number of public outputs
+    pub fn num_outputs(&self) -> usize {
+        self.num_outputs
+    }
+
+    /// Returns the number of public inputs
+    pub fn num_public_inputs(&self) -> usize {
+        self.num_public_inputs
+    }
+
+    /// Returns the number of private inputs
+    pub fn num_private_inputs(&self) -> usize {
+        self.num_private_inputs
+    }
+}
+
 impl<F: PrimeField> From<R1CSFile<F>> for R1CS<F> {
     fn from(file: R1CSFile<F>) -> Self {
         let num_inputs = (1 + file.header.n_pub_in + file.header.n_pub_out) as usize;
@@ -32,6 +55,9 @@ impl<F: PrimeField> From<R1CSFile<F>> for R1CS<F> {
             num_aux,
             num_inputs,
             num_variables,
+            num_outputs: file.header.n_pub_out as usize,
+            num_public_inputs: file.header.n_pub_in as usize,
+            num_private_inputs: file.header.n_prv_in as usize,
             constraints: file.constraints,
             wire_mapping: Some(file.wire_mapping.iter().map(|e| *e as usize).collect()),
         }
@@ -51,7 +77,39 @@ impl<F: PrimeField> R1CSFile<F> {
     /// ```rust,ignore
     /// let reader = BufReader::new(Cursor::new(&data[..]));
     /// ```
-    pub fn new<R: Read + Seek>(mut reader: R) -> IoResult<R1CSFile<F>> {
+    pub fn new<R: Read + Seek>(reader: R) -> IoResult<R1CSFile<F>> {
+        let mut reader = R1CSReader::new(reader)?;
+        let constraints = reader.constraints_iter()?.collect::<IoResult<_>>()?;
+        let wire_mapping = reader.wire_mapping()?;
+
+        Ok(R1CSFile {
+            version: reader.version,
+            header: reader.header,
+            constraints,
+            wire_mapping,
+        })
+    }
+}
+
+/// Reads an R1CS file lazily. Only the header is read up front, so the constraints
+/// of large circuits can be streamed from the file one at a time with
+/// [`R1CSReader::constraints_iter`] instead of being loaded all at once.
+pub struct R1CSReader<R, F> {
+    reader: R,
+    pub version: u32,
+    pub header: Header,
+    // section type -> file offset
+    sec_offsets: HashMap<u32, u64>,
+    sec_sizes: HashMap<u32, u64>,
+    _field: PhantomData<F>,
+}
+
+const HEADER_TYPE: u32 = 1;
+const CONSTRAINT_TYPE: u32 = 2;
+const WIRE2LABEL_TYPE: u32 = 3;
+
+impl<R: Read + Seek, F: PrimeField> R1CSReader<R, F> {
+    pub fn new(mut reader: R) -> IoResult<Self> {
         let mut magic = [0u8; 4];
         reader.read_exact(&mut magic)?;
         if magic != [0x72, 0x31, 0x63, 0x73] {
@@ -86,11 +144,7 @@ impl<F: PrimeField> R1CSFile<F> {
             reader.seek(SeekFrom::Current(sec_size as i64))?;
         }
 
-        let header_type = 1;
-        let constraint_type = 2;
-        let wire2label_type = 3;
-
-        let header_offset = sec_offsets.get(&header_type).ok_or_else(|| {
+        let header_offset = sec_offsets.get(&HEADER_TYPE).ok_or_else(|| {
             Error::new(
                 ErrorKind::InvalidData,
                 "No section offset for header type found",
@@ -99,7 +153,7 @@ impl<F: PrimeField> R1CSFile<F> {
 
         reader.seek(SeekFrom::Start(*header_offset?))?;
 
-        let header_size = sec_sizes.get(&header_type).ok_or_else(|| {
+        let header_size = sec_sizes.get(&HEADER_TYPE).ok_or_else(|| {
             Error::new(
                 ErrorKind::InvalidData,
                 "No section size for header type found",
@@ -108,44 +162,88 @@ impl<F: PrimeField> R1CSFile<F> {
 
         let header = Header::new(&mut reader, *header_size?)?;
 
-        let constraint_offset = sec_offsets.get(&constraint_type).ok_or_else(|| {
+        Ok(Self {
+            reader,
+            version,
+            header,
+            sec_offsets,
+            sec_sizes,
+            _field: PhantomData,
+        })
+    }
+
+    /// Returns an iterator reading the constraints from the file one at a time
+    pub fn constraints_iter(&mut self) -> IoResult<ConstraintsIter<'_, R, F>> {
+        let constraint_offset = self.sec_offsets.get(&CONSTRAINT_TYPE).ok_or_else(|| {
             Error::new(
                 ErrorKind::InvalidData,
                 "No section offset for constraint type found",
             )
         });
 
-        reader.seek(SeekFrom::Start(*constraint_offset?))?;
+        self.reader.seek(SeekFrom::Start(*constraint_offset?))?;
 
-        let constraints = read_constraints::<&mut R, F>(&mut reader, &header)?;
+        // todo check section size
+        Ok(ConstraintsIter {
+            reader: &mut self.reader,
+            remaining: self.header.n_constraints,
+            _field: PhantomData,
+        })
+    }
 
-        let wire2label_offset = sec_offsets.get(&wire2label_type).ok_or_else(|| {
+    /// Reads the mapping from the R1CS wires to the witness labels
+    pub fn wire_mapping(&mut self) -> IoResult<Vec<u64>> {
+        let wire2label_offset = self.sec_offsets.get(&WIRE2LABEL_TYPE).ok_or_else(|| {
             Error::new(
                 ErrorKind::InvalidData,
                 "No section offset for wire2label type found",
             )
         });
 
-        reader.seek(SeekFrom::Start(*wire2label_offset?))?;
+        self.reader.seek(SeekFrom::Start(*wire2label_offset?))?;
 
-        let wire2label_size = sec_sizes.get(&wire2label_type).ok_or_else(|| {
+        let wire2label_size = self.sec_sizes.get(&WIRE2LABEL_TYPE).ok_or_else(|| {
             Error::new(
                 ErrorKind::InvalidData,
                 "No section size for wire2label type found",
             )
         });
 
-        let wire_mapping = read_map(&mut reader, *wire2label_size?, &header)?;
+        read_map(&mut self.reader, *wire2label_size?, &self.header)
+    }
+}
 
-        Ok(R1CSFile {
-            version,
-            header,
-            constraints,
-            wire_mapping,
-        })
+/// Iterator over the constraints of an R1CS file, see [`R1CSReader::constraints_iter`]
+pub struct ConstraintsIter<'a, R, F> {
+    reader: &'a mut R,
+    remaining: u32,
+    _field: PhantomData<F>,
+}
+
+impl<R: Read, F: PrimeField> Iterator for ConstraintsIter<'_, R, F> {
+    type Item = IoResult<Constraints<F>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let constraint = read_constraint(&mut *self.reader);
+        // stop after an error, as the reader's position is no longer known
+        if constraint.is_err() {
+            self.remaining = 0;
+        }
+        Some(constraint)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
     }
 }
 
+impl<R: Read, F: PrimeField> ExactSizeIterator for ConstraintsIter<'_, R, F> {}
+
 pub struct Header {
     pub field_size: u32,
     pub prime_size: Vec<u8>,
@@ -212,20 +310,12 @@ fn read_constraint_vec<R: Read, F: PrimeField>(mut reader: R) -> IoResult<Constr
     Ok(vec)
 }
 
-fn read_constraints<R: Read, F: PrimeField>(
-    mut reader: R,
-    header: &Header,
-) -> IoResult<Vec<Constraints<F>>> {
-    // todo check section size
-    let mut vec = Vec::with_capacity(header.n_constraints as usize);
-    for _ in 0..header.n_constraints {
-        vec.push((
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-            read_constraint_vec::<&mut R, F>(&mut reader)?,
-        ));
-    }
-    Ok(vec)
+fn read_constraint<R: Read, F: PrimeField>(mut reader: R) -> IoResult<Constraints<F>> {
+    Ok((
+        read_constraint_vec::<&mut R, F>(&mut reader)?,
+        read_constraint_vec::<&mut R, F>(&mut reader)?,
+        read_constraint_vec::<&mut R, F>(&mut reader)?,
+    ))
 }
 
 fn read_map<R: Read>(mut reader: R, size: u64, header: &Header) -> IoResult<Vec<u64>> {
@@ -335,5 +425,56 @@ mod tests {
 
         assert_eq!(file.wire_mapping.len(), 7);
         assert_eq!(file.wire_mapping[1], 3);
+
+        let r1cs = R1CS::from(file);
+        assert_eq!(r1cs.num_outputs, 1);
+        assert_eq!(r1cs.num_public_inputs, 2);
+        assert_eq!(r1cs.num_private_inputs, 3);
+        assert_eq!(r1cs.num_inputs, 4);
+    }
+
+    #[test]
+    fn signal_counts() {
+        let data = std::fs::read("./test-vectors/mycircuit.r1cs").unwrap();
+        let r1cs = R1CS::from(R1CSFile::<Fr>::new(Cursor::new(&data[..])).unwrap());
+        // c is the output, a and b are private
+        assert_eq!(r1cs.num_outputs, 1);
+        assert_eq!(r1cs.num_public_inputs, 0);
+        assert_eq!(r1cs.num_private_inputs, 2);
+        assert_eq!(r1cs.num_inputs, 2);
+    }
+
+    #[test]
+    fn constraints_iter() {
+        let data = std::fs::read("./test-vectors/mycircuit.r1cs").unwrap();
+        let file = R1CSFile::<Fr>::new(Cursor::new(&data[..])).unwrap();
+
+        let mut reader = R1CSReader::<_, Fr>::new(Cursor::new(&data[..])).unwrap();
+        assert_eq!(reader.header.n_constraints, file.header.n_constraints);
+        let iter = reader.constraints_iter().unwrap();
+        assert_eq!(iter.len(), file.constraints.len());
+        let constraints = iter.collect::<IoResult<Vec<_>>>().unwrap();
+        assert_eq!(constraints, file.constraints);
+
+        // the iterator can be restarted, e.g. after reading the wire mapping
+        assert_eq!(reader.wire_mapping().unwrap(), file.wire_mapping);
+        assert_eq!(
+            reader.constraints_iter().unwrap().count(),
+            constraints.len()
+        );
+    }
+
+    #[test]
+    fn constraints_iter_invalid() {
+        let mut data = std::fs::read("./test-vectors/mycircuit.r1cs").unwrap();
+        let reader = R1CSReader::<_, Fr>::new(Cursor::new(&data[..])).unwrap();
+        let offset = reader.sec_offsets[&CONSTRAINT_TYPE] as usize;
+
+        // make the first linear combination run past the end of the file
+        data[offset..offset + 4].copy_from_slice(&1_000_000u32.to_le_bytes());
+        let mut reader = R1CSReader::<_, Fr>::new(Cursor::new(&data[..])).unwrap();
+        let mut iter = reader.constraints_iter().unwrap();
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
     }
 }
@@ -0,0 +1,102 @@
+//! Size statistics of a circuit, e.g. for tracking its growth in CI
+use ark_ff::PrimeField;
+use std::fmt;
+
+use super::{CircomCircuit, R1CS};
+
+/// Counts describing the size of a circuit, as returned by [`R1CS::stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CircuitStats {
+    pub num_constraints: usize,
+    pub num_outputs: usize,
+    pub num_public_inputs: usize,
+    pub num_private_inputs: usize,
+    /// Number of wires, including the constant one
+    pub num_variables: usize,
+    /// Number of non-zero coefficients in the A matrix
+    pub a_non_zero: usize,
+    /// Number of non-zero coefficients in the B matrix
+    pub b_non_zero: usize,
+    /// Number of non-zero coefficients in the C matrix
+    pub c_non_zero: usize,
+    /// Size of the evaluation domain used by the Groth16 prover, the next power of two
+    /// of the number of constraints and public signals
+    pub domain_size: usize,
+}
+
+impl<F: PrimeField> R1CS<F> {
+    /// Returns the size statistics of the circuit
+    pub fn stats(&self) -> CircuitStats {
+        let non_zero = |lc: &[(usize, F)]| lc.iter().filter(|(_, coeff)| !coeff.is_zero()).count();
+        let mut stats = CircuitStats {
+            num_constraints: self.constraints.len(),
+            num_outputs: self.num_outputs,
+            num_public_inputs: self.num_public_inputs,
+            num_private_inputs: self.num_private_inputs,
+            num_variables: self.num_variables,
+            // the prover adds a constraint for every public signal and the constant one
+            domain_size: (self.constraints.len() + self.num_inputs).next_power_of_two(),
+            ..Default::default()
+        };
+        for (a, b, c) in &self.constraints {
+            stats.a_non_zero += non_zero(a);
+            stats.b_non_zero += non_zero(b);
+            stats.c_non_zero += non_zero(c);
+        }
+        stats
+    }
+}
+
+impl<F: PrimeField> CircomCircuit<F> {
+    /// Returns the size statistics of the circuit, see [`R1CS::stats`]
+    pub fn stats(&self) -> CircuitStats {
+        self.r1cs.stats()
+    }
+}
+
+impl fmt::Display for CircuitStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "constraints: {}", self.num_constraints)?;
+        writeln!(f, "outputs: {}", self.num_outputs)?;
+        writeln!(f, "public inputs: {}", self.num_public_inputs)?;
+        writeln!(f, "private inputs: {}", self.num_private_inputs)?;
+        writeln!(f, "wires: {}", self.num_variables)?;
+        writeln!(
+            f,
+            "non-zero entries: A {}, B {}, C {}",
+            self.a_non_zero, self.b_non_zero, self.c_non_zero
+        )?;
+        write!(f, "domain size: {}", self.domain_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CircomConfig;
+    use ark_bn254::Fr;
+
+    #[test]
+    fn mycircuit() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let stats = cfg.r1cs.stats();
+        assert_eq!(
+            stats,
+            CircuitStats {
+                num_constraints: 1,
+                num_outputs: 1,
+                num_public_inputs: 0,
+                num_private_inputs: 2,
+                num_variables: 4,
+                a_non_zero: 1,
+                b_non_zero: 1,
+                c_non_zero: 1,
+                domain_size: 4,
+            }
+        );
+    }
+}
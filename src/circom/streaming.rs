@@ -0,0 +1,144 @@
+//! Synthesis of circuits too large to hold their R1CS in memory
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use std::io::{Read, Seek};
+
+use super::{circuit::synthesize, Constraints, R1CSReader, R1CS};
+
+/// Same as [`CircomCircuit`], with the constraints read from the R1CS file while they
+/// are enforced, so that only one constraint is held in memory at a time rather than
+/// the whole R1CS.
+///
+/// The witness must be indexed by wire, as computed by the
+/// [`WitnessCalculator`](crate::WitnessCalculator). As `SynthesisError` can't hold
+/// I/O errors, a constraint which fails to be read is reported as
+/// [`SynthesisError::Unsatisfiable`].
+///
+/// [`CircomCircuit`]: super::CircomCircuit
+pub struct StreamingCircomCircuit<R, F> {
+    pub reader: R1CSReader<R, F>,
+    pub witness: Option<Vec<F>>,
+    /// The public signals, without the leading constant one, as in
+    /// [`CircomCircuit::public_inputs`](super::CircomCircuit::public_inputs)
+    pub public_inputs: Option<Vec<F>>,
+}
+
+impl<R: Read + Seek, F: PrimeField> StreamingCircomCircuit<R, F> {
+    pub fn new(reader: R1CSReader<R, F>, witness: Option<Vec<F>>) -> Self {
+        Self {
+            reader,
+            witness,
+            public_inputs: None,
+        }
+    }
+}
+
+impl<R: Read + Seek, F: PrimeField> ConstraintSynthesizer<F> for StreamingCircomCircuit<R, F> {
+    fn generate_constraints(mut self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let header = &self.reader.header;
+        let num_inputs = (1 + header.n_pub_in + header.n_pub_out) as usize;
+        let num_variables = header.n_wires as usize;
+        // only the sizes are used, the witness is indexed by wire
+        let r1cs = R1CS::<F> {
+            num_inputs,
+            num_aux: num_variables - num_inputs,
+            num_variables,
+            num_outputs: header.n_pub_out as usize,
+            num_public_inputs: header.n_pub_in as usize,
+            num_private_inputs: header.n_prv_in as usize,
+            constraints: vec![],
+            wire_mapping: None,
+        };
+
+        let constraints = self
+            .reader
+            .constraints_iter()
+            .map_err(|_| SynthesisError::Unsatisfiable)?
+            .map(|constraint| constraint.map_err(|_| SynthesisError::Unsatisfiable));
+        synthesize::<F, Constraints<F>>(
+            cs,
+            &r1cs,
+            self.witness.as_deref(),
+            self.public_inputs.as_deref(),
+            constraints,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CircomBuilder, CircomConfig};
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use byteorder::{ByteOrder, LittleEndian};
+    use std::{fs::File, io::Cursor};
+
+    fn constraint_system(circuit: impl ConstraintSynthesizer<Fr>) -> ConstraintSystemRef<Fr> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.finalize();
+        cs
+    }
+
+    #[test]
+    fn same_as_eager() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        let circom = builder.build().unwrap();
+
+        let reader = R1CSReader::new(File::open("./test-vectors/mycircuit.r1cs").unwrap()).unwrap();
+        let streaming = StreamingCircomCircuit::new(reader, circom.witness.clone());
+
+        let (eager, streamed) = (constraint_system(circom), constraint_system(streaming));
+        assert!(streamed.is_satisfied().unwrap());
+        assert_eq!(streamed.to_matrices(), eager.to_matrices());
+        let (eager, streamed) = (eager.borrow().unwrap(), streamed.borrow().unwrap());
+        assert_eq!(streamed.instance_assignment, eager.instance_assignment);
+        assert_eq!(streamed.witness_assignment, eager.witness_assignment);
+    }
+
+    #[test]
+    fn same_as_eager_setup() {
+        let path = "./test-vectors/complex-circuit/complex-circuit-10000-10000.r1cs";
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/complex-circuit/complex-circuit-10000-10000.wasm",
+            path,
+        )
+        .unwrap();
+        let circom = CircomBuilder::new(cfg).setup();
+
+        let reader = R1CSReader::new(File::open(path).unwrap()).unwrap();
+        let streaming = StreamingCircomCircuit::new(reader, None);
+
+        let (eager, streamed) = (constraint_system(circom), constraint_system(streaming));
+        assert_eq!(streamed.num_constraints(), 10000);
+        assert_eq!(streamed.to_matrices(), eager.to_matrices());
+    }
+
+    #[test]
+    fn invalid_constraint() {
+        let mut data = std::fs::read("./test-vectors/mycircuit.r1cs").unwrap();
+        // find the constraints section after the magic, version and section count
+        let mut offset = 12;
+        while LittleEndian::read_u32(&data[offset..]) != 2 {
+            offset += 12 + LittleEndian::read_u64(&data[offset + 4..]) as usize;
+        }
+        // make the first linear combination run past the end of the file
+        data[offset + 12..offset + 16].copy_from_slice(&1_000_000u32.to_le_bytes());
+
+        let reader = R1CSReader::<_, Fr>::new(Cursor::new(&data[..])).unwrap();
+        let streaming = StreamingCircomCircuit::new(reader, None);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(matches!(
+            streaming.generate_constraints(cs),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+//! Circom symbol file reader
+//!
+//! Each line of a `.sym` file describes a signal as `label,witness,component,name`,
+//! where `witness` is the signal's index in the witness, or -1 if the signal was
+//! removed by the compiler's optimizations.
+use ark_ff::PrimeField;
+use ark_serialize::{SerializationError, SerializationError::IoError};
+use indexmap::IndexMap;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Error, ErrorKind, Read},
+    path::Path,
+};
+
+use super::R1CS;
+
+type IoResult<T> = Result<T, SerializationError>;
+
+/// Maps between the names of a circuit's signals and their indices in the witness.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    indices: HashMap<String, usize>,
+    // a witness entry can have several names when signals are aliased
+    names: HashMap<usize, Vec<String>>,
+}
+
+impl SymbolTable {
+    pub fn from_file(path: impl AsRef<Path>) -> IoResult<Self> {
+        let file = File::open(path)?;
+        Self::from_reader(file)
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> IoResult<Self> {
+        let mut table = Self::default();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || {
+                IoError(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid symbol on line {}: {}", i + 1, line),
+                ))
+            };
+            let mut fields = line.splitn(4, ',');
+            let witness = fields.nth(1).ok_or_else(invalid)?;
+            let name = fields.nth(1).ok_or_else(invalid)?;
+            let witness: i64 = witness.parse().map_err(|_| invalid())?;
+
+            // the signal isn't part of the witness
+            if witness < 0 {
+                continue;
+            }
+            let witness = witness as usize;
+            table.indices.insert(name.to_string(), witness);
+            table
+                .names
+                .entry(witness)
+                .or_default()
+                .push(name.to_string());
+        }
+        Ok(table)
+    }
+
+    /// Returns the witness index of the signal with the given name, e.g. `main.a`
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.indices.get(name).copied()
+    }
+
+    /// Returns the names of the signals at the given witness index
+    pub fn names(&self, index: usize) -> &[String] {
+        self.names
+            .get(&index)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the witness index of every named signal
+    pub fn indices(&self) -> &HashMap<String, usize> {
+        &self.indices
+    }
+}
+
+/// An input signal of the circuit, as returned by [`R1CS::input_signals`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputSignal {
+    /// The name passed to the witness calculator, e.g. `in` for `signal input in[2][3]`
+    pub name: String,
+    /// The size of each of the array's dimensions, e.g. `[2, 3]`, empty for scalars.
+    /// The witness calculator takes the values flattened in row-major order.
+    pub dimensions: Vec<usize>,
+}
+
+impl<F: PrimeField> R1CS<F> {
+    /// Returns the circuit's input signals with their array dimensions, e.g. to render
+    /// a form for the inputs of an arbitrary circuit. The public inputs come first, each
+    /// kind in the order of their wires.
+    ///
+    /// The R1CS tells which wires are inputs, and the symbol table their names, with
+    /// the dimensions given by the largest index of each.
+    pub fn input_signals(&self, symbols: &SymbolTable) -> Vec<InputSignal> {
+        let start = 1 + self.num_outputs;
+        let end = start + self.num_public_inputs + self.num_private_inputs;
+
+        let mut signals: Vec<InputSignal> = Vec::new();
+        for name in (start..end).flat_map(|wire| symbols.names(wire)) {
+            // skip the aliases of the subcomponents' signals the inputs are wired to
+            let Some(name) = name.strip_prefix("main.") else {
+                continue;
+            };
+            if name.contains('.') {
+                continue;
+            }
+            let (name, indices) = match name.find('[') {
+                Some(i) => (&name[..i], parse_indices(&name[i..])),
+                None => (name, vec![]),
+            };
+
+            let signal = match signals.iter_mut().find(|s| s.name == name) {
+                Some(signal) => signal,
+                None => {
+                    signals.push(InputSignal {
+                        name: name.to_string(),
+                        dimensions: vec![0; indices.len()],
+                    });
+                    signals.last_mut().unwrap()
+                }
+            };
+            for (dimension, index) in signal.dimensions.iter_mut().zip(indices) {
+                *dimension = (*dimension).max(index + 1);
+            }
+        }
+        signals
+    }
+
+    /// Returns the names of the circuit's input signals as they are passed to the
+    /// witness calculator, e.g. `in` for `main.in[0]` and `main.in[1]`, see
+    /// [`R1CS::input_signals`]
+    pub fn input_signal_names(&self, symbols: &SymbolTable) -> Vec<String> {
+        self.input_signals(symbols)
+            .into_iter()
+            .map(|signal| signal.name)
+            .collect()
+    }
+
+    /// Returns whether the circuit has an input signal called `name`, see
+    /// [`R1CS::input_signal_names`]
+    pub fn has_input_signal(&self, symbols: &SymbolTable, name: &str) -> bool {
+        self.input_signal_names(symbols).iter().any(|n| n == name)
+    }
+}
+
+/// Names the public signals of a proof, i.e. the outputs followed by the public inputs
+/// as returned by [`CircomCircuit::get_public_inputs`], by the signals of the main
+/// component, e.g. `{"out": 33}` or `{"out[0]": 3, "out[1]": 11, "in": 5}`.
+///
+/// The signals are in the order of the vector, which is the order they were declared
+/// in, with the elements of arrays in row-major order. Signals without a name of the
+/// main component in the symbol table are left out.
+///
+/// [`CircomCircuit::get_public_inputs`]: super::CircomCircuit::get_public_inputs
+pub fn map_public_outputs<F: PrimeField>(
+    public_inputs: &[F],
+    symbols: &SymbolTable,
+) -> IndexMap<String, F> {
+    let mut outputs = IndexMap::new();
+    for (i, value) in public_inputs.iter().enumerate() {
+        // the public signals are the wires after the constant one
+        let name = symbols
+            .names(i + 1)
+            .iter()
+            .filter_map(|name| name.strip_prefix("main."))
+            .find(|name| !name.contains('.'));
+        if let Some(name) = name {
+            outputs.insert(name.to_string(), *value);
+        }
+    }
+    outputs
+}
+
+// Parses the indices of an array element's name, e.g. `[1][2]`
+fn parse_indices(indices: &str) -> Vec<usize> {
+    indices
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split("][")
+        .filter_map(|index| index.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CircomConfig;
+    use ark_bn254::Fr;
+
+    #[test]
+    fn mycircuit() {
+        let table = SymbolTable::from_file("./test-vectors/mycircuit.sym").unwrap();
+        assert_eq!(table.index("main.a"), Some(2));
+        assert_eq!(table.index("main.b"), Some(3));
+        assert_eq!(table.index("main.c"), Some(1));
+        assert_eq!(table.index("main.d"), None);
+        assert_eq!(table.names(1), ["main.c"]);
+        assert!(table.names(0).is_empty());
+        assert_eq!(table.indices().len(), 3);
+    }
+
+    #[test]
+    fn aliases_and_removed_signals() {
+        let sym = "1,1,0,main.out\n2,1,1,main.sub.out\n3,-1,1,main.sub.tmp\n";
+        let table = SymbolTable::from_reader(sym.as_bytes()).unwrap();
+        assert_eq!(table.index("main.sub.out"), Some(1));
+        assert_eq!(table.index("main.sub.tmp"), None);
+        assert_eq!(table.names(1), ["main.out", "main.sub.out"]);
+    }
+
+    #[test]
+    fn input_signals() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let table = SymbolTable::from_file("./test-vectors/mycircuit.sym").unwrap();
+        assert_eq!(cfg.r1cs.input_signal_names(&table), ["a", "b"]);
+        assert!(cfg.r1cs.has_input_signal(&table, "a"));
+        // outputs aren't inputs
+        assert!(!cfg.r1cs.has_input_signal(&table, "c"));
+        assert!(!cfg.r1cs.has_input_signal(&table, "main.a"));
+
+        // array inputs, aliased by a subcomponent's signals
+        let mut r1cs = cfg.r1cs;
+        r1cs.num_public_inputs = 0;
+        r1cs.num_private_inputs = 3;
+        let sym =
+            "1,1,0,main.out\n2,2,0,main.in[0]\n3,3,0,main.in[1]\n4,3,1,main.sub.in\n5,4,0,main.k\n";
+        let table = SymbolTable::from_reader(sym.as_bytes()).unwrap();
+        assert_eq!(r1cs.input_signal_names(&table), ["in", "k"]);
+    }
+
+    #[test]
+    fn input_dimensions() {
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let table = SymbolTable::from_file("./test-vectors/mycircuit.sym").unwrap();
+        let signal = |name: &str, dimensions: &[usize]| InputSignal {
+            name: name.to_string(),
+            dimensions: dimensions.to_vec(),
+        };
+        assert_eq!(
+            cfg.r1cs.input_signals(&table),
+            [signal("a", &[]), signal("b", &[])]
+        );
+
+        // a public `signal input m[2][3]`, then private `v[2]` and `k`
+        let mut r1cs = cfg.r1cs;
+        r1cs.num_public_inputs = 6;
+        r1cs.num_private_inputs = 3;
+        let mut sym = "1,1,0,main.out\n".to_string();
+        for i in 0..6 {
+            sym += &format!("{},{},0,main.m[{}][{}]\n", i + 2, i + 2, i / 3, i % 3);
+        }
+        sym += "8,8,0,main.v[0]\n9,9,0,main.v[1]\n10,9,1,main.sub.in[1]\n11,10,0,main.k\n";
+        let table = SymbolTable::from_reader(sym.as_bytes()).unwrap();
+        assert_eq!(
+            r1cs.input_signals(&table),
+            [signal("m", &[2, 3]), signal("v", &[2]), signal("k", &[])]
+        );
+    }
+
+    #[test]
+    fn public_outputs() {
+        let table = SymbolTable::from_file("./test-vectors/mycircuit.sym").unwrap();
+        let outputs = map_public_outputs(&[Fr::from(33u32)], &table);
+        assert_eq!(
+            outputs,
+            IndexMap::from([("c".to_string(), Fr::from(33u32))])
+        );
+
+        // `signal output out[2]` aliased by a subcomponent's signal, `signal output sum`
+        // and a public input `k`
+        let sym = "1,1,0,main.out[0]\n2,1,1,main.sub.out\n3,2,0,main.out[1]\n\
+                   4,3,0,main.sum\n5,4,0,main.k\n6,5,0,main.secret\n";
+        let table = SymbolTable::from_reader(sym.as_bytes()).unwrap();
+        let public = [3u32, 11, 14, 5].map(Fr::from);
+        let outputs = map_public_outputs(&public, &table);
+        assert_eq!(
+            outputs.keys().collect::<Vec<_>>(),
+            ["out[0]", "out[1]", "sum", "k"]
+        );
+        assert_eq!(outputs.values().copied().collect::<Vec<_>>(), public);
+    }
+
+    #[test]
+    fn invalid() {
+        let err = SymbolTable::from_reader("1,x,0,main.a".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+        SymbolTable::from_reader("1,1".as_bytes()).unwrap_err();
+    }
+}
@@ -0,0 +1,46 @@
+//! Conversions between `num_bigint` integers and Arkworks field elements
+use ark_ff::PrimeField;
+use num_bigint::{BigInt, BigUint, Sign};
+
+/// Converts an integer into the field, reducing it modulo the field's prime. Negative
+/// integers are reduced to their canonical representative, e.g. `-1` to `r - 1`.
+pub fn bigint_to_fr<F: PrimeField>(x: &BigInt) -> F {
+    let el = F::from_le_bytes_mod_order(&x.magnitude().to_bytes_le());
+    if x.sign() == Sign::Minus {
+        -el
+    } else {
+        el
+    }
+}
+
+/// Converts a field element into its canonical representative in `[0, r)`
+pub fn fr_to_bigint<F: PrimeField>(fe: &F) -> BigInt {
+    let fe: BigUint = fe.into_bigint().into();
+    fe.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+
+    #[test]
+    fn reduce() {
+        let r: BigInt = BigUint::from(Fr::MODULUS).into();
+
+        assert_eq!(bigint_to_fr::<Fr>(&BigInt::from(5)), Fr::from(5u32));
+        assert_eq!(bigint_to_fr::<Fr>(&BigInt::from(-1)), -Fr::from(1u32));
+        assert_eq!(fr_to_bigint(&-Fr::from(1u32)), &r - 1);
+
+        // out of range values, in both directions
+        assert_eq!(bigint_to_fr::<Fr>(&r), Fr::from(0u32));
+        assert_eq!(bigint_to_fr::<Fr>(&(&r + 7)), Fr::from(7u32));
+        assert_eq!(bigint_to_fr::<Fr>(&(-&r - 7)), -Fr::from(7u32));
+        let large = BigInt::from(1) << 300;
+        assert_eq!(fr_to_bigint(&bigint_to_fr::<Fr>(&large)), &large % &r);
+        assert_eq!(
+            fr_to_bigint(&bigint_to_fr::<Fr>(&-&large)),
+            &r - (&large % &r)
+        );
+    }
+}
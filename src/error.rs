@@ -0,0 +1,145 @@
+//! Error type returned across the crate's public API
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::SerializationError;
+use num_bigint::BigUint;
+use thiserror::Error;
+
+use crate::{circom::InputValidationError, witness::MemoryError};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Failed to compile the WASM module: {0}")]
+    Compile(#[from] wasmer::CompileError),
+    #[error("Failed to serialize the compiled WASM module: {0}")]
+    ModuleSerialization(#[from] wasmer::SerializeError),
+    #[error("Failed to deserialize the compiled WASM module: {0}")]
+    ModuleDeserialization(#[from] wasmer::DeserializeError),
+    #[error("Failed to instantiate the WASM module: {0}")]
+    Instantiation(#[from] Box<wasmer::InstantiationError>),
+    /// The WASM execution trapped, e.g. because an assertion of the circuit failed
+    #[error("WASM runtime error: {0}")]
+    Runtime(#[from] wasmer::RuntimeError),
+    /// The WASM module doesn't export a function, e.g. because it was compiled by
+    /// an unsupported Circom version
+    #[error("WASM export {0} not found")]
+    MissingExport(String),
+    /// A WASM export doesn't return a single `i32`, e.g. because the function has
+    /// another signature in the Circom version the module was compiled by
+    #[error("WASM export {0} doesn't return a single i32")]
+    InvalidExportSignature(String),
+    /// The witness calculation executed more instructions than the metering limit allows
+    #[error("Witness calculation exceeded the limit of {0} instructions")]
+    ExecutionLimitExceeded(u64),
+    /// The circuit aborted the witness calculation, e.g. because one of its asserts
+    /// doesn't hold for the inputs
+    #[error("Witness calculation failed with code {code}: {message}")]
+    WitnessTrap { code: u32, message: String },
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+    #[error("Unsupported Circom version {0}")]
+    UnsupportedVersion(u32),
+    /// The WASM and R1CS weren't compiled from the same circuit by the same Circom version
+    #[error(
+        "The WASM (circom {circom_version}) computes {witness_size} witness values but the \
+         R1CS has {n_wires} wires, were they compiled from the same circuit?"
+    )]
+    WitnessSizeMismatch {
+        circom_version: u32,
+        witness_size: u32,
+        n_wires: u32,
+    },
+    /// An input doesn't match any input signal of the circuit. `hash` is the FNV-1a hash
+    /// of the name, which the WASM looks the signal up by.
+    #[error("unknown input signal '{name}' (hash {hash:#018x})")]
+    UnknownSignal {
+        name: String,
+        hash: u64,
+        #[source]
+        source: wasmer::RuntimeError,
+    },
+    /// An input array doesn't have as many values as the circuit's input signal
+    #[error("input signal '{name}' has {actual} values, but the circuit expects {expected}")]
+    InputLengthMismatch {
+        name: String,
+        expected: u32,
+        actual: usize,
+    },
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error(transparent)]
+    InputValidation(#[from] InputValidationError),
+    #[error("Invalid snarkjs JSON: {0}")]
+    InvalidJson(String),
+    #[error("Failed to deserialize: {0}")]
+    Serialization(#[from] SerializationError),
+    #[error("zkey is missing section {0}")]
+    MissingSection(u32),
+    /// The zkey is for another proving system than Groth16, e.g. 2 for PLONK or 10 for
+    /// FFLONK, which snarkjs lays out differently
+    #[error("zkey protocol {0} is not supported, only Groth16 (1) zkeys can be read")]
+    UnsupportedProtocol(u32),
+    /// The zkey was generated for a different curve than the one it's read into
+    #[error(
+        "zkey {name} {prime} does not match the field modulus {modulus}, \
+         was it generated for a different curve?"
+    )]
+    FieldMismatch {
+        name: &'static str,
+        prime: BigUint,
+        modulus: BigUint,
+    },
+    /// The `.wtns` file was computed for a circuit over a different field, e.g. a
+    /// BLS12-381 witness read for a BN254 circuit
+    #[error("The witness is for the prime {prime} but the field modulus is {modulus}")]
+    WitnessFieldMismatch { prime: BigUint, modulus: BigUint },
+    /// The proving key was generated for another circuit, e.g. the same one compiled with
+    /// another optimization level, which changes the number of wires
+    #[error(
+        "The proving key is for {pk_wires} wires and {pk_public} public signals but the \
+         circuit has {wires} and {public}, were they compiled with the same options?"
+    )]
+    ProvingKeyMismatch {
+        pk_wires: usize,
+        pk_public: usize,
+        wires: usize,
+        public: usize,
+    },
+    /// A constraint of the zkey differs from the circuit's, e.g. as the zkey was
+    /// generated from the R1CS of another optimization level with as many wires. `wire`
+    /// is the lowest wire whose coefficient differs, `None` if the zkey has another
+    /// number of constraints.
+    #[error(
+        "Constraint {constraint} of the zkey's {matrix} matrix differs from the circuit's \
+         at wire {wire:?}, were they compiled with the same options?"
+    )]
+    ConstraintMismatch {
+        matrix: char,
+        constraint: usize,
+        wire: Option<usize>,
+    },
+    /// The proving key's evaluation domain doesn't fit the circuit's constraints, i.e.
+    /// it was generated for another circuit
+    #[error(
+        "The proving key has a domain of size {pk_domain_size} but the circuit's \
+         constraints need {domain_size}, were they compiled from the same circuit?"
+    )]
+    DomainSizeMismatch {
+        pk_domain_size: usize,
+        domain_size: usize,
+    },
+    /// The witness doesn't satisfy the constraint at the given index
+    #[error("Constraint {0} is not satisfied by the witness")]
+    UnsatisfiedConstraint(usize),
+    #[error(transparent)]
+    Synthesis(#[from] SynthesisError),
+}
+
+impl From<wasmer::InstantiationError> for Error {
+    fn from(err: wasmer::InstantiationError) -> Self {
+        Self::Instantiation(Box::new(err))
+    }
+}
@@ -2,11 +2,17 @@
 //! Solidity Groth16 Verifier smart contracts
 use ark_ff::{BigInteger, PrimeField};
 use ethers_core::types::U256;
+use num_bigint::{BigInt, BigUint, Sign};
 use num_traits::Zero;
+use std::fmt::{self, Write};
 
+use crate::{bigint_to_fr, circom::parse_bigint, Error, Result};
 use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
-use ark_serialize::CanonicalDeserialize;
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Valid, Validate,
+};
 
+/// Public inputs, as canonical field representatives in `[0, r)`
 pub struct Inputs(pub Vec<U256>);
 
 impl From<&[Fr]> for Inputs {
@@ -17,6 +23,57 @@ impl From<&[Fr]> for Inputs {
     }
 }
 
+/// Reduces each input modulo the scalar field, so negative values are mapped to `r - |x|`
+impl From<&[BigInt]> for Inputs {
+    fn from(src: &[BigInt]) -> Self {
+        let els = src
+            .iter()
+            .map(|x| point_to_u256(bigint_to_fr::<Fr>(x)))
+            .collect();
+
+        Self(els)
+    }
+}
+
+/// Parses public inputs given as decimal or `0x`-prefixed hexadecimal strings, as in
+/// snarkjs' `public.json`. Inputs outside of `[0, r)` are rejected, as they usually
+/// mean that the inputs were computed for another field. See
+/// [`parse_public_inputs_reduced`] to reduce them instead.
+pub fn parse_public_inputs(inputs: &[&str]) -> Result<Vec<Fr>> {
+    let modulus = BigInt::from(BigUint::from(Fr::MODULUS));
+    inputs
+        .iter()
+        .map(|input| {
+            let x = parse_input(input)?;
+            if x.sign() == Sign::Minus || x >= modulus {
+                return Err(Error::InvalidInput(format!(
+                    "public input {} is not in the scalar field",
+                    input
+                )));
+            }
+            Ok(bigint_to_fr(&x))
+        })
+        .collect()
+}
+
+/// Same as [`parse_public_inputs`], but reduces the inputs modulo the scalar field
+/// like [`Inputs`], so negative values are mapped to `r - |x|`
+pub fn parse_public_inputs_reduced(inputs: &[&str]) -> Result<Vec<Fr>> {
+    inputs
+        .iter()
+        .map(|input| Ok(bigint_to_fr(&parse_input(input)?)))
+        .collect()
+}
+
+fn parse_input(input: &str) -> Result<BigInt> {
+    parse_bigint(input).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "public input {} is not a decimal or a 0x-prefixed hex string",
+            input
+        ))
+    })
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct G1 {
     pub x: U256,
@@ -41,6 +98,28 @@ impl G1 {
     pub fn as_tuple(&self) -> (U256, U256) {
         (self.x, self.y)
     }
+
+    /// Returns the point in the [`PointEncoding::Compressed`] encoding, i.e. its `x`
+    /// coordinate with the [`SIGN_FLAG`] set if `y` is the larger of `±y`
+    pub fn compress(&self) -> U256 {
+        let p = G1Affine::from(*self);
+        if p.infinity {
+            return INFINITY_FLAG;
+        }
+        let flag = if p.y > -p.y { SIGN_FLAG } else { U256::zero() };
+        point_to_u256(p.x) | flag
+    }
+
+    /// Recovers a point encoded by [`G1::compress`], checking that it's on the curve
+    pub fn decompress(compressed: U256) -> Result<Self> {
+        if compressed == INFINITY_FLAG {
+            return Ok(Self::default());
+        }
+        let x = compressed_coordinate::<Fq>(compressed)?;
+        let p = G1Affine::get_point_from_x_unchecked(x, compressed & SIGN_FLAG != U256::zero())
+            .ok_or_else(|| Error::InvalidInput(format!("{} is not on G1", compressed)))?;
+        Ok(Self::from(&p))
+    }
 }
 
 impl From<&G1Affine> for G1 {
@@ -78,13 +157,98 @@ impl From<G2> for G2Affine {
 
 type G2Tup = ([U256; 2], [U256; 2]);
 
+/// The order of the two components `c0 + c1 * u` of a G2 point's coordinates, which
+/// differs between verifier contracts
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum G2Order {
+    /// `[c1, c0]`, as expected by the EIP-197 pairing precompile and snarkjs' Solidity
+    /// verifier and `zkey export soliditycalldata`
+    #[default]
+    C1First,
+    /// `[c0, c1]`, as in Arkworks' `Fq2` and snarkjs' `proof.json`
+    C0First,
+}
+
 impl G2 {
-    // NB: Serialize the c1 limb first.
+    /// Returns the coordinates in the EVM's [`G2Order::C1First`] order
     pub fn as_tuple(&self) -> G2Tup {
-        ([self.x[1], self.x[0]], [self.y[1], self.y[0]])
+        self.as_tuple_with_order(G2Order::C1First)
+    }
+
+    pub fn as_tuple_with_order(&self, order: G2Order) -> G2Tup {
+        match order {
+            G2Order::C1First => ([self.x[1], self.x[0]], [self.y[1], self.y[0]]),
+            G2Order::C0First => (self.x, self.y),
+        }
+    }
+
+    /// Returns the point in the [`PointEncoding::Compressed`] encoding, i.e. its `x`
+    /// coordinate in the [`G2Order::C1First`] order, with the [`SIGN_FLAG`] of the
+    /// first word set if `y` is the larger of `±y`, comparing `c1` first
+    pub fn compress(&self) -> [U256; 2] {
+        let p = G2Affine::from(*self);
+        if p.infinity {
+            return [INFINITY_FLAG, U256::zero()];
+        }
+        let flag = if p.y > -p.y { SIGN_FLAG } else { U256::zero() };
+        [point_to_u256(p.x.c1) | flag, point_to_u256(p.x.c0)]
+    }
+
+    /// Recovers a point encoded by [`G2::compress`], checking that it's on the curve
+    /// and in the prime order subgroup
+    pub fn decompress(compressed: [U256; 2]) -> Result<Self> {
+        if compressed == [INFINITY_FLAG, U256::zero()] {
+            return Ok(Self::default());
+        }
+        let x = Fq2::new(
+            compressed_coordinate(compressed[1])?,
+            compressed_coordinate(compressed[0])?,
+        );
+        let greatest = compressed[0] & SIGN_FLAG != U256::zero();
+        G2Affine::get_point_from_x_unchecked(x, greatest)
+            .filter(|p| p.is_in_correct_subgroup_assuming_on_curve())
+            .map(|p| Self::from(&p))
+            .ok_or_else(|| Error::InvalidInput(format!("{:?} is not on G2", compressed)))
     }
 }
 
+/// How the points of a proof are encoded in the verifier's calldata
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointEncoding {
+    /// Both affine coordinates, as taken by the `ecAdd`, `ecMul` and `ecPairing`
+    /// precompiles of EIP-196 and EIP-197 (`0x06` to `0x08`), which the verifiers of
+    /// snarkjs and [`export_solidity_verifier`] pass the points to
+    #[default]
+    Uncompressed,
+    /// The `x` coordinate with the sign of `y` in its top bit, halving the proof's
+    /// calldata to 4 words. No precompile takes compressed points, so the verifier
+    /// has to recover `y` first, e.g. computing the square roots with the EIP-198
+    /// `modexp` precompile (`0x05`), so it pays off where calldata is expensive compared
+    /// to computation, e.g. on rollups. Like EIP-196 and EIP-197, the EIP-2537
+    /// BLS12-381 precompiles only take uncompressed points.
+    Compressed,
+}
+
+/// Set in the top bit of a compressed point when `y` is the larger of `±y`. BN254's
+/// base field is 254 bits, leaving the top two bits of a word free for flags.
+pub const SIGN_FLAG: U256 = U256([0, 0, 0, 1 << 63]);
+
+/// A compressed point at infinity, which has no `x` coordinate
+pub const INFINITY_FLAG: U256 = U256([0, 0, 0, 1 << 62]);
+
+// The coordinate of a compressed point, without the flags
+fn compressed_coordinate<F: PrimeField>(compressed: U256) -> Result<F> {
+    let x = compressed & !(SIGN_FLAG | INFINITY_FLAG);
+    let modulus = U256::from_big_endian(&F::MODULUS.to_bytes_be());
+    if compressed & INFINITY_FLAG != U256::zero() || x >= modulus {
+        return Err(Error::InvalidInput(format!(
+            "{} is not a compressed coordinate",
+            compressed
+        )));
+    }
+    Ok(u256_to_point(x))
+}
+
 impl From<&G2Affine> for G2 {
     fn from(p: &G2Affine) -> Self {
         Self {
@@ -103,10 +267,105 @@ pub struct Proof {
 
 impl Proof {
     pub fn as_tuple(&self) -> (G1Tup, G2Tup, G1Tup) {
-        (self.a.as_tuple(), self.b.as_tuple(), self.c.as_tuple())
+        self.as_tuple_with_order(G2Order::C1First)
+    }
+
+    pub fn as_tuple_with_order(&self, order: G2Order) -> (G1Tup, G2Tup, G1Tup) {
+        (
+            self.a.as_tuple(),
+            self.b.as_tuple_with_order(order),
+            self.c.as_tuple(),
+        )
+    }
+
+    /// Returns the arguments of the Solidity verifier's `verifyProof` call as a flat
+    /// uint256 array, i.e. `[a, b, c, public_inputs]` with the G2 limbs in the order
+    /// expected by the precompiles.
+    pub fn as_calldata(&self, public_inputs: &[Fr]) -> Vec<U256> {
+        self.as_calldata_with_order(public_inputs, G2Order::C1First)
+    }
+
+    /// Same as [`Proof::as_calldata`], for verifiers expecting another G2 order
+    pub fn as_calldata_with_order(&self, public_inputs: &[Fr], order: G2Order) -> Vec<U256> {
+        let (a, b, c) = self.as_tuple_with_order(order);
+        let mut calldata = vec![a.0, a.1, b.0[0], b.0[1], b.1[0], b.1[1], c.0, c.1];
+        calldata.extend(Inputs::from(public_inputs).0);
+        calldata
+    }
+
+    /// Same as [`Proof::as_calldata`], with the points in the given encoding. With
+    /// [`PointEncoding::Compressed`], the proof takes 4 words: `a`, the 2 of `b`, then
+    /// `c`, see [`G1::compress`] and [`G2::compress`].
+    pub fn as_calldata_with_encoding(
+        &self,
+        public_inputs: &[Fr],
+        encoding: PointEncoding,
+    ) -> Vec<U256> {
+        match encoding {
+            PointEncoding::Uncompressed => self.as_calldata(public_inputs),
+            PointEncoding::Compressed => {
+                let b = self.b.compress();
+                let mut calldata = vec![self.a.compress(), b[0], b[1], self.c.compress()];
+                calldata.extend(Inputs::from(public_inputs).0);
+                calldata
+            }
+        }
+    }
+
+    /// Renders the arguments of the Solidity verifier's `verifyProof` call as hex
+    /// arrays, in the format of snarkjs' `zkey export soliditycalldata`, e.g. for
+    /// pasting them into a block explorer
+    pub fn to_hex_calldata(&self, public_inputs: &[Fr]) -> String {
+        format!("{},{}", self, Inputs::from(public_inputs))
+    }
+}
+
+/// Renders the point as the verifier's `["0x..", "0x.."]` array
+impl fmt::Display for G1 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", hex(self.x), hex(self.y))
+    }
+}
+
+/// Renders the point as the verifier's nested arrays, in the EVM's
+/// [`G2Order::C1First`] order
+impl fmt::Display for G2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (x, y) = self.as_tuple();
+        write!(
+            f,
+            "[[{}, {}],[{}, {}]]",
+            hex(x[0]),
+            hex(x[1]),
+            hex(y[0]),
+            hex(y[1])
+        )
+    }
+}
+
+/// Renders the proof as the `a,b,c` arguments of the verifier, see
+/// [`Proof::to_hex_calldata`]
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.a, self.b, self.c)
+    }
+}
+
+/// Renders the inputs as the verifier's `["0x..",..]` array
+impl fmt::Display for Inputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inputs: Vec<_> = self.0.iter().map(|x| hex(*x)).collect();
+        write!(f, "[{}]", inputs.join(","))
     }
 }
 
+/// Quoted and zero-padded to 32 bytes, as the uint256 arguments in snarkjs' calldata
+fn hex(x: U256) -> String {
+    let mut bytes = [0u8; 32];
+    x.to_big_endian(&mut bytes);
+    format!("\"0x{}\"", hex::encode(bytes))
+}
+
 impl From<ark_groth16::Proof<Bn254>> for Proof {
     fn from(proof: ark_groth16::Proof<Bn254>) -> Self {
         Self {
@@ -172,6 +431,429 @@ impl From<VerifyingKey> for ark_groth16::VerifyingKey<Bn254> {
     }
 }
 
+/// Exports a Solidity Groth16 verifier contract with the verifying key embedded,
+/// following snarkjs' `verifier_groth16.sol` template.
+///
+/// Fails with [`Error::InvalidInput`] for keys without public signals, i.e. with a
+/// single IC point, as the template's `uint[0]` public signals parameter isn't valid
+/// Solidity, and for keys without any IC point.
+pub fn export_solidity_verifier(vk: &ark_groth16::VerifyingKey<Bn254>) -> Result<String> {
+    let n_public = match vk.gamma_abc_g1.len() {
+        0 => {
+            return Err(Error::InvalidInput(
+                "the verifying key has no IC points".to_string(),
+            ))
+        }
+        1 => {
+            return Err(Error::InvalidInput(
+                "the Solidity verifier needs at least one public signal".to_string(),
+            ))
+        }
+        len => len - 1,
+    };
+    let vk = VerifyingKey::from(vk.clone());
+    let mut sol = String::new();
+
+    sol.push_str(SOLIDITY_VERIFIER_HEADER);
+    sol.push_str("    // Verification Key data\n");
+    writeln!(sol, "    uint256 constant alphax  = {};", vk.alpha1.x).unwrap();
+    writeln!(sol, "    uint256 constant alphay  = {};", vk.alpha1.y).unwrap();
+    for (name, point) in [
+        ("beta", vk.beta2),
+        ("gamma", vk.gamma2),
+        ("delta", vk.delta2),
+    ] {
+        // the c1 limb goes first, as in G2::as_tuple
+        let (x, y) = point.as_tuple();
+        writeln!(sol, "    uint256 constant {name}x1 = {};", x[0]).unwrap();
+        writeln!(sol, "    uint256 constant {name}x2 = {};", x[1]).unwrap();
+        writeln!(sol, "    uint256 constant {name}y1 = {};", y[0]).unwrap();
+        writeln!(sol, "    uint256 constant {name}y2 = {};", y[1]).unwrap();
+    }
+    sol.push('\n');
+    for (i, ic) in vk.ic.iter().enumerate() {
+        writeln!(sol, "    uint256 constant IC{i}x = {};", ic.x).unwrap();
+        writeln!(sol, "    uint256 constant IC{i}y = {};", ic.y).unwrap();
+    }
+
+    writeln!(
+        sol,
+        r#"
+    // Memory data
+    uint16 constant pVk = 0;
+    uint16 constant pPairing = 128;
+
+    uint16 constant pLastMem = 896;
+
+    function verifyProof(uint[2] calldata _pA, uint[2][2] calldata _pB, uint[2] calldata _pC, uint[{n_public}] calldata _pubSignals) public view returns (bool) {{
+        assembly {{{SOLIDITY_VERIFIER_FUNCTIONS}
+            function checkPairing(pA, pB, pC, pubSignals, pMem) -> isOk {{
+                let _pPairing := add(pMem, pPairing)
+                let _pVk := add(pMem, pVk)
+
+                mstore(_pVk, IC0x)
+                mstore(add(_pVk, 32), IC0y)
+
+                // Compute the linear combination vk_x"#
+    )
+    .unwrap();
+    for i in 1..=n_public {
+        writeln!(
+            sol,
+            "                g1_mulAccC(_pVk, IC{i}x, IC{i}y, calldataload(add(pubSignals, {})))",
+            (i - 1) * 32
+        )
+        .unwrap();
+    }
+    sol.push_str(SOLIDITY_VERIFIER_PAIRING);
+    for i in 0..n_public {
+        writeln!(
+            sol,
+            "            checkField(calldataload(add(_pubSignals, {})))",
+            i * 32
+        )
+        .unwrap();
+    }
+    sol.push_str(SOLIDITY_VERIFIER_FOOTER);
+
+    Ok(sol)
+}
+
+const SOLIDITY_VERIFIER_HEADER: &str = r#"// SPDX-License-Identifier: GPL-3.0
+/*
+    Copyright 2021 0KIMS association.
+
+    This file is generated with [snarkJS](https://github.com/iden3/snarkjs).
+
+    snarkJS is a free software: you can redistribute it and/or modify it
+    under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+
+    snarkJS is distributed in the hope that it will be useful, but WITHOUT
+    ANY WARRANTY; without even the implied warranty of MERCHANTABILITY
+    or FITNESS FOR A PARTICULAR PURPOSE. See the GNU General Public
+    License for more details.
+
+    You should have received a copy of the GNU General Public License
+    along with snarkJS. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+pragma solidity >=0.7.0 <0.9.0;
+
+contract Groth16Verifier {
+    // Scalar field size
+    uint256 constant r    = 21888242871839275222246405745257275088548364400416034343698204186575808495617;
+    // Base field size
+    uint256 constant q   = 21888242871839275222246405745257275088696311157297823662689037894645226208583;
+
+"#;
+
+const SOLIDITY_VERIFIER_FUNCTIONS: &str = r#"
+            function checkField(v) {
+                if iszero(lt(v, r)) {
+                    mstore(0, 0)
+                    return(0, 0x20)
+                }
+            }
+
+            // G1 function to multiply a G1 value(x,y) to value in an address
+            function g1_mulAccC(pR, x, y, s) {
+                let success
+                let mIn := mload(0x40)
+                mstore(mIn, x)
+                mstore(add(mIn, 32), y)
+                mstore(add(mIn, 64), s)
+
+                success := staticcall(sub(gas(), 2000), 7, mIn, 96, mIn, 64)
+
+                if iszero(success) {
+                    mstore(0, 0)
+                    return(0, 0x20)
+                }
+
+                mstore(add(mIn, 64), mload(pR))
+                mstore(add(mIn, 96), mload(add(pR, 32)))
+
+                success := staticcall(sub(gas(), 2000), 6, mIn, 128, pR, 64)
+
+                if iszero(success) {
+                    mstore(0, 0)
+                    return(0, 0x20)
+                }
+            }
+"#;
+
+const SOLIDITY_VERIFIER_PAIRING: &str = r#"
+                // -A
+                mstore(_pPairing, calldataload(pA))
+                mstore(add(_pPairing, 32), mod(sub(q, calldataload(add(pA, 32))), q))
+
+                // B
+                mstore(add(_pPairing, 64), calldataload(pB))
+                mstore(add(_pPairing, 96), calldataload(add(pB, 32)))
+                mstore(add(_pPairing, 128), calldataload(add(pB, 64)))
+                mstore(add(_pPairing, 160), calldataload(add(pB, 96)))
+
+                // alpha1
+                mstore(add(_pPairing, 192), alphax)
+                mstore(add(_pPairing, 224), alphay)
+
+                // beta2
+                mstore(add(_pPairing, 256), betax1)
+                mstore(add(_pPairing, 288), betax2)
+                mstore(add(_pPairing, 320), betay1)
+                mstore(add(_pPairing, 352), betay2)
+
+                // vk_x
+                mstore(add(_pPairing, 384), mload(add(pMem, pVk)))
+                mstore(add(_pPairing, 416), mload(add(pMem, add(pVk, 32))))
+
+                // gamma2
+                mstore(add(_pPairing, 448), gammax1)
+                mstore(add(_pPairing, 480), gammax2)
+                mstore(add(_pPairing, 512), gammay1)
+                mstore(add(_pPairing, 544), gammay2)
+
+                // C
+                mstore(add(_pPairing, 576), calldataload(pC))
+                mstore(add(_pPairing, 608), calldataload(add(pC, 32)))
+
+                // delta2
+                mstore(add(_pPairing, 640), deltax1)
+                mstore(add(_pPairing, 672), deltax2)
+                mstore(add(_pPairing, 704), deltay1)
+                mstore(add(_pPairing, 736), deltay2)
+
+                let success := staticcall(sub(gas(), 2000), 8, _pPairing, 768, _pPairing, 0x20)
+
+                isOk := and(success, mload(_pPairing))
+            }
+
+            let pMem := mload(0x40)
+            mstore(0x40, add(pMem, pLastMem))
+
+            // Validate that all evaluations ∈ F
+"#;
+
+const SOLIDITY_VERIFIER_FOOTER: &str = r#"
+            // Validate all evaluations
+            let isValid := checkPairing(_pA, _pB, _pC, _pubSignals, pMem)
+
+            mstore(0, isValid)
+            return(0, 0x20)
+        }
+    }
+}
+"#;
+
+// The types are serialized like their Arkworks equivalents, so that either can be read
+// from the bytes of the other
+
+// Like `From<G1>`/`From<G2>`, but without panicking for points which aren't on the
+// curve, so that they can be serialized and then rejected by `Valid::check`
+trait ToAffineUnchecked {
+    type Affine: CanonicalSerialize + CanonicalDeserialize;
+    fn to_affine_unchecked(&self) -> Self::Affine;
+}
+
+impl ToAffineUnchecked for G1 {
+    type Affine = G1Affine;
+    fn to_affine_unchecked(&self) -> G1Affine {
+        let (x, y): (Fq, Fq) = (u256_to_point(self.x), u256_to_point(self.y));
+        if x.is_zero() && y.is_zero() {
+            G1Affine::identity()
+        } else {
+            G1Affine::new_unchecked(x, y)
+        }
+    }
+}
+
+impl ToAffineUnchecked for G2 {
+    type Affine = G2Affine;
+    fn to_affine_unchecked(&self) -> G2Affine {
+        let x = Fq2::new(u256_to_point(self.x[0]), u256_to_point(self.x[1]));
+        let y = Fq2::new(u256_to_point(self.y[0]), u256_to_point(self.y[1]));
+        if x.is_zero() && y.is_zero() {
+            G2Affine::identity()
+        } else {
+            G2Affine::new_unchecked(x, y)
+        }
+    }
+}
+
+macro_rules! impl_canonical_point {
+    ($point:ty) => {
+        impl CanonicalSerialize for $point {
+            fn serialize_with_mode<W: ark_serialize::Write>(
+                &self,
+                writer: W,
+                compress: Compress,
+            ) -> Result<(), SerializationError> {
+                self.to_affine_unchecked()
+                    .serialize_with_mode(writer, compress)
+            }
+
+            fn serialized_size(&self, compress: Compress) -> usize {
+                self.to_affine_unchecked().serialized_size(compress)
+            }
+        }
+
+        impl Valid for $point {
+            fn check(&self) -> Result<(), SerializationError> {
+                self.to_affine_unchecked().check()
+            }
+        }
+
+        impl CanonicalDeserialize for $point {
+            fn deserialize_with_mode<R: ark_serialize::Read>(
+                reader: R,
+                compress: Compress,
+                validate: Validate,
+            ) -> Result<Self, SerializationError> {
+                let point = <<$point as ToAffineUnchecked>::Affine>::deserialize_with_mode(
+                    reader, compress, validate,
+                )?;
+                Ok(Self::from(&point))
+            }
+        }
+    };
+}
+
+impl_canonical_point!(G1);
+impl_canonical_point!(G2);
+
+impl CanonicalSerialize for Proof {
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.a.serialize_with_mode(&mut writer, compress)?;
+        self.b.serialize_with_mode(&mut writer, compress)?;
+        self.c.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.a.serialized_size(compress)
+            + self.b.serialized_size(compress)
+            + self.c.serialized_size(compress)
+    }
+}
+
+impl Valid for Proof {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.a.check()?;
+        self.b.check()?;
+        self.c.check()
+    }
+}
+
+impl CanonicalDeserialize for Proof {
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            a: G1::deserialize_with_mode(&mut reader, compress, validate)?,
+            b: G2::deserialize_with_mode(&mut reader, compress, validate)?,
+            c: G1::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl CanonicalSerialize for VerifyingKey {
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.alpha1.serialize_with_mode(&mut writer, compress)?;
+        self.beta2.serialize_with_mode(&mut writer, compress)?;
+        self.gamma2.serialize_with_mode(&mut writer, compress)?;
+        self.delta2.serialize_with_mode(&mut writer, compress)?;
+        self.ic.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.alpha1.serialized_size(compress)
+            + self.beta2.serialized_size(compress)
+            + self.gamma2.serialized_size(compress)
+            + self.delta2.serialized_size(compress)
+            + self.ic.serialized_size(compress)
+    }
+}
+
+impl Valid for VerifyingKey {
+    fn check(&self) -> Result<(), SerializationError> {
+        self.alpha1.check()?;
+        self.beta2.check()?;
+        self.gamma2.check()?;
+        self.delta2.check()?;
+        self.ic.check()
+    }
+}
+
+impl CanonicalDeserialize for VerifyingKey {
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(Self {
+            alpha1: G1::deserialize_with_mode(&mut reader, compress, validate)?,
+            beta2: G2::deserialize_with_mode(&mut reader, compress, validate)?,
+            gamma2: G2::deserialize_with_mode(&mut reader, compress, validate)?,
+            delta2: G2::deserialize_with_mode(&mut reader, compress, validate)?,
+            ic: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+// Serialized like a `Vec<Fr>`, i.e. the length followed by each input in little-endian
+impl CanonicalSerialize for Inputs {
+    fn serialize_with_mode<W: ark_serialize::Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        (self.0.len() as u64).serialize_with_mode(&mut writer, compress)?;
+        let mut buf = [0; 32];
+        for input in &self.0 {
+            input.to_little_endian(&mut buf);
+            writer.write_all(&buf)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, _compress: Compress) -> usize {
+        8 + 32 * self.0.len()
+    }
+}
+
+impl Valid for Inputs {
+    fn check(&self) -> Result<(), SerializationError> {
+        let modulus = point_to_u256(-Fr::from(1u32)) + 1;
+        if self.0.iter().all(|input| *input < modulus) {
+            Ok(())
+        } else {
+            Err(SerializationError::InvalidData)
+        }
+    }
+}
+
+impl CanonicalDeserialize for Inputs {
+    fn deserialize_with_mode<R: ark_serialize::Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let inputs = Vec::<Fr>::deserialize_with_mode(reader, compress, validate)?;
+        Ok(Self::from(&inputs[..]))
+    }
+}
+
 // Helper for converting a PrimeField to its U256 representation for Ethereum compatibility
 fn u256_to_point<F: PrimeField>(point: U256) -> F {
     let mut buf = [0; 32];
@@ -232,6 +914,47 @@ mod tests {
         assert_eq!(el2, el4);
     }
 
+    #[test]
+    fn negative_inputs() {
+        let r = U256::from_dec_str(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+        )
+        .unwrap();
+
+        let inputs = Inputs::from(&[-Fr::from(1), Fr::from(5)][..]);
+        assert_eq!(inputs.0, vec![r - 1, U256::from(5)]);
+
+        let big_r = BigInt::from_biguint(Sign::Plus, Fr::MODULUS.into());
+        let inputs = Inputs::from(
+            &[
+                BigInt::from(-1),
+                BigInt::from(5),
+                -big_r.clone() - 3,
+                big_r + 2,
+            ][..],
+        );
+        assert_eq!(inputs.0, vec![r - 1, U256::from(5), r - 3, U256::from(2)]);
+        assert!(inputs.0.iter().all(|input| *input < r));
+    }
+
+    #[test]
+    fn public_inputs() {
+        let r = BigUint::from(Fr::MODULUS).to_string();
+        let inputs = parse_public_inputs(&["33", "0x21", "0"]).unwrap();
+        assert_eq!(inputs, [Fr::from(33u32), Fr::from(33u32), Fr::from(0u32)]);
+
+        let out_of_range = [r.as_str(), "-1"];
+        for input in out_of_range {
+            assert!(parse_public_inputs(&[input]).is_err(), "{}", input);
+        }
+        let inputs = parse_public_inputs_reduced(&out_of_range).unwrap();
+        assert_eq!(inputs, [Fr::from(0u32), -Fr::from(1u32)]);
+
+        for input in ["", "0x", "1.5", "abc", "+1"] {
+            assert!(parse_public_inputs_reduced(&[input]).is_err(), "{}", input);
+        }
+    }
+
     #[test]
     fn convert_g1() {
         let el = g1();
@@ -266,6 +989,269 @@ mod tests {
         assert_eq!(ark_vk, vk);
     }
 
+    #[test]
+    fn solidity_verifier() {
+        let rng = &mut ark_std::test_rng();
+        let vk = ark_groth16::VerifyingKey::<Bn254> {
+            alpha_g1: G1Affine::rand(rng),
+            beta_g2: G2Affine::rand(rng),
+            gamma_g2: G2Affine::rand(rng),
+            delta_g2: G2Affine::rand(rng),
+            gamma_abc_g1: vec![
+                G1Affine::rand(rng),
+                G1Affine::rand(rng),
+                G1Affine::rand(rng),
+            ],
+        };
+        let sol = export_solidity_verifier(&vk).unwrap();
+
+        let constant = |name: &str| -> U256 {
+            let prefix = format!("uint256 constant {name} ");
+            let line = sol
+                .lines()
+                .map(str::trim)
+                .find(|line| line.starts_with(&prefix))
+                .unwrap();
+            let value = line.split('=').nth(1).unwrap().trim().trim_end_matches(';');
+            U256::from_dec_str(value).unwrap()
+        };
+        let g1 = |name: &str| G1 {
+            x: constant(&format!("{name}x")),
+            y: constant(&format!("{name}y")),
+        };
+        // the constants are stored with the c1 limb first
+        let g2 = |name: &str| G2 {
+            x: [
+                constant(&format!("{name}x2")),
+                constant(&format!("{name}x1")),
+            ],
+            y: [
+                constant(&format!("{name}y2")),
+                constant(&format!("{name}y1")),
+            ],
+        };
+
+        assert_eq!(g1("alpha"), G1::from(&vk.alpha_g1));
+        assert_eq!(g2("beta"), G2::from(&vk.beta_g2));
+        assert_eq!(g2("gamma"), G2::from(&vk.gamma_g2));
+        assert_eq!(g2("delta"), G2::from(&vk.delta_g2));
+        for (i, ic) in vk.gamma_abc_g1.iter().enumerate() {
+            assert_eq!(g1(&format!("IC{i}")), G1::from(ic));
+        }
+        assert!(!sol.contains("IC3x"));
+        assert!(sol.contains("uint[2] calldata _pubSignals"));
+        assert!(sol.contains("g1_mulAccC(_pVk, IC2x, IC2y, calldataload(add(pubSignals, 32)))"));
+
+        // without public signals, or without the constant one's point
+        for len in [1, 0] {
+            let mut vk = vk.clone();
+            vk.gamma_abc_g1.truncate(len);
+            let err = export_solidity_verifier(&vk).unwrap_err();
+            assert!(matches!(err, Error::InvalidInput(_)), "{}", err);
+        }
+    }
+
+    #[test]
+    fn compress_points() {
+        let rng = &mut ark_std::test_rng();
+        for _ in 0..10 {
+            let (p1, p2) = (G1Affine::rand(rng), G2Affine::rand(rng));
+            for p in [p1, -p1] {
+                let p = G1::from(&p);
+                assert_eq!(G1::decompress(p.compress()).unwrap(), p);
+            }
+            for p in [p2, -p2] {
+                let p = G2::from(&p);
+                assert_eq!(G2::decompress(p.compress()).unwrap(), p);
+            }
+        }
+
+        let zero = G1::from(&G1Affine::identity());
+        assert_eq!(zero.compress(), INFINITY_FLAG);
+        assert_eq!(G1::decompress(zero.compress()).unwrap(), zero);
+        let zero = G2::from(&G2Affine::identity());
+        assert_eq!(G2::decompress(zero.compress()).unwrap(), zero);
+
+        // misplaced flags and coordinates outside of the field
+        let p = G1::from(&g1());
+        let mut compressed = p.compress();
+        assert!(G1::decompress(compressed | INFINITY_FLAG).is_err());
+        compressed = (compressed & !SIGN_FLAG) + U256::from_big_endian(&Fq::MODULUS.to_bytes_be());
+        assert!(G1::decompress(compressed).is_err());
+        // an x on the curve but not in the prime order subgroup of G2
+        let x = Fq2::new(Fq::from(1), Fq::from(0));
+        let not_in_subgroup = (0..)
+            .map(|i| x + Fq2::new(Fq::from(i), Fq::from(0)))
+            .find_map(|x| G2Affine::get_point_from_x_unchecked(x, false))
+            .unwrap();
+        assert!(!not_in_subgroup.is_in_correct_subgroup_assuming_on_curve());
+        let compressed = [
+            point_to_u256(not_in_subgroup.x.c1),
+            point_to_u256(not_in_subgroup.x.c0),
+        ];
+        assert!(G2::decompress(compressed).is_err());
+    }
+
+    #[test]
+    fn compressed_calldata() {
+        let proof = Proof {
+            a: G1::from(&g1()),
+            b: G2::from(&g2()),
+            c: G1::from(&G1Affine::rand(&mut ark_std::test_rng())),
+        };
+        let inputs = [Fr::from(33u32), Fr::from(5u32)];
+        assert_eq!(
+            proof.as_calldata_with_encoding(&inputs, PointEncoding::default()),
+            proof.as_calldata(&inputs)
+        );
+
+        let calldata = proof.as_calldata_with_encoding(&inputs, PointEncoding::Compressed);
+        assert_eq!(calldata.len(), 4 + 2);
+        assert_eq!(G1::decompress(calldata[0]).unwrap(), proof.a);
+        assert_eq!(G2::decompress([calldata[1], calldata[2]]).unwrap(), proof.b);
+        assert_eq!(G1::decompress(calldata[3]).unwrap(), proof.c);
+        assert_eq!(calldata[4..], [U256::from(33), U256::from(5)]);
+    }
+
+    #[test]
+    fn proof_calldata() {
+        use serde_json::Value;
+        use std::str::FromStr;
+
+        let read = |path: &str| -> Value {
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+        };
+        let proof_json = read("./test-vectors/proof.json");
+        let public_json = read("./test-vectors/public.json");
+
+        let fq = |v: &Value| Fq::from_str(v.as_str().unwrap()).unwrap();
+        let g1 = |v: &Value| G1Affine::new(fq(&v[0]), fq(&v[1]));
+        let g2 = |v: &Value| {
+            G2Affine::new(
+                Fq2::new(fq(&v[0][0]), fq(&v[0][1])),
+                Fq2::new(fq(&v[1][0]), fq(&v[1][1])),
+            )
+        };
+        let proof = ark_groth16::Proof::<Bn254> {
+            a: g1(&proof_json["pi_a"]),
+            b: g2(&proof_json["pi_b"]),
+            c: g1(&proof_json["pi_c"]),
+        };
+        let inputs: Vec<Fr> = public_json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| Fr::from_str(v.as_str().unwrap()).unwrap())
+            .collect();
+        // the order used by `snarkjs zkey export soliditycalldata`
+        let u256 = |v: &Value| U256::from_dec_str(v.as_str().unwrap()).unwrap();
+        let expected = vec![
+            u256(&proof_json["pi_a"][0]),
+            u256(&proof_json["pi_a"][1]),
+            u256(&proof_json["pi_b"][0][1]),
+            u256(&proof_json["pi_b"][0][0]),
+            u256(&proof_json["pi_b"][1][1]),
+            u256(&proof_json["pi_b"][1][0]),
+            u256(&proof_json["pi_c"][0]),
+            u256(&proof_json["pi_c"][1]),
+            u256(&public_json[0]),
+        ];
+        let proof = Proof::from(proof);
+        assert_eq!(proof.as_calldata(&inputs), expected);
+        assert_eq!(
+            proof.as_calldata_with_order(&inputs, G2Order::C1First),
+            expected
+        );
+
+        // the order of proof.json
+        let mut expected = expected;
+        expected.swap(2, 3);
+        expected.swap(4, 5);
+        assert_eq!(
+            proof.as_calldata_with_order(&inputs, G2Order::C0First),
+            expected
+        );
+        let (_, b, _) = proof.as_tuple_with_order(G2Order::C0First);
+        assert_eq!(b, (proof.b.x, proof.b.y));
+
+        // as printed by `snarkjs zkey export soliditycalldata`
+        let expected = concat!(
+            r#"["0x0dc94ea9fccfba93e54bfab6f0fb3354c6faa156c2db760902f541b32a64a064", "#,
+            r#""0x1b69fcdc91dc3e98b9f4b3b667a849aae98160a36c913d63d9dcd1ca1e35080f"],"#,
+            r#"[["0x0e16ad41ab02f32980fbc4442a806ad95de4343d42f10d69197a97e6c3fa517d", "#,
+            r#""0x08934f8dd59a3b5e04d9603c7e40fa7150bcc0faad47c9f0ad57aa3379bedc09"],"#,
+            r#"["0x1d136b00a31a52564bcdeafbaf3569a44984f3eae6805ca5db9927917ab01bd9", "#,
+            r#""0x04165c34db638e7b6ef4d57c6d783b95d1f2d34eb9d5aebc56b6f7735c9b24ff"]],"#,
+            r#"["0x2dc56e89744bab3b7beedf6c005949bdd52dcc2c7afae9588ec34e5ad414db1d", "#,
+            r#""0x19293380e8e3f5c1f517f305308217a4febf4c986846bec36c6cb07cd7f13d3c"],"#,
+            r#"["0x2cb82c15880738d566e327fb4cee58930ca32b80bf4ea966a271ca25cdff4319"]"#,
+        );
+        assert_eq!(proof.to_hex_calldata(&inputs), expected);
+        assert_eq!(
+            Inputs::from(&inputs[..]).to_string(),
+            expected[expected.rfind(",[").unwrap() + 1..]
+        );
+    }
+
+    #[test]
+    fn serialize() {
+        let proof = ark_groth16::Proof::<Bn254> {
+            a: g1(),
+            b: g2(),
+            c: g1(),
+        };
+        let vk = ark_groth16::VerifyingKey::<Bn254> {
+            alpha_g1: g1(),
+            beta_g2: g2(),
+            gamma_g2: g2(),
+            delta_g2: g2(),
+            gamma_abc_g1: vec![g1(), G1Affine::identity()],
+        };
+        let inputs = vec![fr(), -fr()];
+        let (eth_proof, eth_vk) = (Proof::from(proof.clone()), VerifyingKey::from(vk.clone()));
+
+        for compress in [Compress::Yes, Compress::No] {
+            // the encodings are those of the Arkworks types
+            let mut bytes = vec![];
+            eth_proof.serialize_with_mode(&mut bytes, compress).unwrap();
+            let mut expected = vec![];
+            proof.serialize_with_mode(&mut expected, compress).unwrap();
+            assert_eq!(bytes, expected);
+            let decoded = Proof::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(decoded.unwrap(), eth_proof);
+
+            let mut bytes = vec![];
+            eth_vk.serialize_with_mode(&mut bytes, compress).unwrap();
+            let decoded = ark_groth16::VerifyingKey::<Bn254>::deserialize_with_mode(
+                &bytes[..],
+                compress,
+                Validate::Yes,
+            );
+            assert_eq!(decoded.unwrap(), vk);
+            let decoded = VerifyingKey::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert!(decoded.unwrap() == eth_vk);
+
+            let mut bytes = vec![];
+            Inputs::from(&inputs[..])
+                .serialize_with_mode(&mut bytes, compress)
+                .unwrap();
+            let decoded = Vec::<Fr>::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(decoded.unwrap(), inputs);
+            let decoded = Inputs::deserialize_with_mode(&bytes[..], compress, Validate::Yes);
+            assert_eq!(decoded.unwrap().0, Inputs::from(&inputs[..]).0);
+        }
+
+        // points which aren't on the curve are rejected
+        let mut bytes = vec![];
+        let invalid = G1 {
+            x: U256::from(1),
+            y: U256::from(3),
+        };
+        invalid.serialize_uncompressed(&mut bytes).unwrap();
+        assert!(G1::deserialize_uncompressed(&bytes[..]).is_err());
+        assert!(invalid.check().is_err());
+    }
+
     #[test]
     fn convert_proof() {
         let p = ark_groth16::Proof::<Bn254> {
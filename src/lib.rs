@@ -1,14 +1,44 @@
 //! Arkworks - Circom Compatibility layer
 //!
 //! Provides bindings to Circom's R1CS, for Groth16 Proof and Witness generation in Rust.
+mod error;
+pub use error::{Error, Result};
+
+mod convert;
+pub use convert::{bigint_to_fr, fr_to_bigint};
+
 mod witness;
-pub use witness::WitnessCalculator;
+#[cfg(feature = "zeroize")]
+pub use witness::ZeroizingWitness;
+pub use witness::{
+    decode_fr, encode_fr, read_wtns, read_wtns_for_circuit, signal_hash, write_wtns, Compiler,
+    MemoryError, ModuleCache, WitnessCalculator, WitnessCalculatorModule, WitnessCalculatorPool,
+};
 
 pub mod circom;
-pub use circom::{CircomBuilder, CircomCircuit, CircomConfig, CircomReduction};
+pub use circom::{
+    map_public_outputs, CircomBuilder, CircomCircuit, CircomConfig, CircomReduction, CircuitStats,
+    InputSignal, InputValidationError, IntoCircomInput, StreamingCircomCircuit, SymbolTable,
+};
 
 #[cfg(feature = "ethereum")]
 pub mod ethereum;
 
+pub mod snarkjs;
+
+mod prover;
+pub use prover::{
+    batch_verify, check_zkey_matches_r1cs, find_invalid_proof, generate_test_keys,
+    prepare_verifying_key, prove, prove_with_matrices, read_proof, verify, verify_from_zkey,
+    verify_with_prepared, write_proof,
+};
+
 mod zkey;
-pub use zkey::read_zkey;
+#[cfg(feature = "mmap")]
+pub use zkey::read_zkey_mmap;
+pub use zkey::{
+    read_verifying_key, read_zkey, read_zkey_at, read_zkey_coefficients, read_zkey_contributions,
+    read_zkey_from_reader, read_zkey_keys, read_zkey_metadata, read_zkey_sections, write_zkey,
+    ZKeyCoefficient, ZKeyContribution, ZKeyContributions, ZKeyHeader, ZKeyMetadata, ZKeySection,
+    ZKeyWindow,
+};
@@ -0,0 +1,283 @@
+//! Groth16 proving and verification over BN254 in a single call, for circuits whose
+//! proving key comes from a snarkjs zkey. Use [`CircomBuilder`] and [`CircomCircuit`]
+//! directly for control over e.g. the rng, the curve or the setup.
+//!
+//! [`CircomCircuit`]: crate::CircomCircuit
+use ark_bn254::{Bn254, Fr, G1Projective};
+use ark_crypto_primitives::snark::SNARK;
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::Zero;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::{ConstraintMatrices, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use ark_std::{
+    rand::{thread_rng, CryptoRng, Rng, RngCore},
+    UniformRand,
+};
+use num_bigint::BigInt;
+use std::io::{Read, Seek, Write};
+
+use crate::{
+    read_verifying_key, CircomBuilder, CircomCircuit, CircomConfig, CircomReduction, Error, Result,
+    WitnessCalculator,
+};
+
+/// Computes the witness for the inputs and proves it, returning the proof along with
+/// the public inputs to verify it with. A witness which doesn't satisfy the circuit is
+/// reported as [`Error::UnsatisfiedConstraint`] instead of producing an invalid proof.
+///
+/// [`Error::UnsatisfiedConstraint`]: crate::Error::UnsatisfiedConstraint
+pub fn prove(
+    config: CircomConfig<Fr>,
+    inputs: impl IntoIterator<Item = (String, Vec<BigInt>)>,
+    pk: &ProvingKey<Bn254>,
+) -> Result<(Proof<Bn254>, Vec<Fr>)> {
+    let mut builder = CircomBuilder::new(config);
+    builder.inputs.extend(inputs);
+
+    let circom = builder.build()?;
+    circom.check_witness_satisfaction()?;
+    let public_inputs = circom
+        .get_public_inputs()
+        .ok_or(SynthesisError::AssignmentMissing)?;
+    let proof = circom.prove(pk, &mut thread_rng())?;
+    Ok((proof, public_inputs))
+}
+
+/// Same as [`prove`], without the circuit's R1CS: the witness is computed by the WASM
+/// alone, and proven with the constraint matrices of the zkey, as read by
+/// [`read_zkey`](crate::read_zkey). As there's no R1CS to check the witness against,
+/// a witness which doesn't satisfy the circuit results in an invalid proof.
+///
+/// The matrices must be those of the key, see [`check_zkey_matches_r1cs`], and the
+/// WASM must compute a witness with a value for each of their wires.
+pub fn prove_with_matrices(
+    wtns: &mut WitnessCalculator,
+    inputs: impl IntoIterator<Item = (String, Vec<BigInt>)>,
+    pk: &ProvingKey<Bn254>,
+    matrices: &ConstraintMatrices<Fr>,
+) -> Result<(Proof<Bn254>, Vec<Fr>)> {
+    check_zkey_matches_r1cs(pk, matrices)?;
+    let num_inputs = matrices.num_instance_variables;
+
+    let witness = wtns.calculate_witness_fr::<Fr, _>(inputs, false)?;
+    // the queries have an entry for every wire
+    if witness.len() != pk.a_query.len() {
+        return Err(Error::ProvingKeyMismatch {
+            pk_wires: pk.a_query.len(),
+            pk_public: pk.vk.gamma_abc_g1.len().saturating_sub(1),
+            wires: witness.len(),
+            public: num_inputs.saturating_sub(1),
+        });
+    }
+    // the constant one is followed by the public signals
+    let public_inputs = witness
+        .get(1..num_inputs)
+        .ok_or_else(|| {
+            Error::InvalidInput(
+                "the matrices have no instance variable for the constant one".to_string(),
+            )
+        })?
+        .to_vec();
+
+    let mut rng = thread_rng();
+    let (r, s) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+    let proof = Groth16::<Bn254, CircomReduction>::create_proof_with_reduction_and_matrices(
+        pk,
+        r,
+        s,
+        matrices,
+        num_inputs,
+        matrices.num_constraints,
+        &witness,
+    )?;
+    Ok((proof, public_inputs))
+}
+
+/// Generates a proving and verifying key for the circuit with a circuit-specific setup
+/// run by a single party, e.g. to prove end to end in tests without a zkey fixture.
+///
+/// **Not for production**: whoever knows the rng's output, i.e. the setup's toxic
+/// waste, can forge proofs for any public inputs. Production keys come from a trusted
+/// setup ceremony, e.g. a snarkjs zkey read by [`read_zkey`](crate::read_zkey).
+///
+/// Only the circuit's constraints are used, so it doesn't need a witness, e.g. as
+/// returned by [`CircomBuilder::setup`]. The keys are for snarkjs' witness map, as
+/// used by [`CircomCircuit::prove`].
+pub fn generate_test_keys(
+    circom: &CircomCircuit<Fr>,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>)> {
+    Ok(Groth16::<Bn254, CircomReduction>::circuit_specific_setup(
+        circom.clone(),
+        rng,
+    )?)
+}
+
+/// Checks that the proving key was generated for the circuit of the constraint
+/// matrices, e.g. those of its R1CS as returned by [`CircomCircuit::matrices`], so
+/// that a zkey and R1CS of different circuits are caught when loading them rather than
+/// by failing verifications. The wires, public signals and evaluation domain, which
+/// the number of constraints determines, are compared.
+///
+/// [`CircomCircuit::matrices`]: crate::CircomCircuit::matrices
+pub fn check_zkey_matches_r1cs<E: Pairing>(
+    pk: &ProvingKey<E>,
+    matrices: &ConstraintMatrices<E::ScalarField>,
+) -> Result<()> {
+    // the queries have an entry for every wire, and the IC one for the constant one
+    // followed by the public signals
+    let pk_wires = pk.a_query.len();
+    let pk_public = pk.vk.gamma_abc_g1.len().saturating_sub(1);
+    let wires = matrices.num_instance_variables + matrices.num_witness_variables;
+    let public = matrices.num_instance_variables.saturating_sub(1);
+    if pk_wires != wires || pk_public != public {
+        return Err(Error::ProvingKeyMismatch {
+            pk_wires,
+            pk_public,
+            wires,
+            public,
+        });
+    }
+
+    // snarkjs' H query has a point for every element of the domain, Arkworks' one less.
+    // The domain also holds a constraint per public signal and the constant one.
+    let pk_domain_size = pk.h_query.len().next_power_of_two();
+    let domain_size =
+        (matrices.num_constraints + matrices.num_instance_variables).next_power_of_two();
+    if pk_domain_size != domain_size {
+        return Err(Error::DomainSizeMismatch {
+            pk_domain_size,
+            domain_size,
+        });
+    }
+    Ok(())
+}
+
+/// Verifies a proof created by [`prove`], or by snarkjs for the same zkey. The key is
+/// prepared for every call, see [`verify_with_prepared`] to verify many proofs.
+pub fn verify(
+    vk: &VerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<bool> {
+    verify_with_prepared(&prepare_verifying_key(vk), proof, public_inputs)
+}
+
+/// Precomputes the pairing `e(alpha, beta)` and the Miller loop coefficients of `gamma`
+/// and `delta`, which every verification against the key needs
+pub fn prepare_verifying_key(vk: &VerifyingKey<Bn254>) -> PreparedVerifyingKey<Bn254> {
+    ark_groth16::prepare_verifying_key(vk)
+}
+
+/// Same as [`verify`], with a key prepared once by [`prepare_verifying_key`]
+pub fn verify_with_prepared(
+    pvk: &PreparedVerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<bool> {
+    Ok(Groth16::<Bn254>::verify_with_processed_vk(
+        pvk,
+        public_inputs,
+        proof,
+    )?)
+}
+
+/// Same as [`verify`], with the verifying key read from a snarkjs zkey. Only the
+/// key's header and IC sections are read, see [`read_verifying_key`].
+///
+/// [`read_verifying_key`]: crate::read_verifying_key
+pub fn verify_from_zkey<R: Read + Seek>(
+    zkey: &mut R,
+    proof: &Proof<Bn254>,
+    public_inputs: &[Fr],
+) -> Result<bool> {
+    let vk = read_verifying_key::<Bn254, _>(zkey)?;
+    verify(&vk, proof, public_inputs)
+}
+
+/// Writes the proof in Arkworks' canonical encoding straight to the writer, e.g. a
+/// socket. Compressed proofs take 128 bytes instead of 256, but are slower to read as
+/// the points' coordinates have to be recovered.
+pub fn write_proof<W: Write>(
+    proof: &Proof<Bn254>,
+    writer: &mut W,
+    compress: Compress,
+) -> Result<()> {
+    Ok(proof.serialize_with_mode(writer, compress)?)
+}
+
+/// Reads a proof written by [`write_proof`] with the same `compress` mode. The points
+/// are checked to be on the curve and in the right subgroup.
+pub fn read_proof<R: Read>(reader: &mut R, compress: Compress) -> Result<Proof<Bn254>> {
+    Ok(Proof::deserialize_with_mode(
+        reader,
+        compress,
+        Validate::Yes,
+    )?)
+}
+
+/// Verifies many proofs for the same verifying key at once, which is much cheaper than
+/// verifying them one by one: the `4n` pairings become a single multi-pairing of `n + 3`.
+///
+/// The proofs' equations are combined with random coefficients, so an invalid proof
+/// can't be compensated by the others. Returns `false` if any proof is invalid, see
+/// [`find_invalid_proof`] to find out which.
+pub fn batch_verify(vk: &VerifyingKey<Bn254>, proofs: &[(Proof<Bn254>, Vec<Fr>)]) -> Result<bool> {
+    let mut rng = thread_rng();
+    let mut a = Vec::with_capacity(proofs.len() + 3);
+    let mut b = Vec::with_capacity(proofs.len() + 3);
+    let (mut r_sum, mut inputs_sum, mut c_sum) =
+        (Fr::zero(), G1Projective::zero(), G1Projective::zero());
+
+    for (proof, public_inputs) in proofs {
+        if public_inputs.len() + 1 != vk.gamma_abc_g1.len() {
+            return Err(SynthesisError::MalformedVerifyingKey.into());
+        }
+        // 128 bits of randomness are enough for a negligible chance of a false positive
+        let r = Fr::from(rng.gen::<u128>());
+        let inputs = public_inputs
+            .iter()
+            .zip(&vk.gamma_abc_g1[1..])
+            .fold(vk.gamma_abc_g1[0].into_group(), |acc, (x, ic)| {
+                acc + *ic * x
+            });
+
+        // e(r * A, B) = e(r * alpha, beta) * e(r * inputs, gamma) * e(r * C, delta)
+        a.push((proof.a * r).into_affine());
+        b.push(proof.b);
+        r_sum += r;
+        inputs_sum += inputs * r;
+        c_sum += proof.c * r;
+    }
+
+    a.extend(G1Projective::normalize_batch(&[
+        -(vk.alpha_g1 * r_sum),
+        -inputs_sum,
+        -c_sum,
+    ]));
+    b.extend([vk.beta_g2, vk.gamma_g2, vk.delta_g2]);
+    Ok(Bn254::multi_pairing(a, b).is_zero())
+}
+
+/// Returns the index of the first invalid proof, or `None` if they are all valid. The
+/// proofs are verified in batches which are halved until the invalid proof is found,
+/// so this is about as cheap as [`batch_verify`] when all proofs are valid.
+pub fn find_invalid_proof(
+    vk: &VerifyingKey<Bn254>,
+    proofs: &[(Proof<Bn254>, Vec<Fr>)],
+) -> Result<Option<usize>> {
+    if batch_verify(vk, proofs)? {
+        return Ok(None);
+    }
+    if proofs.len() == 1 {
+        return Ok(Some(0));
+    }
+
+    let (left, right) = proofs.split_at(proofs.len() / 2);
+    if let Some(i) = find_invalid_proof(vk, left)? {
+        return Ok(Some(i));
+    }
+    // otherwise the invalid proof is in the right half
+    Ok(find_invalid_proof(vk, right)?.map(|i| left.len() + i))
+}
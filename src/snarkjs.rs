@@ -0,0 +1,303 @@
+//! Conversions between Arkworks' Groth16 types and snarkjs' `proof.json`,
+//! `verification_key.json` and `public.json` files
+//!
+//! Field elements are encoded as decimal strings, and points in projective
+//! coordinates with `z = 1`, or `z = 0` for the point at infinity.
+use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::{
+    pairing::Pairing,
+    short_weierstrass::{Affine, SWCurveConfig},
+    AffineRepr,
+};
+use ark_ff::{One, PrimeField, Zero};
+use ark_groth16::{Proof, VerifyingKey};
+use num_bigint::BigUint;
+use serde_json::{json, Value};
+
+use crate::{fr_to_bigint, Error, Result};
+
+/// Encodes the proof as in snarkjs' `proof.json`
+pub fn proof_to_json(proof: &Proof<Bn254>) -> Value {
+    json!({
+        "pi_a": g1_to_json(&proof.a),
+        "pi_b": g2_to_json(&proof.b),
+        "pi_c": g1_to_json(&proof.c),
+        "protocol": "groth16",
+        "curve": "bn128",
+    })
+}
+
+/// Decodes a proof from snarkjs' `proof.json`
+pub fn proof_from_json(value: &Value) -> Result<Proof<Bn254>> {
+    check_protocol(value)?;
+    Ok(Proof {
+        a: g1_from_json(field(value, "pi_a")?)?,
+        b: g2_from_json(field(value, "pi_b")?)?,
+        c: g1_from_json(field(value, "pi_c")?)?,
+    })
+}
+
+/// Encodes the verifying key as in snarkjs' `verification_key.json`
+pub fn verifying_key_to_json(vk: &VerifyingKey<Bn254>) -> Value {
+    let alphabeta = Bn254::pairing(vk.alpha_g1, vk.beta_g2).0;
+    let fq6_to_json =
+        |c: &ark_bn254::Fq6| json!([fq2_to_json(&c.c0), fq2_to_json(&c.c1), fq2_to_json(&c.c2)]);
+
+    json!({
+        "protocol": "groth16",
+        "curve": "bn128",
+        "nPublic": vk.gamma_abc_g1.len().saturating_sub(1),
+        "vk_alpha_1": g1_to_json(&vk.alpha_g1),
+        "vk_beta_2": g2_to_json(&vk.beta_g2),
+        "vk_gamma_2": g2_to_json(&vk.gamma_g2),
+        "vk_delta_2": g2_to_json(&vk.delta_g2),
+        "vk_alphabeta_12": [fq6_to_json(&alphabeta.c0), fq6_to_json(&alphabeta.c1)],
+        "IC": vk.gamma_abc_g1.iter().map(g1_to_json).collect::<Vec<_>>(),
+    })
+}
+
+/// Decodes a verifying key from snarkjs' `verification_key.json`. The precomputed
+/// `vk_alphabeta_12` pairing isn't part of Arkworks' key and is ignored.
+pub fn verifying_key_from_json(value: &Value) -> Result<VerifyingKey<Bn254>> {
+    check_protocol(value)?;
+    let gamma_abc_g1 = array(field(value, "IC")?)?
+        .iter()
+        .map(g1_from_json)
+        .collect::<Result<Vec<_>>>()?;
+
+    // the first point is the constant one's, followed by one per public signal
+    let n_points = gamma_abc_g1.len() as u64;
+    let Some(num_public) = n_points.checked_sub(1) else {
+        return Err(invalid("IC has no points".to_string()));
+    };
+    if let Some(n_public) = value.get("nPublic") {
+        if n_public.as_u64() != Some(num_public) {
+            return Err(invalid(format!(
+                "nPublic {} doesn't match the {} IC points",
+                n_public,
+                gamma_abc_g1.len()
+            )));
+        }
+    }
+
+    Ok(VerifyingKey {
+        alpha_g1: g1_from_json(field(value, "vk_alpha_1")?)?,
+        beta_g2: g2_from_json(field(value, "vk_beta_2")?)?,
+        gamma_g2: g2_from_json(field(value, "vk_gamma_2")?)?,
+        delta_g2: g2_from_json(field(value, "vk_delta_2")?)?,
+        gamma_abc_g1,
+    })
+}
+
+/// Encodes the public inputs as in snarkjs' `public.json`
+pub fn public_inputs_to_json(inputs: &[Fr]) -> Value {
+    inputs.iter().map(fe_to_json).collect()
+}
+
+/// Decodes the public inputs from snarkjs' `public.json`
+pub fn public_inputs_from_json(value: &Value) -> Result<Vec<Fr>> {
+    array(value)?.iter().map(fe_from_json).collect()
+}
+
+fn check_protocol(value: &Value) -> Result<()> {
+    for (name, expected) in [("protocol", "groth16"), ("curve", "bn128")] {
+        match value.get(name) {
+            Some(Value::String(s)) if s == expected => {}
+            // both are optional, but must match if present
+            None => {}
+            Some(other) => {
+                return Err(invalid(format!(
+                    "Unsupported {} {}, expected {}",
+                    name, other, expected
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn g1_to_json(p: &G1Affine) -> Value {
+    if p.infinity {
+        json!(["0", "1", "0"])
+    } else {
+        json!([fe_to_json(&p.x), fe_to_json(&p.y), "1"])
+    }
+}
+
+fn g2_to_json(p: &G2Affine) -> Value {
+    if p.infinity {
+        json!([["0", "0"], ["1", "0"], ["0", "0"]])
+    } else {
+        json!([fq2_to_json(&p.x), fq2_to_json(&p.y), ["1", "0"]])
+    }
+}
+
+fn fq2_to_json(fe: &Fq2) -> Value {
+    json!([fe_to_json(&fe.c0), fe_to_json(&fe.c1)])
+}
+
+fn fe_to_json<F: PrimeField>(fe: &F) -> Value {
+    Value::String(fr_to_bigint(fe).to_string())
+}
+
+fn g1_from_json(value: &Value) -> Result<G1Affine> {
+    let (x, y, z) = coordinates(value)?;
+    let z: Fq = fe_from_json(z)?;
+    if z.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+    if !z.is_one() {
+        return Err(invalid(format!("G1 point {} is not normalized", value)));
+    }
+
+    let p = G1Affine::new_unchecked(fe_from_json(x)?, fe_from_json(y)?);
+    check_point(p, value)
+}
+
+fn g2_from_json(value: &Value) -> Result<G2Affine> {
+    let (x, y, z) = coordinates(value)?;
+    let z = fq2_from_json(z)?;
+    if z.is_zero() {
+        return Ok(G2Affine::zero());
+    }
+    if !z.is_one() {
+        return Err(invalid(format!("G2 point {} is not normalized", value)));
+    }
+
+    let p = G2Affine::new_unchecked(fq2_from_json(x)?, fq2_from_json(y)?);
+    check_point(p, value)
+}
+
+fn check_point<P: SWCurveConfig>(p: Affine<P>, value: &Value) -> Result<Affine<P>> {
+    if p.is_on_curve() && p.is_in_correct_subgroup_assuming_on_curve() {
+        Ok(p)
+    } else {
+        Err(invalid(format!("{} is not a valid curve point", value)))
+    }
+}
+
+fn coordinates(value: &Value) -> Result<(&Value, &Value, &Value)> {
+    match array(value)? {
+        [x, y, z] => Ok((x, y, z)),
+        _ => Err(invalid(format!("Expected 3 coordinates, got {}", value))),
+    }
+}
+
+fn fq2_from_json(value: &Value) -> Result<Fq2> {
+    match array(value)? {
+        [c0, c1] => Ok(Fq2::new(fe_from_json(c0)?, fe_from_json(c1)?)),
+        _ => Err(invalid(format!("Expected an Fq2 element, got {}", value))),
+    }
+}
+
+fn fe_from_json<F: PrimeField>(value: &Value) -> Result<F> {
+    let fe = value
+        .as_str()
+        .and_then(|s| s.parse::<BigUint>().ok())
+        .filter(|fe| *fe < F::MODULUS.into())
+        .ok_or_else(|| invalid(format!("{} is not a field element", value)))?;
+    Ok(F::from(fe))
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value> {
+    value
+        .get(name)
+        .ok_or_else(|| invalid(format!("Missing field {}", name)))
+}
+
+fn array(value: &Value) -> Result<&[Value]> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| invalid(format!("Expected an array, got {}", value)))
+}
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidJson(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{read_zkey, CircomBuilder, CircomConfig};
+    use ark_crypto_primitives::snark::SNARK;
+    use ark_groth16::Groth16;
+    use std::fs::File;
+
+    fn read_json(path: &str) -> Value {
+        serde_json::from_reader(File::open(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn roundtrip_snarkjs() {
+        let json = read_json("./test-vectors/verification_key.json");
+        let vk = verifying_key_from_json(&json).unwrap();
+        assert_eq!(vk.gamma_abc_g1.len(), 2);
+        assert_eq!(verifying_key_to_json(&vk), json);
+
+        let json = read_json("./test-vectors/proof.json");
+        let proof = proof_from_json(&json).unwrap();
+        assert_eq!(proof_to_json(&proof), json);
+
+        let json = read_json("./test-vectors/public.json");
+        let inputs = public_inputs_from_json(&json).unwrap();
+        assert_eq!(public_inputs_to_json(&inputs), json);
+    }
+
+    #[test]
+    fn empty_ic() {
+        let mut json = read_json("./test-vectors/verification_key.json");
+        json["IC"] = json!([]);
+        for n_public in [json!(0), json!(1)] {
+            json["nPublic"] = n_public;
+            let err = verifying_key_from_json(&json).unwrap_err();
+            assert!(matches!(err, crate::Error::InvalidJson(_)), "{}", err);
+        }
+        json.as_object_mut().unwrap().remove("nPublic");
+        verifying_key_from_json(&json).unwrap_err();
+    }
+
+    #[test]
+    fn prove_and_verify() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (params, _) = read_zkey(&mut file).unwrap();
+
+        let cfg = CircomConfig::<Fr>::new(
+            "./test-vectors/mycircuit.wasm",
+            "./test-vectors/mycircuit.r1cs",
+        )
+        .unwrap();
+        let mut builder = CircomBuilder::new(cfg);
+        builder.push_input("a", 3);
+        builder.push_input("b", 11);
+        let circom = builder.build().unwrap();
+        let inputs = circom.get_public_inputs().unwrap();
+        let proof = circom.prove_with_seed(&params, 0).unwrap();
+
+        let proof = proof_from_json(&proof_to_json(&proof)).unwrap();
+        let vk = verifying_key_from_json(&verifying_key_to_json(&params.vk)).unwrap();
+        let inputs = public_inputs_from_json(&public_inputs_to_json(&inputs)).unwrap();
+        assert!(Groth16::<Bn254>::verify(&vk, &inputs, &proof).unwrap());
+    }
+
+    #[test]
+    fn invalid() {
+        let mut json = read_json("./test-vectors/proof.json");
+        json["curve"] = json!("bls12381");
+        assert!(proof_from_json(&json).is_err());
+
+        // the generator's y coordinate is 2
+        let point = json!(["1", "3", "1"]);
+        assert!(g1_from_json(&point).is_err());
+        assert!(g1_from_json(&json!(["1", "2", "1"])).is_ok());
+        assert!(g1_from_json(&json!(["1", "2"])).is_err());
+        assert!(g1_from_json(&json!(["0x1", "2", "1"])).is_err());
+        assert_eq!(
+            g1_from_json(&json!(["0", "1", "0"])).unwrap(),
+            G1Affine::zero()
+        );
+
+        let modulus = BigUint::from(Fq::MODULUS).to_string();
+        assert!(fe_from_json::<Fq>(&json!(modulus)).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Cache of compiled witness calculator modules
+use super::{WitnessCalculator, WitnessCalculatorModule};
+use crate::Result;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+use wasmer::{Engine, Module, Store};
+
+/// Caches compiled [`WitnessCalculatorModule`]s keyed by the SHA-256 of their WASM
+/// bytes, so loading the same circuit again skips the compilation.
+///
+/// The cache can be shared across threads, e.g. created once at startup and wrapped
+/// in an `Arc`. With [`ModuleCache::with_dir`], compiled modules are also persisted
+/// in wasmer's serialized format, so they survive restarts.
+#[derive(Debug)]
+pub struct ModuleCache {
+    engine: Engine,
+    modules: Mutex<HashMap<[u8; 32], WitnessCalculatorModule>>,
+    dir: Option<PathBuf>,
+}
+
+impl Default for ModuleCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModuleCache {
+    /// Creates an in-memory cache
+    pub fn new() -> Self {
+        Self {
+            engine: Store::default().engine().clone(),
+            modules: Mutex::new(HashMap::new()),
+            dir: None,
+        }
+    }
+
+    /// Creates a cache which also stores the compiled modules in `dir`.
+    ///
+    /// # Safety
+    ///
+    /// The serialized modules are native code which is loaded without validation, so
+    /// `dir` must only be writable by trusted users.
+    pub unsafe fn with_dir(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir: Some(dir),
+            ..Self::new()
+        })
+    }
+
+    /// Returns the compiled module for the WASM bytes, compiling it on a cache miss
+    pub fn module(&self, wasm: &[u8]) -> Result<WitnessCalculatorModule> {
+        let hash: [u8; 32] = Sha256::digest(wasm).into();
+        if let Some(module) = self.modules.lock().unwrap().get(&hash) {
+            return Ok(module.clone());
+        }
+
+        // compile outside of the lock, at worst a module is compiled twice
+        let module = self.load_or_compile(&hash, wasm)?;
+        let module = WitnessCalculatorModule::new(self.engine.clone(), module);
+        self.modules.lock().unwrap().insert(hash, module.clone());
+        Ok(module)
+    }
+
+    /// Instantiates a [`WitnessCalculator`] from the cached module for the WASM bytes
+    pub fn calculator(&self, wasm: &[u8]) -> Result<WitnessCalculator> {
+        self.module(wasm)?.instantiate()
+    }
+
+    fn load_or_compile(&self, hash: &[u8; 32], wasm: &[u8]) -> Result<Module> {
+        span!("compile");
+        let path = match &self.dir {
+            Some(dir) => dir.join(format!("{}.wasmu", hex::encode(hash))),
+            None => return Ok(Module::new(&self.engine, wasm)?),
+        };
+
+        if path.exists() {
+            // safe as the directory is trusted, see `with_dir`
+            let module = unsafe { Module::deserialize_from_file(&self.engine, &path) };
+            // fall back to recompiling e.g. artifacts of another wasmer version
+            if let Ok(module) = module {
+                return Ok(module);
+            }
+        }
+
+        let module = Module::new(&self.engine, wasm)?;
+        // write to a temporary file first, so concurrent loads never see a partial artifact
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        module.serialize_to_file(&tmp)?;
+        std::fs::rename(tmp, path)?;
+        Ok(module)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+    use std::{collections::HashMap, time::Instant};
+
+    fn witness(calculator: &mut WitnessCalculator) -> Vec<BigInt> {
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+        calculator.calculate_witness(inputs, false).unwrap()
+    }
+
+    #[test]
+    fn cached() {
+        let wasm = std::fs::read("./test-vectors/mycircuit.wasm").unwrap();
+        let cache = ModuleCache::new();
+
+        let start = Instant::now();
+        let mut first = cache.calculator(&wasm).unwrap();
+        let compiled = start.elapsed();
+
+        let start = Instant::now();
+        let mut second = cache.calculator(&wasm).unwrap();
+        let cached = start.elapsed();
+
+        assert!(cached < compiled, "{:?} >= {:?}", cached, compiled);
+        assert_eq!(witness(&mut first), witness(&mut second));
+        assert_eq!(cache.modules.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn persisted() {
+        let wasm = std::fs::read("./test-vectors/mycircuit.wasm").unwrap();
+        let dir = std::env::temp_dir().join(format!("ark-circom-cache-{}", std::process::id()));
+
+        let cache = unsafe { ModuleCache::with_dir(&dir) }.unwrap();
+        let expected = witness(&mut cache.calculator(&wasm).unwrap());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        // a new cache loads the module from the directory
+        let cache = unsafe { ModuleCache::with_dir(&dir) }.unwrap();
+        assert_eq!(witness(&mut cache.calculator(&wasm).unwrap()), expected);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
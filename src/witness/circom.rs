@@ -1,7 +1,12 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
 
-use color_eyre::Result;
-use wasmer::{Function, Instance, Store, Value};
+use super::MemoryError;
+use crate::{Error, Result};
+#[cfg(feature = "circom-2")]
+use num_bigint::BigInt;
+#[cfg(feature = "circom-2")]
+use num_traits::Zero;
+use wasmer::{Function, Instance, Memory, Store, Value};
 
 #[derive(Clone, Debug)]
 pub struct WasmInstance {
@@ -11,7 +16,9 @@ pub struct WasmInstance {
 
 pub trait CircomBase {
     fn init(&self, sanity_check: bool) -> Result<()>;
-    fn func(&self, name: &str) -> &Function;
+    /// Looks up an exported function, which may be missing in WASM modules
+    /// compiled by other Circom versions
+    fn try_func(&self, name: &str) -> Result<&Function>;
     fn get_ptr_witness_buffer(&self) -> Result<u32>;
     fn get_ptr_witness(&self, w: u32) -> Result<u32>;
     fn get_n_vars(&self) -> Result<u32>;
@@ -36,11 +43,33 @@ pub trait Circom {
 pub trait Circom2 {
     fn get_field_num_len32(&self) -> Result<u32>;
     fn get_raw_prime(&self) -> Result<()>;
-    fn read_shared_rw_memory(&self, i: u32) -> Result<u32>;
+    /// Returns the field's prime, which `get_raw_prime` writes to the shared memory
+    /// as little-endian 32-bit limbs
+    fn read_raw_prime(&self) -> Result<BigInt> {
+        self.get_raw_prime()?;
+        let limbs = self.read_shared_rw_memory_array(0, self.get_field_num_len32()?)?;
+        let prime = limbs
+            .iter()
+            .rev()
+            .fold(BigInt::zero(), |prime, limb| (prime << 32) + *limb);
+        Ok(prime)
+    }
+    /// Reads `count` consecutive u32s of the shared memory from `start`, taking the store
+    /// lock only once
+    fn read_shared_rw_memory_array(&self, start: u32, count: u32) -> Result<Vec<u32>>;
     fn write_shared_rw_memory(&self, i: u32, v: u32) -> Result<()>;
     fn set_input_signal(&self, hmsb: u32, hlsb: u32, pos: u32) -> Result<()>;
-    fn get_witness(&self, i: u32) -> Result<()>;
+    // Only exported by newer Circom 2 versions, `getVersion` being the major version
+    fn get_minor_version(&self) -> Result<u32>;
+    fn get_patch_version(&self) -> Result<u32>;
+    // Only exported since Circom 2.1, negative for unknown signals
+    fn get_input_signal_size(&self, hmsb: u32, hlsb: u32) -> Result<i32>;
     fn get_witness_size(&self) -> Result<u32>;
+    /// Reads the first `size` values of the witness, each as `limbs` little-endian u32s,
+    /// holding the store lock throughout. The module isn't reentrant, as `getWitness`
+    /// copies each value to the single shared memory, so the reads can't be split
+    /// across threads.
+    fn read_witness_limbs(&self, size: u32, limbs: u32) -> Result<Vec<u32>>;
 }
 
 impl Circom for WasmInstance {
@@ -60,49 +89,78 @@ impl Circom2 for WasmInstance {
     }
 
     fn get_raw_prime(&self) -> Result<()> {
-        let func = self.func("getRawPrime");
-        let mut store = self.store.write().unwrap();
+        let func = self.try_func("getRawPrime")?;
+        let mut store = self.lock_store()?;
         func.call(&mut store, &[])?;
         Ok(())
     }
 
-    fn read_shared_rw_memory(&self, i: u32) -> Result<u32> {
-        let func = self.func("readSharedRWMemory");
-        let mut store = self.store.write().unwrap();
-        let result = func.call(&mut store, &[i.into()])?;
-        Ok(result[0].unwrap_i32() as u32)
+    fn read_shared_rw_memory_array(&self, start: u32, count: u32) -> Result<Vec<u32>> {
+        let mut store = self.lock_store()?;
+        let func = self
+            .try_func("readSharedRWMemory")?
+            .typed::<u32, u32>(&store)?;
+        (start..start + count)
+            .map(|i| Ok(func.call(&mut store, i)?))
+            .collect()
     }
 
     fn write_shared_rw_memory(&self, i: u32, v: u32) -> Result<()> {
-        let func = self.func("writeSharedRWMemory");
-        let mut store = self.store.write().unwrap();
+        let func = self.try_func("writeSharedRWMemory")?;
+        let mut store = self.lock_store()?;
         func.call(&mut store, &[i.into(), v.into()])?;
         Ok(())
     }
 
     fn set_input_signal(&self, hmsb: u32, hlsb: u32, pos: u32) -> Result<()> {
-        let func = self.func("setInputSignal");
-        let mut store = self.store.write().unwrap();
+        let func = self.try_func("setInputSignal")?;
+        let mut store = self.lock_store()?;
         func.call(&mut store, &[hmsb.into(), hlsb.into(), pos.into()])?;
         Ok(())
     }
 
-    fn get_witness(&self, i: u32) -> Result<()> {
-        let func = self.func("getWitness");
-        let mut store = self.store.write().unwrap();
-        func.call(&mut store, &[i.into()])?;
-        Ok(())
+    fn get_minor_version(&self) -> Result<u32> {
+        self.get_u32("getMinorVersion")
+    }
+
+    fn get_patch_version(&self) -> Result<u32> {
+        self.get_u32("getPatchVersion")
+    }
+
+    fn get_input_signal_size(&self, hmsb: u32, hlsb: u32) -> Result<i32> {
+        let func = self.try_func("getInputSignalSize")?;
+        let mut store = self.lock_store()?;
+        let result = func.call(&mut store, &[hmsb.into(), hlsb.into()])?;
+        single_i32("getInputSignalSize", &result)
     }
 
     fn get_witness_size(&self) -> Result<u32> {
         self.get_u32("getWitnessSize")
     }
+
+    fn read_witness_limbs(&self, size: u32, limbs: u32) -> Result<Vec<u32>> {
+        let mut store = self.lock_store()?;
+        let get_witness = self.try_func("getWitness")?.typed::<u32, ()>(&store)?;
+        let read = self
+            .try_func("readSharedRWMemory")?
+            .typed::<u32, u32>(&store)?;
+
+        let mut values = Vec::with_capacity(size as usize * limbs as usize);
+        for i in 0..size {
+            get_witness.call(&mut store, i)?;
+            for j in 0..limbs {
+                values.push(read.call(&mut store, j)?);
+            }
+        }
+        Ok(values)
+    }
 }
 
 impl CircomBase for WasmInstance {
     fn init(&self, sanity_check: bool) -> Result<()> {
-        let func = self.func("init");
-        let mut store = self.store.write().unwrap();
+        span!("init");
+        let func = self.try_func("init")?;
+        let mut store = self.lock_store()?;
         func.call(&mut store, &[Value::I32(sanity_check as i32)])?;
         Ok(())
     }
@@ -112,11 +170,10 @@ impl CircomBase for WasmInstance {
     }
 
     fn get_ptr_witness(&self, w: u32) -> Result<u32> {
-        let func = self.func("getPWitness");
-        let mut store = self.store.write().unwrap();
-        let res = func.call(&mut store, &[w.into()])?;
-
-        Ok(res[0].unwrap_i32() as u32)
+        let func = self.try_func("getPWitness")?;
+        let mut store = self.lock_store()?;
+        let result = func.call(&mut store, &[w.into()])?;
+        Ok(single_i32("getPWitness", &result)? as u32)
     }
 
     fn get_n_vars(&self) -> Result<u32> {
@@ -130,8 +187,8 @@ impl CircomBase for WasmInstance {
         hash_msb: u32,
         hash_lsb: u32,
     ) -> Result<()> {
-        let func = self.func("getSignalOffset32");
-        let mut store = self.store.write().unwrap();
+        let func = self.try_func("getSignalOffset32")?;
+        let mut store = self.lock_store()?;
         func.call(
             &mut store,
             &[
@@ -146,8 +203,8 @@ impl CircomBase for WasmInstance {
     }
 
     fn set_signal(&self, c_idx: u32, component: u32, signal: u32, p_val: u32) -> Result<()> {
-        let func = self.func("setSignal");
-        let mut store = self.store.write().unwrap();
+        let func = self.try_func("setSignal")?;
+        let mut store = self.lock_store()?;
         func.call(
             &mut store,
             &[c_idx.into(), component.into(), signal.into(), p_val.into()],
@@ -158,25 +215,33 @@ impl CircomBase for WasmInstance {
 
     // Default to version 1 if it isn't explicitly defined
     fn get_version(&self) -> Result<u32> {
-        let mut store = self.store.write().unwrap();
-        match self.instance.exports.get_function("getVersion") {
-            Ok(func) => Ok(func.call(&mut store, &[])?[0].unwrap_i32() as u32),
+        match self.try_func("getVersion") {
+            Ok(_) => self.get_u32("getVersion"),
             Err(_) => Ok(1),
         }
     }
 
     fn get_u32(&self, name: &str) -> Result<u32> {
-        let func = self.func(name);
-        let mut store = self.store.write().unwrap();
+        let func = self.try_func(name)?;
+        let mut store = self.lock_store()?;
         let result = func.call(&mut store, &[])?;
-        Ok(result[0].unwrap_i32() as u32)
+        Ok(single_i32(name, &result)? as u32)
     }
 
-    fn func(&self, name: &str) -> &Function {
+    fn try_func(&self, name: &str) -> Result<&Function> {
         self.instance
             .exports
             .get_function(name)
-            .unwrap_or_else(|_| panic!("function {} not found", name))
+            .map_err(|_| Error::MissingExport(name.to_string()))
+    }
+}
+
+// Returns the value of an export returning a single i32, erroring rather than
+// panicking for modules whose export has another signature
+fn single_i32(name: &str, result: &[Value]) -> Result<i32> {
+    match result {
+        [Value::I32(value)] => Ok(*value),
+        _ => Err(Error::InvalidExportSignature(name.to_string())),
     }
 }
 
@@ -184,4 +249,35 @@ impl WasmInstance {
     pub fn new(instance: Instance, store: Arc<RwLock<Store>>) -> Self {
         Self { instance, store }
     }
+
+    // Locks the store, which is poisoned if a calculation panicked while holding it
+    fn lock_store(&self) -> Result<RwLockWriteGuard<'_, Store>> {
+        Ok(self.store.write().map_err(|_| MemoryError::PoisonedLock)?)
+    }
+
+    /// Returns the memory exported by the module. Circom 2 modules define their own
+    /// memory instead of importing it.
+    pub(super) fn exported_memory(&self) -> Option<Memory> {
+        self.instance.exports.get_memory("memory").ok().cloned()
+    }
+
+    /// Sets the number of instructions the instance may execute, which is only
+    /// possible if its module was compiled with metering
+    #[cfg(feature = "metering")]
+    pub(super) fn set_remaining_points(&self, points: u64) -> Result<()> {
+        let mut store = self.lock_store()?;
+        wasmer_middlewares::metering::set_remaining_points(&mut store, &self.instance, points);
+        Ok(())
+    }
+
+    #[cfg(feature = "metering")]
+    pub(super) fn points_exhausted(&self) -> Result<bool> {
+        use wasmer_middlewares::metering::{get_remaining_points, MeteringPoints};
+
+        let mut store = self.lock_store()?;
+        Ok(matches!(
+            get_remaining_points(&mut store, &self.instance),
+            MeteringPoints::Exhausted
+        ))
+    }
 }
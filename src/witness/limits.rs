@@ -0,0 +1,125 @@
+//! Bounding the memory of witness calculator instances, so that a hostile or buggy
+//! circuit can't exhaust the host's memory
+use std::ptr::NonNull;
+use wasmer::{
+    sys::BaseTunables,
+    vm::{
+        MemoryError, MemoryStyle, TableStyle, VMMemory, VMMemoryDefinition, VMTable,
+        VMTableDefinition,
+    },
+    MemoryType, Pages, TableType, Tunables,
+};
+
+/// Tunables capping the maximum size of every memory, whether imported or defined by
+/// the module. `memory.grow` past the cap fails, and modules declaring more initial
+/// memory than the cap can't be instantiated.
+pub(super) struct MemoryLimit {
+    base: BaseTunables,
+    max_pages: Pages,
+}
+
+impl MemoryLimit {
+    pub(super) fn new(base: BaseTunables, max_pages: u32) -> Self {
+        Self {
+            base,
+            max_pages: Pages(max_pages),
+        }
+    }
+
+    // Lowers the memory's maximum to the cap
+    fn adjust(&self, ty: &MemoryType) -> MemoryType {
+        let mut ty = *ty;
+        ty.maximum = Some(
+            ty.maximum
+                .map_or(self.max_pages, |max| max.min(self.max_pages)),
+        );
+        ty
+    }
+
+    fn validate(&self, ty: &MemoryType) -> Result<(), MemoryError> {
+        if ty.minimum > self.max_pages {
+            return Err(MemoryError::MinimumMemoryTooLarge {
+                min_requested: ty.minimum,
+                max_allowed: self.max_pages,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Tunables for MemoryLimit {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        // the style of the capped memory, so that static memories are bounds checked
+        // against the cap
+        self.base.memory_style(&self.adjust(memory))
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<VMMemory, MemoryError> {
+        self.validate(ty)?;
+        self.base.create_host_memory(&self.adjust(ty), style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<VMMemory, MemoryError> {
+        self.validate(ty)?;
+        self.base
+            .create_vm_memory(&self.adjust(ty), style, vm_definition_location)
+    }
+
+    fn create_host_table(&self, ty: &TableType, style: &TableStyle) -> Result<VMTable, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<VMTable, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer::{imports, sys::NativeEngineExt, Instance, Module, Store, TypedFunction};
+
+    #[test]
+    fn grow_capped() {
+        let mut engine = Store::default().engine().clone();
+        engine.set_tunables(MemoryLimit::new(
+            BaseTunables::for_target(engine.target()),
+            4,
+        ));
+        let mut store = Store::new(engine);
+
+        let wat = r#"(module
+            (memory (export "memory") 1)
+            (func (export "grow") (param i32) (result i32) local.get 0 memory.grow))"#;
+        let module = Module::new(&store, wat).unwrap();
+        let instance = Instance::new(&mut store, &module, &imports! {}).unwrap();
+        let grow: TypedFunction<i32, i32> =
+            instance.exports.get_typed_function(&store, "grow").unwrap();
+        // returns the previous size in pages, or -1 on failure
+        assert_eq!(grow.call(&mut store, 3).unwrap(), 1);
+        assert_eq!(grow.call(&mut store, 1).unwrap(), -1);
+        assert_eq!(grow.call(&mut store, 1 << 15).unwrap(), -1);
+
+        let wat = r#"(module (memory 5))"#;
+        let module = Module::new(&store, wat).unwrap();
+        assert!(Instance::new(&mut store, &module, &imports! {}).is_err());
+    }
+}
@@ -1,19 +1,37 @@
 //! Safe-ish interface for reading and writing specific types to the WASM runtime's memory
-use ark_serialize::CanonicalDeserialize;
 use num_traits::ToPrimitive;
-use wasmer::{Memory, Store};
+use wasmer::{Memory, MemoryAccessError, MemoryView, Store, WASM_PAGE_SIZE};
 
-// TODO: Decide whether we want Ark here or if it should use a generic BigInt package
-use ark_bn254::FrConfig;
-use ark_ff::MontConfig;
-use ark_ff::{BigInteger, BigInteger256, Zero};
+use ark_ff::{One, Zero};
 
+use num::Integer;
 use num_bigint::{BigInt, BigUint};
 
-use color_eyre::Result;
-use std::str::FromStr;
-use std::sync::{Arc, RwLock};
-use std::{convert::TryFrom, ops::Deref};
+use std::ops::Deref;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+/// Errors returned when accessing the WASM runtime's memory
+#[derive(thiserror::Error, Debug)]
+pub enum MemoryError {
+    /// A read of `len` bytes at `ptr` doesn't fit in the memory, e.g. because a
+    /// malformed circuit returned a corrupt pointer
+    #[error(
+        "memory access of {len} bytes at {ptr} is out of bounds of the {mem_size} bytes memory"
+    )]
+    OutOfBounds {
+        ptr: usize,
+        len: usize,
+        mem_size: u64,
+    },
+    #[error("memory access failed: {0}")]
+    Access(#[from] MemoryAccessError),
+    #[error("could not grow the memory: {0}")]
+    Grow(#[from] wasmer::MemoryError),
+    #[error("the store lock is poisoned")]
+    PoisonedLock,
+    #[error("invalid value: {0}")]
+    InvalidValue(String),
+}
 
 /// `SafeMemory` is a wrapper around the Wasm `Memory` instance that is intended to provide a safer/simpler
 /// interface for witness computation in their natural language.
@@ -31,11 +49,11 @@ pub struct SafeMemory {
 
     pub prime: BigInt,
 
-    short_max: BigInt,
-    short_min: BigInt,
     r_inv: BigInt,
     /// Number of 32-bit limbs required to represent a field element
     limbs_32: usize,
+    /// Highest free position seen, see [`SafeMemory::peak_allocation`]
+    peak: u32,
 }
 
 impl Deref for SafeMemory {
@@ -47,168 +65,288 @@ impl Deref for SafeMemory {
 }
 
 impl SafeMemory {
-    /// Creates a new SafeMemory
+    /// Creates a new SafeMemory for a field with the given prime. The Montgomery
+    /// constants are derived from the prime, so any field circom supports can be used.
     pub fn new(store: Arc<RwLock<Store>>, memory: Memory, limbs_32: usize, prime: BigInt) -> Self {
-        // TODO: Figure out a better way to calculate these
-        let short_max = BigInt::from(0x8000_0000u64);
-        let short_min =
-            BigInt::from_biguint(num_bigint::Sign::NoSign, BigUint::from(FrConfig::MODULUS))
-                - &short_max;
-        let r_inv = BigInt::from_str(
-            "9915499612839321149637521777990102151350674507940716049588462388200839649614",
-        )
-        .unwrap();
-
-        Self {
+        let mut safe_memory = Self {
             store,
             memory,
-            prime,
+            prime: BigInt::zero(),
 
-            short_max,
-            short_min,
-            r_inv,
+            r_inv: BigInt::zero(),
             limbs_32,
-        }
+            peak: 0,
+        };
+        safe_memory.set_prime(prime);
+        safe_memory
+    }
+
+    /// Sets the field prime and recomputes the Montgomery `R^-1` used when reading
+    /// long values. A zero prime (not yet known) leaves `R^-1` unset.
+    pub fn set_prime(&mut self, prime: BigInt) {
+        self.r_inv = r_inv(&prime);
+        self.prime = prime;
     }
 
     /// Returns the next free position in the memory
-    pub fn free_pos(&self) -> u32 {
-        let store = self.store.read().unwrap();
-        let view = self.memory.view(&*store);
-        let mut buf = [0u8; 4];
-        view.read(0, &mut buf).unwrap();
-        u32::from_le_bytes(buf)
+    pub fn free_pos(&self) -> Result<u32, MemoryError> {
+        self.read_u32(0)
     }
 
     /// Sets the next free position in the memory
-    pub fn set_free_pos(&mut self, ptr: u32) {
-        self.write_u32(0, ptr);
+    pub fn set_free_pos(&mut self, ptr: u32) -> Result<(), MemoryError> {
+        self.write_u32(0, ptr)?;
+        self.peak = self.peak.max(ptr);
+        Ok(())
+    }
+
+    /// Records the current free position towards the peak, e.g. after the WASM
+    /// allocated memory itself, and returns it
+    pub fn track_peak(&mut self) -> Result<u32, MemoryError> {
+        let pos = self.free_pos()?;
+        self.peak = self.peak.max(pos);
+        Ok(pos)
+    }
+
+    /// Returns the highest free position set through this memory or recorded by
+    /// [`SafeMemory::track_peak`], i.e. the high-water mark of the allocations
+    pub fn peak_allocation(&self) -> u32 {
+        self.peak
+    }
+
+    /// Grows the memory, if needed, so that `bytes` more bytes fit after the
+    /// next free position
+    pub fn ensure_capacity(&mut self, bytes: usize) -> Result<(), MemoryError> {
+        let required = self.free_pos()? as u64 + bytes as u64;
+
+        let mut store = self.store.write().map_err(|_| MemoryError::PoisonedLock)?;
+        let size = self.memory.view(&*store).data_size();
+        if required > size {
+            let page_size = WASM_PAGE_SIZE as u64;
+            let pages = (required - size).div_ceil(page_size);
+            self.memory.grow(&mut *store, pages as u32)?;
+        }
+        Ok(())
     }
 
     /// Allocates a u32 in memory with 8 byte allignment
-    pub fn alloc_u32(&mut self) -> u32 {
-        let p = self.free_pos();
-        self.set_free_pos(p + 8);
-        p
+    pub fn alloc_u32(&mut self) -> Result<u32, MemoryError> {
+        self.ensure_capacity(8)?;
+        let p = self.free_pos()?;
+        self.set_free_pos(p + 8)?;
+        Ok(p)
     }
 
     /// Writes a u32 to the specified memory offset
-    pub fn write_u32(&mut self, ptr: usize, num: u32) {
-        let store = self.store.read().unwrap();
+    pub fn write_u32(&mut self, ptr: usize, num: u32) -> Result<(), MemoryError> {
+        let store = self.store()?;
         let view = self.memory.view(&*store);
 
-        view.write(ptr as u64, &num.to_le_bytes()).unwrap();
+        view.write(ptr as u64, &num.to_le_bytes())?;
+        Ok(())
     }
 
     /// Reads a u32 from the specified memory offset
-    pub fn read_u32(&self, ptr: usize) -> u32 {
-        let store = self.store.read().unwrap();
+    pub fn read_u32(&self, ptr: usize) -> Result<u32, MemoryError> {
+        let store = self.store()?;
         let view = self.memory.view(&*store);
+        check_bounds(&view, ptr, 4)?;
 
         let mut bytes = [0; 4];
-        view.read(ptr as u64, &mut bytes).unwrap();
+        view.read(ptr as u64, &mut bytes)?;
 
-        u32::from_le_bytes(bytes)
+        Ok(u32::from_le_bytes(bytes))
     }
 
     /// Allocates `self.limbs_32 * 4 + 8` bytes in the memory
-    pub fn alloc_fr(&mut self) -> u32 {
-        let p = self.free_pos();
-        self.set_free_pos(p + self.limbs_32 as u32 * 4 + 8);
-        p
+    pub fn alloc_fr(&mut self) -> Result<u32, MemoryError> {
+        self.alloc_fr_array(1)
+    }
+
+    /// Allocates `len` consecutive Field Elements in the memory
+    pub fn alloc_fr_array(&mut self, len: usize) -> Result<u32, MemoryError> {
+        let size = len * self.fr_size();
+        self.ensure_capacity(size)?;
+        let p = self.free_pos()?;
+        self.set_free_pos(p + size as u32)?;
+        Ok(p)
     }
 
     /// Writes a Field Element to memory at the specified offset, truncating
     /// to smaller u32 types if needed and adjusting the sign via 2s complement
-    pub fn write_fr(&mut self, ptr: usize, fr: &BigInt) -> Result<()> {
-        if fr < &self.short_max && fr > &self.short_min {
-            if fr >= &BigInt::zero() {
-                self.write_short_positive(ptr, fr)?;
-            } else {
-                self.write_short_negative(ptr, fr)?;
-            }
-        } else {
-            self.write_long_normal(ptr, fr)?;
-        }
+    pub fn write_fr(&mut self, ptr: usize, fr: &BigInt) -> Result<(), MemoryError> {
+        let bytes = encode_fr(fr, &self.prime, self.limbs_32)?;
 
+        let store = self.store()?;
+        let view = self.memory.view(&*store);
+        view.write(ptr as u64, &bytes)?;
         Ok(())
     }
 
-    /// Reads a Field Element from the memory at the specified offset
-    pub fn read_fr(&self, ptr: usize) -> Result<BigInt> {
-        let store = self.store.read().unwrap();
+    /// Writes consecutive Field Elements to memory starting at the specified offset,
+    /// laid out as allocated by `alloc_fr_array`. The store is only locked once for
+    /// the whole array.
+    pub fn write_fr_array(&mut self, ptr: usize, values: &[BigInt]) -> Result<(), MemoryError> {
+        let fr_size = self.fr_size();
+
+        let store = self.store()?;
         let view = self.memory.view(&*store);
+        for (i, fr) in values.iter().enumerate() {
+            view.write(
+                (ptr + i * fr_size) as u64,
+                &encode_fr(fr, &self.prime, self.limbs_32)?,
+            )?;
+        }
+        Ok(())
+    }
 
-        let res = if view.read_u8(ptr as u64 + 4 + 3)? & 0x80 != 0 {
-            let mut num = self.read_big(ptr + 8, self.limbs_32)?;
-            if view.read_u8(ptr as u64 + 4 + 3)? & 0x40 != 0 {
-                num = (num * &self.r_inv) % &self.prime
-            }
-            num
-        } else if view.read_u8(ptr as u64 + 3)? & 0x40 != 0 {
-            let mut num = self.read_u32(ptr).into();
-            // handle small negative
-            num -= BigInt::from(0x100000000i64);
-            num
-        } else {
-            self.read_u32(ptr).into()
+    /// Reads a Field Element from the memory at the specified offset
+    pub fn read_fr(&self, ptr: usize) -> Result<BigInt, MemoryError> {
+        let bytes = {
+            let store = self.store()?;
+            let view = self.memory.view(&*store);
+            check_bounds(&view, ptr, self.fr_size())?;
+            view.copy_range_to_vec(ptr as u64..(ptr + self.fr_size()) as u64)?
         };
+        let fr = decode(&bytes, &self.prime, &self.r_inv, self.limbs_32);
+        #[cfg(feature = "zeroize")]
+        {
+            let mut bytes = bytes;
+            zeroize::Zeroize::zeroize(&mut bytes);
+        }
+        fr
+    }
 
-        Ok(res)
+    /// Number of 32-bit limbs of a Field Element
+    pub fn limbs_32(&self) -> usize {
+        self.limbs_32
     }
 
-    fn write_short_positive(&mut self, ptr: usize, fr: &BigInt) -> Result<()> {
-        let num = fr.to_i32().expect("not a short positive");
-        self.write_u32(ptr, num as u32);
-        self.write_u32(ptr + 4, 0);
-        Ok(())
+    /// Size in bytes of a Field Element in memory
+    pub fn fr_size(&self) -> usize {
+        self.limbs_32 * 4 + 8
     }
 
-    fn write_short_negative(&mut self, ptr: usize, fr: &BigInt) -> Result<()> {
-        // 2s complement
-        let num = fr - &self.short_min;
-        let num = num - &self.short_max;
-        let num = num + BigInt::from(0x0001_0000_0000i64);
+    /// Reads `limbs_32 * 4` little-endian bytes from the specified memory offset in a Big Integer
+    pub fn read_big(&self, ptr: usize, limbs_32: usize) -> Result<BigInt, MemoryError> {
+        let store = self.store()?;
+        let view = self.memory.view(&*store);
+        check_bounds(&view, ptr, limbs_32 * 4)?;
+        let buf = view.copy_range_to_vec(ptr as u64..(ptr + limbs_32 * 4) as u64)?;
 
-        let num = num
-            .to_u32()
-            .expect("could not cast as u32 (should never happen)");
+        let big = BigUint::from_bytes_le(&buf);
+        Ok(big.into())
+    }
 
-        self.write_u32(ptr, num);
-        self.write_u32(ptr + 4, 0);
-        Ok(())
+    fn store(&self) -> Result<RwLockReadGuard<'_, Store>, MemoryError> {
+        self.store.read().map_err(|_| MemoryError::PoisonedLock)
     }
+}
 
-    fn write_long_normal(&mut self, ptr: usize, fr: &BigInt) -> Result<()> {
-        self.write_u32(ptr, 0);
-        self.write_u32(ptr + 4, i32::MIN as u32); // 0x80000000
-        self.write_big(ptr + 8, fr)?;
-        Ok(())
+/// Checks that `len` bytes at `ptr` are within the memory, before computing the end
+/// of the range, which could overflow for corrupt pointers
+fn check_bounds(view: &MemoryView, ptr: usize, len: usize) -> Result<(), MemoryError> {
+    let mem_size = view.data_size();
+    match ptr.checked_add(len) {
+        Some(end) if end as u64 <= mem_size => Ok(()),
+        _ => Err(MemoryError::OutOfBounds { ptr, len, mem_size }),
     }
+}
 
-    fn write_big(&self, ptr: usize, num: &BigInt) -> Result<()> {
-        let store = self.store.read().unwrap();
-        let view = self.memory.view(&*store);
+/// Encodes a field element as Circom 1 stores it in the WASM memory:
+///
+/// - values in `(-2^31, 2^31)` are short: the value as a 2s complement `i32`,
+///   followed by a zero type. Only these 8 bytes are written, the limbs are unused.
+/// - other values are long: 4 zero bytes, the `0x80000000` type, and the value
+///   reduced modulo `prime` as `limbs_32` little-endian 32-bit limbs
+///
+/// This is the encoding of [`SafeMemory::write_fr`], independently of a live memory.
+pub fn encode_fr(fr: &BigInt, prime: &BigInt, limbs_32: usize) -> Result<Vec<u8>, MemoryError> {
+    let mut bytes = Vec::with_capacity(limbs_32 * 4 + 8);
+    if let Some(num) = fr.to_i32().filter(|num| *num != i32::MIN) {
+        bytes.extend_from_slice(&num.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+    } else {
+        if prime.is_zero() {
+            return Err(MemoryError::InvalidValue("the prime is zero".to_string()));
+        }
+        // negative numbers are reduced into their canonical field representation
+        let (_, num) = fr.mod_floor(prime).into_parts();
+        let limbs = num.to_bytes_le();
+        if limbs.len() > limbs_32 * 4 {
+            return Err(MemoryError::InvalidValue(format!(
+                "{} does not fit in {} 32-bit limbs",
+                num, limbs_32
+            )));
+        }
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&(i32::MIN as u32).to_le_bytes()); // 0x80000000
+        bytes.extend_from_slice(&limbs);
+        bytes.resize(limbs_32 * 4 + 8, 0);
+    }
+    Ok(bytes)
+}
 
-        // TODO: How do we handle negative bignums?
-        let (_, num) = num.clone().into_parts();
-        let num = BigInteger256::try_from(num).unwrap();
+/// Decodes a field element encoded as by [`encode_fr`], from at least 8 bytes for short
+/// values and exactly `limbs_32 * 4 + 8` for long ones. Besides the short and long
+/// values `encode_fr` produces, Circom 1 also stores long values in Montgomery form,
+/// flagged by `0x40` in the type's high byte, which are converted back.
+pub fn decode_fr(bytes: &[u8], prime: &BigInt, limbs_32: usize) -> Result<BigInt, MemoryError> {
+    decode(bytes, prime, &r_inv(prime), limbs_32)
+}
 
-        view.write(ptr as u64, &num.to_bytes_le())
-            .map_err(Into::into)
+// R = 2^(64 * limbs_64), inverted via Fermat's little theorem. A zero prime (not yet
+// known) leaves `R^-1` zero.
+fn r_inv(prime: &BigInt) -> BigInt {
+    if prime.is_zero() {
+        return BigInt::zero();
     }
+    let limbs_64 = (prime.bits() - 1) / 64 + 1;
+    let r = BigInt::one() << (64 * limbs_64);
+    r.modpow(&(prime - 2u32), prime)
+}
 
-    /// Reads `limbs_32 * 32` bytes from the specified memory offset in a Big Integer
-    pub fn read_big(&self, ptr: usize, limbs_32: usize) -> Result<BigInt> {
-        let store = self.store.read().unwrap();
-        let view = self.memory.view(&*store);
-        let buf = view.copy_range_to_vec(ptr as u64..(ptr + limbs_32 * 32) as u64)?;
-
-        // TODO: Is there a better way to read big integers?
-        let big = BigInteger256::deserialize_uncompressed(buf.as_slice()).unwrap();
-        let big = BigUint::from(big);
-        Ok(big.into())
+fn decode(
+    bytes: &[u8],
+    prime: &BigInt,
+    r_inv: &BigInt,
+    limbs_32: usize,
+) -> Result<BigInt, MemoryError> {
+    let invalid_len = || {
+        MemoryError::InvalidValue(format!(
+            "{} bytes are not a field element of {} 32-bit limbs",
+            bytes.len(),
+            limbs_32
+        ))
+    };
+    if bytes.len() < 8 {
+        return Err(invalid_len());
     }
+
+    let short = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let (high, low) = (bytes[4 + 3], bytes[3]);
+
+    let res = if high & 0x80 != 0 {
+        if bytes.len() != limbs_32 * 4 + 8 {
+            return Err(invalid_len());
+        }
+        let num: BigInt = BigUint::from_bytes_le(&bytes[8..]).into();
+        if high & 0x40 != 0 {
+            if prime.is_zero() {
+                return Err(MemoryError::InvalidValue("the prime is zero".to_string()));
+            }
+            (num * r_inv) % prime
+        } else {
+            num
+        }
+    } else if low & 0x40 != 0 {
+        // handle small negative
+        BigInt::from(short) - BigInt::from(0x100000000i64)
+    } else {
+        short.into()
+    };
+
+    Ok(res)
 }
 
 // TODO: Figure out how to read / write numbers > u32
@@ -221,7 +359,6 @@ impl SafeMemory {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use num_traits::ToPrimitive;
     use std::str::FromStr;
     use wasmer::{MemoryType, Store};
 
@@ -235,7 +372,7 @@ mod tests {
         SafeMemory::new(
             store,
             memory,
-            2,
+            8,
             BigInt::from_str(
                 "21888242871839275222246405745257275088548364400416034343698204186575808495617",
             )
@@ -246,9 +383,104 @@ mod tests {
     #[test]
     fn i32_bounds() {
         let mem = safe_memory_testing_context();
+        let is_short = |num: i64| {
+            let bytes = encode_fr(&BigInt::from(num), &mem.prime, 8).unwrap();
+            bytes[4..8] == [0; 4]
+        };
         let i32_max = i32::MAX as i64 + 1;
-        assert_eq!(mem.short_min.to_i64().unwrap(), -i32_max);
-        assert_eq!(mem.short_max.to_i64().unwrap(), i32_max);
+        assert!(is_short(i32_max - 1));
+        assert!(is_short(-i32_max + 1));
+        assert!(!is_short(i32_max));
+        assert!(!is_short(-i32_max));
+    }
+
+    #[test]
+    fn encode_decode_fr() {
+        let mem = safe_memory_testing_context();
+        let prime = &mem.prime;
+
+        // short positive
+        let bytes = encode_fr(&BigInt::from(5), prime, 8).unwrap();
+        assert_eq!(bytes, [5, 0, 0, 0, 0, 0, 0, 0]);
+
+        // short negative, 2s complement
+        let bytes = encode_fr(&BigInt::from(-5), prime, 8).unwrap();
+        assert_eq!(bytes, [0xfb, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+
+        // long, reduced modulo the prime
+        let num = BigInt::from(-500000000000i64);
+        let bytes = encode_fr(&num, prime, 8).unwrap();
+        assert_eq!(bytes.len(), 40);
+        assert_eq!(bytes[..8], [0, 0, 0, 0, 0, 0, 0, 0x80]);
+        let (_, reduced) = (&num + prime).into_parts();
+        assert_eq!(bytes[8..], reduced.to_bytes_le());
+
+        // long negative values decode to their canonical form
+        assert_eq!(decode_fr(&bytes, prime, 8).unwrap(), &num + prime);
+        for num in [5, -5, 500000000000i64] {
+            let num = BigInt::from(num);
+            let bytes = encode_fr(&num, prime, 8).unwrap();
+            assert_eq!(decode_fr(&bytes, prime, 8).unwrap(), num);
+        }
+
+        // Montgomery form, i.e. times R = 2^256
+        let num = BigInt::from(500000000000i64);
+        let (_, mont) = ((&num << 256usize) % prime).into_parts();
+        let mut bytes = vec![0, 0, 0, 0, 0, 0, 0, 0xc0];
+        bytes.extend(mont.to_bytes_le());
+        bytes.resize(40, 0);
+        assert_eq!(decode_fr(&bytes, prime, 8).unwrap(), num);
+
+        // a prime too large for the limbs
+        let large = prime << 32usize;
+        assert!(encode_fr(&(&large - 1), &large, 8).is_err());
+        assert!(decode_fr(&bytes[..32], prime, 8).is_err());
+        assert!(decode_fr(&[5, 0, 0, 0], prime, 8).is_err());
+    }
+
+    #[test]
+    fn r_inv_bn254() {
+        let mem = safe_memory_testing_context();
+        assert_eq!(
+            mem.r_inv,
+            BigInt::from_str(
+                "9915499612839321149637521777990102151350674507940716049588462388200839649614"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn r_inv_bls12_381() {
+        let mut mem = safe_memory_testing_context();
+        let prime = BigInt::from_str(
+            "52435875175126190479447740508185965837690552500527637822603658699938581184513",
+        )
+        .unwrap();
+        mem.set_prime(prime.clone());
+        let r = BigInt::one() << 256;
+        assert_eq!((r * &mem.r_inv) % &prime, BigInt::one());
+    }
+
+    #[test]
+    fn read_montgomery_bls12_381() {
+        use ark_bls12_381::Fr;
+        use ark_ff::{BigInteger, PrimeField};
+
+        let mut mem = safe_memory_testing_context();
+        mem.set_prime(BigUint::from(Fr::MODULUS).into());
+
+        // Arkworks keeps the field elements in Montgomery form as well
+        let fe = -Fr::from(123456789u64);
+        let mut bytes = vec![0, 0, 0, 0, 0, 0, 0, 0xc0];
+        bytes.extend(fe.0.to_bytes_le());
+        {
+            let store = mem.store().unwrap();
+            mem.memory.view(&*store).write(0, &bytes).unwrap();
+        }
+
+        let expected: BigUint = fe.into_bigint().into();
+        assert_eq!(mem.read_fr(0).unwrap(), expected.into());
     }
 
     #[test]
@@ -256,11 +488,11 @@ mod tests {
         let mut mem = safe_memory_testing_context();
         let num = u32::MAX;
 
-        let inp = mem.read_u32(0);
+        let inp = mem.read_u32(0).unwrap();
         assert_eq!(inp, 0);
 
-        mem.write_u32(0, num);
-        let inp = mem.read_u32(0);
+        mem.write_u32(0, num).unwrap();
+        let inp = mem.read_u32(0).unwrap();
         assert_eq!(inp, num);
     }
 
@@ -279,11 +511,111 @@ mod tests {
         read_write_fr(BigInt::from(500000000000i64));
     }
 
-    // TODO: How should this be handled?
     #[test]
-    #[ignore]
     fn read_write_fr_big_negative() {
-        read_write_fr(BigInt::from_str("-500000000000").unwrap())
+        let mut mem = safe_memory_testing_context();
+        let num = BigInt::from_str("-500000000000").unwrap();
+        mem.write_fr(0, &num).unwrap();
+        let res = mem.read_fr(0).unwrap();
+        // negative values come back in their canonical (positive) field form
+        assert_eq!(res, &num + &mem.prime);
+    }
+
+    #[test]
+    fn read_write_fr_381_bits() {
+        let store = Arc::new(RwLock::new(Store::default()));
+        let mut store_write = store.write().unwrap();
+        let memory = Memory::new(&mut store_write, MemoryType::new(1, None, false)).unwrap();
+        drop(store_write);
+
+        // BLS12-381 base field
+        let prime = BigInt::parse_bytes(
+            b"1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab",
+            16,
+        )
+        .unwrap();
+        let mut mem = SafeMemory::new(store, memory, 12, prime.clone());
+
+        let num = &prime - 1;
+        mem.write_fr(0, &num).unwrap();
+        let res = mem.read_fr(0).unwrap();
+        assert_eq!(res, num);
+    }
+
+    #[test]
+    fn read_write_fr_array() {
+        let mut mem = safe_memory_testing_context();
+        let values = vec![
+            BigInt::from(1_000_000),
+            BigInt::from(-1_000_000),
+            BigInt::from(500000000000i64),
+        ];
+        let ptr = mem.alloc_fr_array(values.len()).unwrap() as usize;
+        mem.write_fr_array(ptr, &values).unwrap();
+        for (i, value) in values.iter().enumerate() {
+            let res = mem.read_fr(ptr + i * mem.fr_size()).unwrap();
+            assert_eq!(&res, value);
+        }
+    }
+
+    #[test]
+    fn alloc_grows_memory() {
+        let mut mem = safe_memory_testing_context();
+        // leave a single u32 worth of room in the first page
+        mem.set_free_pos((WASM_PAGE_SIZE - 4) as u32).unwrap();
+
+        let len = 1000;
+        let ptr = mem.alloc_fr_array(len).unwrap() as usize;
+        let last = ptr + (len - 1) * mem.fr_size();
+        let num = BigInt::from(500000000000i64);
+        mem.write_fr(last, &num).unwrap();
+        assert_eq!(mem.read_fr(last).unwrap(), num);
+    }
+
+    #[test]
+    fn peak_allocation() {
+        let mut mem = safe_memory_testing_context();
+        let start = mem.free_pos().unwrap();
+        mem.alloc_fr_array(10).unwrap();
+        let end = mem.free_pos().unwrap();
+        assert_eq!(mem.peak_allocation(), end);
+
+        // freeing keeps the peak
+        mem.set_free_pos(start).unwrap();
+        assert_eq!(mem.peak_allocation(), end);
+
+        // allocations made behind its back only count once tracked
+        mem.write_u32(0, end + 64).unwrap();
+        assert_eq!(mem.peak_allocation(), end);
+        assert_eq!(mem.track_peak().unwrap(), end + 64);
+        assert_eq!(mem.peak_allocation(), end + 64);
+    }
+
+    #[test]
+    fn read_out_of_bounds() {
+        let mem = safe_memory_testing_context();
+        // a single page is 64KiB
+        let err = mem.read_u32(1 << 16).unwrap_err();
+        assert!(matches!(err, MemoryError::OutOfBounds { .. }));
+
+        // the last field element which fits in the page
+        let end = (1 << 16) - mem.fr_size();
+        mem.read_fr(end).unwrap();
+        let err = mem.read_fr(end + 1).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                MemoryError::OutOfBounds {
+                    len: 40,
+                    mem_size: 65536,
+                    ..
+                }
+            ),
+            "{}",
+            err
+        );
+        let err = mem.read_big(usize::MAX - 4, 8).unwrap_err();
+        assert!(matches!(err, MemoryError::OutOfBounds { .. }), "{}", err);
     }
 
     fn read_write_fr(num: BigInt) {
@@ -1,8 +1,34 @@
+// Enters a tracing span until the end of the enclosing scope, if the `tracing`
+// feature is enabled
+macro_rules! span {
+    ($name:literal) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name).entered();
+    };
+}
+
 mod witness_calculator;
-pub use witness_calculator::WitnessCalculator;
+pub use witness_calculator::{Compiler, WitnessCalculator, WitnessCalculatorModule};
+
+mod cache;
+pub use cache::ModuleCache;
+
+mod limits;
+
+mod pool;
+pub use pool::WitnessCalculatorPool;
+
+mod wtns;
+pub use wtns::{read_wtns, read_wtns_for_circuit, write_wtns};
+
+#[cfg(feature = "zeroize")]
+mod secret;
+#[cfg(feature = "zeroize")]
+pub use secret::ZeroizingWitness;
 
 mod memory;
 pub(super) use memory::SafeMemory;
+pub use memory::{decode_fr, encode_fr, MemoryError};
 
 mod circom;
 pub(super) use circom::{CircomBase, WasmInstance};
@@ -15,7 +41,11 @@ pub(super) use circom::Circom;
 use fnv::FnvHasher;
 use std::hash::Hasher;
 
-pub(crate) fn fnv(inp: &str) -> (u32, u32) {
+/// Returns the hash the WASM looks an input signal up by, as the most and least
+/// significant 32 bits of the FNV-1a hash of its name. Hashing the names of a fixed
+/// set of inputs once saves rehashing them for every witness, see
+/// [`WitnessCalculator::calculate_witness_by_hash`].
+pub fn signal_hash(inp: &str) -> (u32, u32) {
     let mut hasher = FnvHasher::default();
     hasher.write(inp.as_bytes());
     let h = hasher.finish();
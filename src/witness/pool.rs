@@ -0,0 +1,164 @@
+//! Pool of witness calculators for computing witnesses concurrently
+use super::{WitnessCalculator, WitnessCalculatorModule};
+use crate::{Error, Result};
+use num_bigint::BigInt;
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
+use std::sync::{Condvar, Mutex};
+
+/// A fixed-size pool of [`WitnessCalculator`]s instantiated from the same compiled
+/// module. Each calculation checks a calculator out of the pool, waiting for one to
+/// become available if all are in use, so the pool can be shared across threads.
+#[derive(Debug)]
+pub struct WitnessCalculatorPool {
+    calculators: Mutex<Vec<WitnessCalculator>>,
+    available: Condvar,
+}
+
+impl WitnessCalculatorPool {
+    /// Instantiates `size` calculators from the module. The size must be at least 1,
+    /// as calculations would otherwise wait forever for a calculator.
+    pub fn new(module: &WitnessCalculatorModule, size: usize) -> Result<Self> {
+        if size == 0 {
+            return Err(Error::InvalidInput(
+                "a witness calculator pool needs at least one calculator".to_string(),
+            ));
+        }
+        let calculators = (0..size)
+            .map(|_| module.instantiate())
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            calculators: Mutex::new(calculators),
+            available: Condvar::new(),
+        })
+    }
+
+    /// Calculates the witness on the next available calculator
+    pub fn calculate<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        let mut calculator = self.checkout();
+        calculator.get().calculate_witness(inputs, sanity_check)
+    }
+
+    /// Calculates the witness on a thread of Tokio's blocking pool, so that the
+    /// CPU-bound WASM execution doesn't stall the async runtime. The calculator is
+    /// checked out on that thread and returned to the pool once it's done.
+    #[cfg(feature = "tokio")]
+    pub async fn calculate_async<I>(
+        self: Arc<Self>,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>>
+    where
+        I: IntoIterator<Item = (String, Vec<BigInt>)> + Send + 'static,
+    {
+        let handle = tokio::task::spawn_blocking(move || self.calculate(inputs, sanity_check));
+        match handle.await {
+            Ok(witness) => witness,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    fn checkout(&self) -> PooledCalculator<'_> {
+        let mut calculators = self.calculators.lock().unwrap();
+        loop {
+            if let Some(calculator) = calculators.pop() {
+                return PooledCalculator {
+                    pool: self,
+                    calculator: Some(calculator),
+                };
+            }
+            calculators = self.available.wait(calculators).unwrap();
+        }
+    }
+}
+
+/// A calculator checked out of the pool, which is returned to it when dropped
+struct PooledCalculator<'a> {
+    pool: &'a WitnessCalculatorPool,
+    calculator: Option<WitnessCalculator>,
+}
+
+impl PooledCalculator<'_> {
+    fn get(&mut self) -> &mut WitnessCalculator {
+        self.calculator.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledCalculator<'_> {
+    fn drop(&mut self) {
+        if let Some(calculator) = self.calculator.take() {
+            let mut calculators = match self.pool.calculators.lock() {
+                Ok(calculators) => calculators,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            calculators.push(calculator);
+            self.pool.available.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn concurrent() {
+        let module = WitnessCalculatorModule::from_file("./test-vectors/mycircuit.wasm").unwrap();
+        let pool = WitnessCalculatorPool::new(&module, 4).unwrap();
+
+        std::thread::scope(|s| {
+            let handles = (0..100u32)
+                .map(|i| {
+                    let pool = &pool;
+                    s.spawn(move || {
+                        let inputs = HashMap::from([
+                            ("a".to_string(), vec![BigInt::from(i)]),
+                            ("b".to_string(), vec![BigInt::from(i + 1)]),
+                        ]);
+                        (i, pool.calculate(inputs, false).unwrap())
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for handle in handles {
+                let (i, witness) = handle.join().unwrap();
+                assert_eq!(witness[1], BigInt::from(i * (i + 1)));
+            }
+        });
+    }
+
+    #[test]
+    fn empty() {
+        let module = WitnessCalculatorModule::from_file("./test-vectors/mycircuit.wasm").unwrap();
+        let err = WitnessCalculatorPool::new(&module, 0).unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{}", err);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn concurrent_async() {
+        let module = WitnessCalculatorModule::from_file("./test-vectors/mycircuit.wasm").unwrap();
+        let pool = std::sync::Arc::new(WitnessCalculatorPool::new(&module, 4).unwrap());
+
+        let handles = (0..20u32)
+            .map(|i| {
+                let inputs = HashMap::from([
+                    ("a".to_string(), vec![BigInt::from(i)]),
+                    ("b".to_string(), vec![BigInt::from(i + 1)]),
+                ]);
+                tokio::spawn(pool.clone().calculate_async(inputs, false))
+            })
+            .collect::<Vec<_>>();
+
+        for (i, handle) in (0..20u32).zip(handles) {
+            let witness = handle.await.unwrap().unwrap();
+            assert_eq!(witness[1], BigInt::from(i * (i + 1)));
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Wiping of witnesses and of the WASM memory they were computed in, for circuits with
+//! secret inputs such as keys or nonces
+//!
+//! The wiping is best effort:
+//! - `BigInt` doesn't support zeroization, so its digits are overwritten through its
+//!   API, which may leave copies made by reallocations, e.g. while computing a value,
+//!   and the compiler may elide the writes
+//! - the WASM memory is wiped when the last clone of a [`WitnessCalculator`] is
+//!   dropped, not after each calculation, so the memory holds the last witness as long
+//!   as the calculator lives
+//! - the memory may be swapped to disk or be part of a core dump before it's wiped
+//!
+//! Wiping scans the whole memory, e.g. the 125 MiB Circom 1 modules start with, which
+//! makes dropping a calculator take milliseconds.
+//!
+//! [`WitnessCalculator`]: super::WitnessCalculator
+use num_bigint::{BigInt, Sign};
+use num_traits::Zero;
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{Arc, RwLock},
+};
+use wasmer::{Memory, Store, WASM_PAGE_SIZE};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A witness which is overwritten with zeros when dropped, as returned by
+/// [`WitnessCalculator::calculate_witness_zeroizing`]
+///
+/// [`WitnessCalculator::calculate_witness_zeroizing`]: super::WitnessCalculator::calculate_witness_zeroizing
+pub struct ZeroizingWitness(Vec<BigInt>);
+
+impl ZeroizingWitness {
+    pub fn new(witness: Vec<BigInt>) -> Self {
+        Self(witness)
+    }
+}
+
+impl Deref for ZeroizingWitness {
+    type Target = [BigInt];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Sets every value to zero, keeping the witness' length
+impl Zeroize for ZeroizingWitness {
+    fn zeroize(&mut self) {
+        for value in &mut self.0 {
+            wipe(value);
+        }
+    }
+}
+
+impl Drop for ZeroizingWitness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for ZeroizingWitness {}
+
+impl fmt::Debug for ZeroizingWitness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ZeroizingWitness([REDACTED; {}])", self.0.len())
+    }
+}
+
+// Overwrites the digits in place with ones, which the compiler can't drop as the value
+// is still read afterwards, before resetting it to zero
+fn wipe(value: &mut BigInt) {
+    let digits = value.bits().div_ceil(32) as usize;
+    value.assign_from_slice(Sign::Plus, &vec![u32::MAX; digits]);
+    std::hint::black_box(&*value);
+    value.set_zero();
+}
+
+/// Zeroes the memory of a WASM instance when dropped, i.e. when the last clone of the
+/// witness calculator sharing it is dropped
+pub(super) struct MemoryWipe {
+    store: Arc<RwLock<Store>>,
+    memory: Memory,
+}
+
+impl MemoryWipe {
+    pub(super) fn new(store: Arc<RwLock<Store>>, memory: Memory) -> Self {
+        Self { store, memory }
+    }
+
+    pub(super) fn wipe(&self) {
+        // wipe even if a calculation panicked while holding the lock
+        let store = self.store.write().unwrap_or_else(|err| err.into_inner());
+        let view = self.memory.view(&*store);
+        // safe as the store is locked, so the memory isn't accessed concurrently
+        let data = unsafe { view.data_unchecked_mut() };
+        // only write the pages in use, as writing untouched ones commits them
+        for page in data.chunks_mut(WASM_PAGE_SIZE) {
+            if page.iter().any(|byte| *byte != 0) {
+                page.zeroize();
+            }
+        }
+    }
+}
+
+impl Drop for MemoryWipe {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+impl fmt::Debug for MemoryWipe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryWipe").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zeroize_witness() {
+        let values = vec![
+            BigInt::from(1),
+            BigInt::from(-7),
+            BigInt::parse_bytes(b"123456789012345678901234567890123456789", 10).unwrap(),
+        ];
+        let mut witness = ZeroizingWitness::new(values.clone());
+        assert_eq!(&witness[..], &values[..]);
+        assert_eq!(format!("{:?}", witness), "ZeroizingWitness([REDACTED; 3])");
+
+        // as done when dropped
+        witness.zeroize();
+        assert_eq!(witness.len(), 3);
+        assert!(witness.iter().all(Zero::is_zero));
+    }
+}
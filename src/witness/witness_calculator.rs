@@ -1,10 +1,15 @@
-use super::{fnv, CircomBase, SafeMemory, WasmInstance};
+use super::{limits::MemoryLimit, signal_hash, CircomBase, MemoryError, SafeMemory, WasmInstance};
+use crate::{Error, Result};
 use ark_ff::PrimeField;
-use color_eyre::Result;
 use num_bigint::BigInt;
 use num_traits::Zero;
 use std::sync::{Arc, RwLock};
-use wasmer::{imports, Function, Instance, Memory, MemoryType, Module, RuntimeError, Store};
+use wasmer::{
+    imports,
+    sys::{BaseTunables, NativeEngineExt},
+    Engine, Function, FunctionEnv, FunctionEnvMut, Instance, Memory, MemoryType, Module,
+    RuntimeError, Store,
+};
 
 #[cfg(feature = "circom-2")]
 use num::ToPrimitive;
@@ -12,6 +17,9 @@ use num::ToPrimitive;
 #[cfg(feature = "circom-2")]
 use super::Circom2;
 
+#[cfg(feature = "zeroize")]
+use super::secret::MemoryWipe;
+
 use super::Circom;
 
 #[derive(Clone, Debug)]
@@ -22,13 +30,24 @@ pub struct WitnessCalculator {
     /// Number of 64-bit limbs required to represent a field element
     pub limbs_64: u32,
     pub circom_version: u32,
+    #[cfg(feature = "metering")]
+    execution_limit: Option<u64>,
+    // the input signals set by the last `calculate_witness_reusing`, by name and hash
+    reused_inputs: Vec<(String, (u32, u32))>,
+    // only held to wipe the memory when the last clone is dropped
+    #[cfg(feature = "zeroize")]
+    #[allow(dead_code)]
+    wipe: Arc<MemoryWipe>,
 }
 
-// Error type to signal end of execution.
+// Error type to signal end of execution, turned into `Error::WitnessTrap`.
 // From https://docs.wasmer.io/integrations/examples/exit-early
-#[derive(thiserror::Error, Debug, Clone, Copy)]
-#[error("{0}")]
-struct ExitCode(u32);
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("{message}")]
+struct ExitCode {
+    code: u32,
+    message: String,
+}
 
 #[cfg(feature = "circom-2")]
 fn from_array32(arr: Vec<u32>) -> BigInt {
@@ -55,30 +74,260 @@ fn to_array32(s: &BigInt, size: usize) -> Vec<u32> {
     res
 }
 
+// The prime is read with the field's limbs, so it always fits them. A module without
+// limbs is malformed, and would otherwise e.g. divide the witness into empty chunks.
+fn check_field_size(limbs_32: u32) -> Result<()> {
+    if limbs_32 == 0 {
+        return Err(MemoryError::InvalidValue("the field has no 32-bit limbs".to_string()).into());
+    }
+    Ok(())
+}
+
+/// The wasmer compiler used to compile the witness calculator's WASM. Each compiler
+/// besides wasmer's default one is enabled by the Cargo feature of the same name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compiler {
+    /// wasmer's default compiler, i.e. Cranelift, LLVM or Singlepass in this order of
+    /// preference among the enabled ones
+    #[default]
+    Default,
+    /// Optimizing compiler, compiling reasonably fast to reasonably fast code
+    #[cfg(feature = "cranelift")]
+    Cranelift,
+    /// Single pass compiler, compiling in linear time for the fastest cold starts but
+    /// producing the slowest code. Best for large circuits whose witness is only
+    /// calculated a few times.
+    #[cfg(feature = "singlepass")]
+    Singlepass,
+    /// Compiles the slowest, to the fastest code. Best for long running services
+    /// calculating many witnesses. Requires LLVM to be installed.
+    #[cfg(feature = "llvm")]
+    Llvm,
+}
+
+impl Compiler {
+    #[cfg(feature = "metering")]
+    fn metered_store(self, limit: u64) -> Store {
+        use wasmer::{wasmparser::Operator, CompilerConfig};
+        use wasmer_middlewares::Metering;
+
+        let mut config: Box<dyn CompilerConfig> = match self {
+            Compiler::Default => wasmer::get_default_compiler_config()
+                .expect("metering requires the cranelift, singlepass or llvm feature"),
+            #[cfg(feature = "cranelift")]
+            Compiler::Cranelift => Box::new(wasmer::Cranelift::default()),
+            #[cfg(feature = "singlepass")]
+            Compiler::Singlepass => Box::new(wasmer::Singlepass::default()),
+            #[cfg(feature = "llvm")]
+            Compiler::Llvm => Box::new(wasmer::LLVM::default()),
+        };
+        // every instruction costs the same
+        config.push_middleware(Arc::new(Metering::new(limit, |_: &Operator| 1)));
+        Store::new(config)
+    }
+
+    fn store(self) -> Store {
+        match self {
+            Compiler::Default => Store::default(),
+            #[cfg(feature = "cranelift")]
+            Compiler::Cranelift => Store::new(wasmer::Cranelift::default()),
+            #[cfg(feature = "singlepass")]
+            Compiler::Singlepass => Store::new(wasmer::Singlepass::default()),
+            #[cfg(feature = "llvm")]
+            Compiler::Llvm => Store::new(wasmer::LLVM::default()),
+        }
+    }
+}
+
+/// A compiled witness calculator WASM module. Compiling is the expensive part of
+/// creating a [`WitnessCalculator`], so a module can be compiled once and then
+/// instantiated cheaply as many times as needed.
+#[derive(Clone, Debug)]
+pub struct WitnessCalculatorModule {
+    engine: Engine,
+    module: Module,
+    #[cfg(feature = "metering")]
+    execution_limit: Option<u64>,
+}
+
+impl WitnessCalculatorModule {
+    pub(super) fn new(engine: Engine, module: Module) -> Self {
+        Self {
+            engine,
+            module,
+            #[cfg(feature = "metering")]
+            execution_limit: None,
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Compiles the module from the WASM bytes, e.g. embedded with `include_bytes!`
+    pub fn from_bytes(wasm: &[u8]) -> Result<Self> {
+        Self::from_bytes_with_compiler(wasm, Compiler::default())
+    }
+
+    /// Compiles the module from the WASM bytes with the given compiler
+    pub fn from_bytes_with_compiler(wasm: &[u8], compiler: Compiler) -> Result<Self> {
+        Self::from_bytes_with_engine(wasm, compiler.store().engine())
+    }
+
+    /// Compiles the module from the WASM bytes with a caller configured engine, e.g.
+    /// with tunables limiting the memories. The engine is reference counted, so the
+    /// module keeps a handle to it and can outlive the caller's. Each instantiated
+    /// [`WitnessCalculator`] gets its own [`Store`] of the engine, see
+    /// [`WitnessCalculator::from_module`] to instantiate into a given store instead.
+    pub fn from_bytes_with_engine(wasm: &[u8], engine: &Engine) -> Result<Self> {
+        span!("compile");
+        let module = Module::new(engine, wasm)?;
+        Ok(Self::new(engine.clone(), module))
+    }
+
+    /// Compiles the module from the WASM bytes, capping the memory of its instances at
+    /// `max_pages` pages of 64KiB, e.g. to run untrusted circuits without risking the
+    /// host running out of memory. Growing the memory past the cap fails, and modules
+    /// starting with more memory fail to instantiate, such as Circom 1 ones which need
+    /// 2000 pages. See [`WitnessCalculator::memory_pages`] for the memory a circuit
+    /// starts with.
+    pub fn from_bytes_with_memory_limit(
+        wasm: &[u8],
+        compiler: Compiler,
+        max_pages: u32,
+    ) -> Result<Self> {
+        let mut engine = compiler.store().engine().clone();
+        let base = BaseTunables::for_target(engine.target());
+        engine.set_tunables(MemoryLimit::new(base, max_pages));
+        Self::from_bytes_with_engine(wasm, &engine)
+    }
+
+    /// Loads a module compiled ahead of time by
+    /// [`WitnessCalculator::compile_and_serialize`], skipping the compilation.
+    ///
+    /// # Safety
+    ///
+    /// The artifact is native code which is loaded without validation: it must have
+    /// been serialized by the same version of this crate, for the same target, and only
+    /// come from trusted sources. Loading a tampered artifact can execute arbitrary code.
+    pub unsafe fn from_serialized(bytes: &[u8]) -> Result<Self> {
+        span!("deserialize");
+        let engine = Compiler::default().store().engine().clone();
+        let module = Module::deserialize(&engine, bytes)?;
+        Ok(Self::new(engine, module))
+    }
+
+    /// Compiles the module from the WASM bytes, bounding each witness calculation to
+    /// `limit` executed instructions. Calculations exceeding it fail with
+    /// [`Error::ExecutionLimitExceeded`], so that untrusted circuits can't hang the
+    /// caller. Metering makes the calculation somewhat slower.
+    #[cfg(feature = "metering")]
+    pub fn from_bytes_metered(wasm: &[u8], compiler: Compiler, limit: u64) -> Result<Self> {
+        span!("compile");
+        let store = compiler.metered_store(limit);
+        let module = Module::new(&store, wasm)?;
+        Ok(Self {
+            execution_limit: Some(limit),
+            ..Self::new(store.engine().clone(), module)
+        })
+    }
+
+    /// Creates a fresh [`WitnessCalculator`] with its own store and instance,
+    /// without recompiling the module
+    pub fn instantiate(&self) -> Result<WitnessCalculator> {
+        let store = Store::new(self.engine.clone());
+        #[allow(unused_mut)]
+        let mut calculator = WitnessCalculator::from_module(store, self.module.clone())?;
+        #[cfg(feature = "metering")]
+        {
+            calculator.execution_limit = self.execution_limit;
+        }
+        Ok(calculator)
+    }
+}
+
 impl WitnessCalculator {
     pub fn new(path: impl AsRef<std::path::Path>) -> Result<Self> {
         Self::from_file(path)
     }
 
     pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
-        let store = Store::default();
-        let module = Module::from_file(&store, path)?;
-        Self::from_module(store, module)
+        WitnessCalculatorModule::from_file(path)?.instantiate()
+    }
+
+    pub fn from_bytes(wasm: &[u8]) -> Result<Self> {
+        WitnessCalculatorModule::from_bytes(wasm)?.instantiate()
+    }
+
+    pub fn from_bytes_with_compiler(wasm: &[u8], compiler: Compiler) -> Result<Self> {
+        WitnessCalculatorModule::from_bytes_with_compiler(wasm, compiler)?.instantiate()
+    }
+
+    /// See [`WitnessCalculatorModule::from_bytes_with_engine`]
+    pub fn from_bytes_with_engine(wasm: &[u8], engine: &Engine) -> Result<Self> {
+        WitnessCalculatorModule::from_bytes_with_engine(wasm, engine)?.instantiate()
+    }
+
+    /// See [`WitnessCalculatorModule::from_bytes_with_memory_limit`]
+    pub fn from_bytes_with_memory_limit(
+        wasm: &[u8],
+        compiler: Compiler,
+        max_pages: u32,
+    ) -> Result<Self> {
+        WitnessCalculatorModule::from_bytes_with_memory_limit(wasm, compiler, max_pages)?
+            .instantiate()
     }
 
+    /// See [`WitnessCalculatorModule::from_bytes_metered`]
+    #[cfg(feature = "metering")]
+    pub fn from_bytes_metered(wasm: &[u8], compiler: Compiler, limit: u64) -> Result<Self> {
+        WitnessCalculatorModule::from_bytes_metered(wasm, compiler, limit)?.instantiate()
+    }
+
+    /// Compiles the WASM bytes with the default compiler and serializes the compiled
+    /// module, e.g. in a build step, to be loaded without compiling it by
+    /// [`WitnessCalculator::from_serialized_module`]. The artifact is specific to the
+    /// target and wasmer version.
+    pub fn compile_and_serialize(wasm: &[u8]) -> Result<Vec<u8>> {
+        let module = WitnessCalculatorModule::from_bytes(wasm)?;
+        Ok(module.module.serialize()?.to_vec())
+    }
+
+    /// Instantiates a module serialized by [`WitnessCalculator::compile_and_serialize`],
+    /// see [`WitnessCalculatorModule::from_serialized`].
+    ///
+    /// # Safety
+    ///
+    /// The artifact must come from a trusted source, see
+    /// [`WitnessCalculatorModule::from_serialized`].
+    pub unsafe fn from_serialized_module(bytes: &[u8]) -> Result<Self> {
+        WitnessCalculatorModule::from_serialized(bytes)?.instantiate()
+    }
+
+    /// Instantiates the module into the store, which the calculator takes ownership of:
+    /// it's locked during each calculation, so it can't be shared with other instances.
+    /// The module must have been compiled with the store's engine.
     pub fn from_module(store: Store, module: Module) -> Result<Self> {
+        span!("instantiate");
         let store = Arc::new(RwLock::new(store));
         let mut store_locked = store.write().unwrap();
 
-        // Set up the memory
-        let memory = Memory::new(&mut store_locked, MemoryType::new(2000, None, false)).unwrap();
+        // Set up the memory. Circom 2 modules export their own instead of importing it,
+        // leaving this one unused, so it's kept small for memory limits
+        let imports_memory = module
+            .imports()
+            .memories()
+            .any(|import| import.module() == "env" && import.name() == "memory");
+        let pages = if imports_memory { 2000 } else { 1 };
+        let memory = Memory::new(&mut store_locked, MemoryType::new(pages, None, false))
+            .map_err(MemoryError::from)?;
         let import_object = imports! {
             "env" => {
                 "memory" => memory.clone(),
             },
             // Host function callbacks from the WASM
             "runtime" => {
-                "error" => runtime::error(&mut store_locked),
+                "error" => runtime::error(&mut store_locked, memory.clone()),
                 "logSetSignal" => runtime::log_signal(&mut store_locked),
                 "logGetSignal" => runtime::log_signal(&mut store_locked),
                 "logFinishComponent" => runtime::log_component(&mut store_locked),
@@ -96,7 +345,7 @@ impl WitnessCalculator {
 
         let wasm = WasmInstance::new(instance, store.clone());
 
-        let version = wasm.get_version().unwrap_or(1);
+        let version = wasm.get_version()?;
 
         // Circom 2 feature flag with version 2
         #[cfg(feature = "circom-2")]
@@ -107,18 +356,16 @@ impl WitnessCalculator {
             version: u32,
         ) -> Result<WitnessCalculator> {
             let limbs_32 = instance.get_field_num_len32()?;
-            let mut safe_memory =
-                SafeMemory::new(store.clone(), memory, limbs_32 as usize, BigInt::zero());
-            instance.get_raw_prime()?;
-            let mut arr = vec![0; limbs_32 as usize];
-            for i in 0..limbs_32 {
-                let res = instance.read_shared_rw_memory(i)?;
-                arr[(limbs_32 as usize) - (i as usize) - 1] = res;
-            }
-            let prime = from_array32(arr);
+            check_field_size(limbs_32)?;
+            let prime = instance.read_raw_prime()?;
 
-            let limbs_64 = ((prime.bits() - 1) / 64 + 1) as u32;
-            safe_memory.prime = prime;
+            let limbs_64 = limbs_32.div_ceil(2);
+            #[cfg(feature = "zeroize")]
+            let wipe = MemoryWipe::new(
+                store.clone(),
+                instance.exported_memory().unwrap_or(memory.clone()),
+            );
+            let safe_memory = SafeMemory::new(store.clone(), memory, limbs_32 as usize, prime);
 
             Ok(WitnessCalculator {
                 store,
@@ -126,6 +373,11 @@ impl WitnessCalculator {
                 memory: safe_memory,
                 limbs_64,
                 circom_version: version,
+                #[cfg(feature = "metering")]
+                execution_limit: None,
+                reused_inputs: vec![],
+                #[cfg(feature = "zeroize")]
+                wipe: Arc::new(wipe),
             })
         }
 
@@ -135,22 +387,30 @@ impl WitnessCalculator {
             memory: Memory,
             version: u32,
         ) -> Result<WitnessCalculator> {
-            // Fallback to Circom 1 behavior
-            let limbs_32 = (instance.get_fr_len()? >> 2) - 2;
+            // Fallback to Circom 1 behavior. An element is 8 bytes of type and short value
+            // followed by the limbs
+            let fr_len = instance.get_fr_len()?;
+            let limbs_32 = (fr_len >> 2).saturating_sub(2);
+            check_field_size(limbs_32)?;
             let mut safe_memory =
                 SafeMemory::new(store.clone(), memory, limbs_32 as usize, BigInt::zero());
             let ptr = instance.get_ptr_raw_prime()?;
             let prime = safe_memory.read_big(ptr as usize, limbs_32 as usize)?;
 
-            let limbs_64 = ((prime.bits() - 1) / 64 + 1) as u32;
-            safe_memory.prime = prime;
+            let limbs_64 = limbs_32.div_ceil(2);
+            safe_memory.set_prime(prime);
 
             Ok(WitnessCalculator {
+                #[cfg(feature = "zeroize")]
+                wipe: Arc::new(MemoryWipe::new(store.clone(), safe_memory.memory.clone())),
                 store,
                 instance,
                 memory: safe_memory,
                 limbs_64,
                 circom_version: version,
+                #[cfg(feature = "metering")]
+                execution_limit: None,
+                reused_inputs: vec![],
             })
         }
 
@@ -166,7 +426,7 @@ impl WitnessCalculator {
                 match version {
                     2 => new_circom2(store, wasm, memory, version),
                     1 => new_circom1(store, wasm, memory, version),
-                    _ => panic!("Unknown Circom version")
+                    _ => Err(Error::UnsupportedVersion(version)),
                 }
             } else {
                 new_circom1(store, instance, memory, version)
@@ -174,54 +434,299 @@ impl WitnessCalculator {
         }
     }
 
+    /// Returns the number of values in the witness, which is the number of wires
+    /// of the circuit's R1CS
+    pub fn witness_size(&self) -> Result<u32> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "circom-2")] {
+                match self.circom_version {
+                    2 => self.instance.get_witness_size(),
+                    _ => self.instance.get_n_vars(),
+                }
+            } else {
+                self.instance.get_n_vars()
+            }
+        }
+    }
+
+    /// Returns the `(major, minor, patch)` version of Circom the module was compiled
+    /// with. Only newer Circom 2 modules export more than their major version, which is
+    /// [`WitnessCalculator::circom_version`].
+    pub fn circom_full_version(&self) -> Option<(u32, u32, u32)> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "circom-2")] {
+                if self.circom_version != 2 {
+                    return None;
+                }
+                let minor = self.instance.get_minor_version().ok()?;
+                let patch = self.instance.get_patch_version().ok()?;
+                Some((self.circom_version, minor, patch))
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Returns the number of values of the input signal with the given name, e.g. 6 for
+    /// `signal input in[2][3]`, or `None` if the module can't tell: Circom 1 modules
+    /// and those compiled by Circom older than 2.1 don't export the sizes. Fails with
+    /// [`Error::UnknownSignal`] if the circuit has no such input signal.
+    pub fn input_signal_size(&self, name: &str) -> Result<Option<u32>> {
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "circom-2")] {
+                if self.circom_version != 2 {
+                    return Ok(None);
+                }
+                let (msb, lsb) = signal_hash(name);
+                match self.instance.get_input_signal_size(msb, lsb) {
+                    Ok(size) if size >= 0 => Ok(Some(size as u32)),
+                    Ok(_) | Err(Error::MissingExport(_)) => Ok(None),
+                    Err(err) => Err(unknown_signal(err, name, msb, lsb)),
+                }
+            } else {
+                let _ = name;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns the prime of the field the circuit was compiled for
+    pub fn prime(&self) -> BigInt {
+        self.memory.prime.clone()
+    }
+
+    /// Returns the size of the WASM memory in 64KiB pages
+    pub fn memory_pages(&self) -> u32 {
+        let store = self.store.read().unwrap();
+        self.wasm_memory().view(&*store).size().0
+    }
+
+    /// Returns the highest position the Circom 1 allocation pointer reached in the
+    /// calculations so far, e.g. to size [`WitnessCalculator::reserve_memory`] for the
+    /// circuit. Circom 2 modules manage their memory internally, so it stays 0 for
+    /// them, see [`WitnessCalculator::memory_pages`] instead.
+    pub fn peak_allocation(&self) -> u32 {
+        self.memory.peak_allocation()
+    }
+
+    /// Grows the WASM memory to at least `pages` pages of 64KiB up front, so that the
+    /// witness calculation of large circuits doesn't have to grow it step by step.
+    /// Circom 1 modules start with 2000 pages, Circom 2 modules with the size chosen by
+    /// the compiler, see [`WitnessCalculator::memory_pages`]. The memory is never shrunk.
+    pub fn reserve_memory(&mut self, pages: u32) -> Result<()> {
+        let memory = self.wasm_memory();
+        let mut store = self.store.write().unwrap();
+        let current = memory.view(&*store).size().0;
+        if pages > current {
+            memory
+                .grow(&mut *store, pages - current)
+                .map_err(MemoryError::from)?;
+        }
+        Ok(())
+    }
+
+    // The memory used by the module, which Circom 1 modules import
+    fn wasm_memory(&self) -> Memory {
+        self.instance
+            .exported_memory()
+            .unwrap_or_else(|| self.memory.memory.clone())
+    }
+
+    /// Computes the witness for the inputs, given as the values of each input signal.
+    /// Modules compiled by Circom 2.1 or newer reject inputs with fewer or more values
+    /// than their signal with [`Error::InputLengthMismatch`], older ones can't tell.
     pub fn calculate_witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
         &mut self,
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<BigInt>> {
-        self.instance.init(sanity_check)?;
+        self.calculate_witness_hashed(hash_names(inputs), sanity_check)
+    }
 
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "circom-2")] {
-                match self.circom_version {
-                    2 => self.calculate_witness_circom2(inputs, sanity_check),
-                    1 => self.calculate_witness_circom1(inputs, sanity_check),
-                    _ => panic!("Unknown Circom version")
+    /// Same as [`WitnessCalculator::calculate_witness`], with the input signals given
+    /// by the hashes of their names, as returned by [`signal_hash`]. Errors name the
+    /// signals by their hash.
+    ///
+    /// [`signal_hash`]: crate::signal_hash
+    pub fn calculate_witness_by_hash<I: IntoIterator<Item = ((u32, u32), Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        let inputs = inputs.into_iter().map(|((msb, lsb), values)| {
+            let name = format!("{:#010x}{:08x}", msb, lsb);
+            (name, (msb, lsb), values)
+        });
+        self.calculate_witness_hashed(inputs, sanity_check)
+    }
+
+    /// Same as [`WitnessCalculator::calculate_witness`], for calculating many witnesses
+    /// of a circuit in a row on the same instance, checking that the inputs fully
+    /// overwrite the ones of the previous call.
+    ///
+    /// Every calculation already reuses the instance, only running the module's `init`,
+    /// which resets the signal counters and is much cheaper than instantiating the
+    /// module again. It doesn't clear the signals though, so an input which isn't set
+    /// keeps its previous value, and the circuit silently computes the witness of
+    /// stale inputs. This fails with [`Error::InvalidInput`] instead if an input signal
+    /// of the previous call isn't set. Inputs set through the other methods aren't
+    /// tracked, nor are those of the clones of the calculator sharing its instance.
+    pub fn calculate_witness_reusing<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        let inputs: Vec<HashedInput> = hash_names(inputs).collect();
+        let stale = self
+            .reused_inputs
+            .iter()
+            .find(|(_, hash)| inputs.iter().all(|(_, other, _)| other != hash));
+        if let Some((name, _)) = stale {
+            return Err(Error::InvalidInput(format!(
+                "input signal '{}' of the previous calculation isn't set, it would keep its value",
+                name
+            )));
+        }
+
+        self.reused_inputs = inputs
+            .iter()
+            .map(|(name, hash, _)| (name.clone(), *hash))
+            .collect();
+        self.calculate_witness_hashed(inputs, sanity_check)
+    }
+
+    fn calculate_witness_hashed<I: IntoIterator<Item = HashedInput>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        span!("calculate_witness");
+        self.metered(|calculator| {
+            calculator.instance.init(sanity_check)?;
+
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "circom-2")] {
+                    match calculator.circom_version {
+                        2 => calculator.calculate_witness_circom2(inputs, sanity_check),
+                        1 => calculator.calculate_witness_circom1(inputs, sanity_check),
+                        version => Err(Error::UnsupportedVersion(version)),
+                    }
+                } else {
+                    calculator.calculate_witness_circom1(inputs, sanity_check)
                 }
-            } else {
-                self.calculate_witness_circom1(inputs, sanity_check)
             }
+        })
+    }
+
+    /// Same as [`WitnessCalculator::calculate_witness`], returning a witness which is
+    /// overwritten with zeros when dropped. See [`ZeroizingWitness`] for the caveats.
+    ///
+    /// [`ZeroizingWitness`]: crate::ZeroizingWitness
+    #[cfg(feature = "zeroize")]
+    pub fn calculate_witness_zeroizing<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<super::ZeroizingWitness> {
+        let witness = self.calculate_witness(inputs, sanity_check)?;
+        Ok(super::ZeroizingWitness::new(witness))
+    }
+
+    // Runs a calculation with the full execution limit, if metering is enabled
+    fn metered<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        #[cfg(feature = "metering")]
+        if let Some(limit) = self.execution_limit {
+            self.instance.set_remaining_points(limit)?;
+            let result = f(self);
+            // the middleware aborts with an `unreachable` trap
+            if result.is_err() && self.instance.points_exhausted()? {
+                return Err(Error::ExecutionLimitExceeded(limit));
+            }
+            return result.map_err(trap);
         }
+
+        f(self).map_err(trap)
+    }
+
+    /// Same as [`WitnessCalculator::calculate_witness`], on a thread of Tokio's blocking
+    /// pool so that the CPU-bound WASM execution doesn't stall the async runtime. The
+    /// calculator is moved to that thread and handed back along with the result, see
+    /// [`WitnessCalculatorPool::calculate_async`] to share calculators between tasks.
+    ///
+    /// [`WitnessCalculatorPool::calculate_async`]: crate::WitnessCalculatorPool::calculate_async
+    #[cfg(feature = "tokio")]
+    pub async fn calculate_witness_async<I>(
+        mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> (Self, Result<Vec<BigInt>>)
+    where
+        I: IntoIterator<Item = (String, Vec<BigInt>)> + Send + 'static,
+    {
+        let handle = tokio::task::spawn_blocking(move || {
+            let witness = self.calculate_witness(inputs, sanity_check);
+            (self, witness)
+        });
+        match handle.await {
+            Ok(result) => result,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    /// Calculates the witness for each of the input sets, reusing the same instance
+    /// between them. The returned witnesses are in the same order as the input sets.
+    pub fn calculate_witness_batch<I, B>(
+        &mut self,
+        batch: B,
+        sanity_check: bool,
+    ) -> Result<Vec<Vec<BigInt>>>
+    where
+        I: IntoIterator<Item = (String, Vec<BigInt>)>,
+        B: IntoIterator<Item = I>,
+    {
+        batch
+            .into_iter()
+            .map(|inputs| self.calculate_witness(inputs, sanity_check))
+            .collect()
     }
 
     // Circom 1 default behavior
-    fn calculate_witness_circom1<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+    fn calculate_witness_circom1<I: IntoIterator<Item = HashedInput>>(
         &mut self,
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<BigInt>> {
         self.instance.init(sanity_check)?;
 
-        let old_mem_free_pos = self.memory.free_pos();
-        let p_sig_offset = self.memory.alloc_u32();
-        let p_fr = self.memory.alloc_fr();
+        let old_mem_free_pos = self.memory.free_pos()?;
+        let p_sig_offset = self.memory.alloc_u32()?;
 
         // allocate the inputs
-        for (name, values) in inputs.into_iter() {
-            let (msb, lsb) = fnv(&name);
-
-            self.instance
-                .get_signal_offset32(p_sig_offset, 0, msb, lsb)?;
-
-            let sig_offset = self.memory.read_u32(p_sig_offset as usize) as usize;
-
-            for (i, value) in values.into_iter().enumerate() {
-                self.memory.write_fr(p_fr as usize, &value)?;
+        {
+            span!("set_inputs");
+            for (name, (msb, lsb), values) in inputs.into_iter() {
                 self.instance
-                    .set_signal(0, 0, (sig_offset + i) as u32, p_fr)?;
+                    .get_signal_offset32(p_sig_offset, 0, msb, lsb)
+                    .map_err(|err| unknown_signal(err, &name, msb, lsb))?;
+
+                let sig_offset = self.memory.read_u32(p_sig_offset as usize)? as usize;
+
+                // write the whole input array at once, then point each signal at its element
+                let p_frs = self.memory.alloc_fr_array(values.len())?;
+                self.memory.write_fr_array(p_frs as usize, &values)?;
+                let fr_size = self.memory.fr_size() as u32;
+                for i in 0..values.len() {
+                    self.instance.set_signal(
+                        0,
+                        0,
+                        (sig_offset + i) as u32,
+                        p_frs + i as u32 * fr_size,
+                    )?;
+                }
             }
         }
 
+        span!("read_witness");
         let mut w = Vec::new();
 
         let n_vars = self.instance.get_n_vars()?;
@@ -231,52 +736,116 @@ impl WitnessCalculator {
             w.push(el);
         }
 
-        self.memory.set_free_pos(old_mem_free_pos);
+        // the WASM allocated the components while computing the witness
+        self.memory.track_peak()?;
+        self.memory.set_free_pos(old_mem_free_pos)?;
 
         Ok(w)
     }
 
     // Circom 2 feature flag with version 2
     #[cfg(feature = "circom-2")]
-    fn calculate_witness_circom2<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+    fn calculate_witness_circom2<I: IntoIterator<Item = HashedInput>>(
         &mut self,
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<BigInt>> {
+        self.calculate_witness_circom2_with(inputs, sanity_check, |limbs| {
+            from_array32(limbs.iter().rev().copied().collect())
+        })
+    }
+
+    // Computes the witness and converts each element from its little-endian 32-bit limbs
+    #[cfg(feature = "circom-2")]
+    fn calculate_witness_circom2_with<T: Send, I: IntoIterator<Item = HashedInput>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+        convert: impl Fn(&[u32]) -> T + Send + Sync,
+    ) -> Result<Vec<T>> {
+        let limbs_32 = self.limbs_32();
+        #[allow(unused_mut)]
+        let mut limbs = self.calculate_witness_limbs_circom2(inputs, sanity_check)?;
+
+        // unlike reading the limbs, converting them can be done in parallel
+        #[cfg(feature = "parallel")]
+        let witness = {
+            use rayon::prelude::*;
+            limbs.par_chunks(limbs_32 as usize).map(convert).collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let witness = limbs.chunks(limbs_32 as usize).map(convert).collect();
+
+        #[cfg(feature = "zeroize")]
+        zeroize::Zeroize::zeroize(&mut limbs);
+        Ok(witness)
+    }
+
+    // Computes the witness as the little-endian 32-bit limbs of its elements, one after
+    // the other
+    #[cfg(feature = "circom-2")]
+    fn calculate_witness_limbs_circom2<I: IntoIterator<Item = HashedInput>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<u32>> {
         self.instance.init(sanity_check)?;
 
-        let limbs_32 = self.instance.get_field_num_len32()?;
+        let limbs_32 = self.limbs_32();
 
         // allocate the inputs
-        for (name, values) in inputs.into_iter() {
-            let (msb, lsb) = fnv(&name);
-
-            for (i, value) in values.into_iter().enumerate() {
-                let f_arr = to_array32(&value, limbs_32 as usize);
-                for j in 0..limbs_32 {
-                    self.instance
-                        .write_shared_rw_memory(j, f_arr[(limbs_32 as usize) - 1 - (j as usize)])?;
+        {
+            span!("set_inputs");
+            for (name, (msb, lsb), values) in inputs.into_iter() {
+                // too few values would leave the circuit waiting for the rest of the signal, and
+                // the witness uncomputed. Older modules don't export the sizes to check this.
+                match self.instance.get_input_signal_size(msb, lsb) {
+                    Ok(size) if size >= 0 && size as usize != values.len() => {
+                        return Err(Error::InputLengthMismatch {
+                            name,
+                            expected: size as u32,
+                            actual: values.len(),
+                        });
+                    }
+                    // unknown signals are reported when setting them
+                    Ok(_) | Err(Error::MissingExport(_)) => {}
+                    Err(err) => return Err(err),
                 }
-                self.instance.set_input_signal(msb, lsb, i as u32)?;
-            }
-        }
 
-        let mut w = Vec::new();
-
-        let witness_size = self.instance.get_witness_size()?;
-        for i in 0..witness_size {
-            self.instance.get_witness(i)?;
-            let mut arr = vec![0; limbs_32 as usize];
-            for j in 0..limbs_32 {
-                arr[(limbs_32 as usize) - 1 - (j as usize)] =
-                    self.instance.read_shared_rw_memory(j)?;
+                for (i, value) in values.into_iter().enumerate() {
+                    // as the JS witness calculator, reduce negative or too large values
+                    let prime = &self.memory.prime;
+                    let value = ((value % prime) + prime) % prime;
+                    let f_arr = to_array32(&value, limbs_32 as usize);
+                    for j in 0..limbs_32 {
+                        self.instance.write_shared_rw_memory(
+                            j,
+                            f_arr[(limbs_32 as usize) - 1 - (j as usize)],
+                        )?;
+                    }
+                    // setting the last input runs the circuit, so only code 1 means that the
+                    // circuit has no signal with that hash, rather than e.g. a failed assert
+                    self.instance.set_input_signal(msb, lsb, i as u32).map_err(
+                        |err| match &err {
+                            Error::Runtime(source)
+                                if source.downcast_ref::<ExitCode>().map(|exit| exit.code)
+                                    == Some(1) =>
+                            {
+                                unknown_signal(err, &name, msb, lsb)
+                            }
+                            _ => err,
+                        },
+                    )?;
+                }
             }
-            w.push(from_array32(arr));
         }
 
-        Ok(w)
+        span!("read_witness");
+        let witness_size = self.instance.get_witness_size()?;
+        self.instance.read_witness_limbs(witness_size, limbs_32)
     }
 
+    /// Same as [`WitnessCalculator::calculate_witness_fr`]
     pub fn calculate_witness_element<
         F: PrimeField,
         I: IntoIterator<Item = (String, Vec<BigInt>)>,
@@ -285,25 +854,90 @@ impl WitnessCalculator {
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<F>> {
+        self.calculate_witness_fr(inputs, sanity_check)
+    }
+
+    /// Calculates the witness as field elements. With Circom 2 the elements are read
+    /// directly from the WASM memory into `F`, without going through a `BigInt`.
+    pub fn calculate_witness_fr<F: PrimeField, I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<F>> {
+        #[cfg(feature = "circom-2")]
+        if self.circom_version == 2 {
+            return self.metered(|calculator| {
+                let inputs = hash_names(inputs);
+                calculator.calculate_witness_circom2_with(inputs, sanity_check, |limbs| {
+                    let bytes = limbs
+                        .iter()
+                        .flat_map(|limb| limb.to_le_bytes())
+                        .collect::<Vec<_>>();
+                    F::from_le_bytes_mod_order(&bytes)
+                })
+            });
+        }
+
         let witness = self.calculate_witness(inputs, sanity_check)?;
-        let modulus = F::MODULUS;
+        Ok(witness.iter().map(crate::bigint_to_fr).collect())
+    }
 
-        // convert it to field elements
-        use num_traits::Signed;
-        let witness = witness
-            .into_iter()
-            .map(|w| {
-                let w = if w.sign() == num_bigint::Sign::Minus {
-                    // Need to negate the witness element if negative
-                    modulus.into() - w.abs().to_biguint().unwrap()
-                } else {
-                    w.to_biguint().unwrap()
-                };
-                F::from(w)
-            })
-            .collect::<Vec<_>>();
+    /// Calculates the witness as the little-endian bytes of its elements, laid out one
+    /// after the other in `limbs_32 * 4` bytes each, e.g. to hand it over FFI without
+    /// converting it. `limbs_32` must be at least [`WitnessCalculator::limbs_32`], the
+    /// elements are padded with zeros to wider limb counts. With Circom 2 the bytes
+    /// are read directly from the WASM memory, without going through a `BigInt`.
+    pub fn calculate_witness_bytes<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+        limbs_32: u32,
+        sanity_check: bool,
+    ) -> Result<Vec<u8>> {
+        if limbs_32 < self.limbs_32() {
+            return Err(Error::InvalidInput(format!(
+                "field elements need {} limbs of 32 bits, got {}",
+                self.limbs_32(),
+                limbs_32
+            )));
+        }
+        let element_size = limbs_32 as usize * 4;
 
-        Ok(witness)
+        #[cfg(feature = "circom-2")]
+        if self.circom_version == 2 {
+            return self.metered(|calculator| {
+                let field_limbs = calculator.limbs_32() as usize;
+                #[allow(unused_mut)]
+                let mut limbs =
+                    calculator.calculate_witness_limbs_circom2(hash_names(inputs), sanity_check)?;
+
+                let mut bytes = Vec::with_capacity(limbs.len() / field_limbs * element_size);
+                for element in limbs.chunks(field_limbs) {
+                    bytes.extend(element.iter().flat_map(|limb| limb.to_le_bytes()));
+                    bytes.resize(bytes.len() + (element_size - field_limbs * 4), 0);
+                }
+
+                #[cfg(feature = "zeroize")]
+                zeroize::Zeroize::zeroize(&mut limbs);
+                Ok(bytes)
+            });
+        }
+
+        let witness = self.calculate_witness(inputs, sanity_check)?;
+        let prime = &self.memory.prime;
+        let mut bytes = Vec::with_capacity(witness.len() * element_size);
+        for value in &witness {
+            // Circom 1 witnesses may hold negative values
+            let (_, element) = (((value % prime) + prime) % prime).to_bytes_le();
+            bytes.extend_from_slice(&element);
+            bytes.resize(bytes.len() + (element_size - element.len()), 0);
+        }
+        Ok(bytes)
+    }
+
+    /// Number of 32-bit limbs of a field element, as reported by the WASM when it
+    /// was instantiated
+    pub fn limbs_32(&self) -> u32 {
+        self.memory.limbs_32() as u32
     }
 
     pub fn get_witness_buffer(&self) -> Result<Vec<u8>> {
@@ -313,31 +947,102 @@ impl WitnessCalculator {
         let store_read = self.store.read().unwrap();
         let view = self.memory.memory.view(&store_read);
 
-        let arr = view.copy_range_to_vec(ptr..(ptr + len)).unwrap();
+        let arr = view
+            .copy_range_to_vec(ptr..(ptr + len))
+            .map_err(MemoryError::from)?;
         Ok(arr)
     }
 }
 
+// Turns the traps raised by the runtime callbacks into typed errors
+fn trap(err: Error) -> Error {
+    match err {
+        Error::Runtime(err) => match err.downcast::<ExitCode>() {
+            Ok(ExitCode { code, message }) => Error::WitnessTrap { code, message },
+            Err(err) => Error::Runtime(err),
+        },
+        err => err,
+    }
+}
+
+// An input signal's name, along with the hash the WASM looks it up by, and its values
+type HashedInput = (String, (u32, u32), Vec<BigInt>);
+
+fn hash_names<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+    inputs: I,
+) -> impl Iterator<Item = HashedInput> {
+    inputs.into_iter().map(|(name, values)| {
+        let hash = signal_hash(&name);
+        (name, hash, values)
+    })
+}
+
+// Attributes a trap while looking up an input signal to the signal's name
+fn unknown_signal(err: Error, name: &str, msb: u32, lsb: u32) -> Error {
+    match err {
+        Error::Runtime(source) => Error::UnknownSignal {
+            name: name.to_string(),
+            hash: (msb as u64) << 32 | lsb as u64,
+            source,
+        },
+        err => err,
+    }
+}
+
 // callback hooks for debugging
 mod runtime {
     use super::*;
 
-    pub fn error(store: &mut Store) -> Function {
+    // Circom 1.0, `pstr` points to the error message. The other arguments are values
+    // in Montgomery form or further strings depending on the code, see p2str etc here:
+    // https://github.com/iden3/circom_runtime/blob/master/js/witness_calculator.js#L52-L64
+    pub fn error(store: &mut Store, memory: Memory) -> Function {
         #[allow(unused)]
-        #[allow(clippy::many_single_char_names)]
-        fn func(a: i32, b: i32, c: i32, d: i32, e: i32, f: i32) -> Result<(), RuntimeError> {
-            // NOTE: We can also get more information why it is failing, see p2str etc here:
-            // https://github.com/iden3/circom_runtime/blob/master/js/witness_calculator.js#L52-L64
-            println!("runtime error, exiting early: {a} {b} {c} {d} {e} {f}",);
-            Err(RuntimeError::user(Box::new(ExitCode(1))))
+        fn func(
+            env: FunctionEnvMut<Memory>,
+            code: i32,
+            pstr: i32,
+            a: i32,
+            b: i32,
+            c: i32,
+            d: i32,
+        ) -> Result<(), RuntimeError> {
+            let view = env.data().view(&env);
+            let mut message = Vec::new();
+            // the string is NUL terminated, bound it in case it isn't
+            for offset in (pstr as u32 as u64..).take(1024) {
+                match view.read_u8(offset) {
+                    Ok(0) | Err(_) => break,
+                    Ok(byte) => message.push(byte),
+                }
+            }
+            Err(RuntimeError::user(Box::new(ExitCode {
+                code: code as u32,
+                message: String::from_utf8_lossy(&message).into_owned(),
+            })))
         }
-        Function::new_typed(store, func)
+        let env = FunctionEnv::new(store, memory);
+        Function::new_typed_with_env(store, &env, func)
     }
 
-    // Circom 2.0
+    // Circom 2.0, traps as the JS witness calculator throws, otherwise e.g. an unknown input
+    // signal is silently ignored
     pub fn exception_handler(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func(a: i32) {}
+        fn func(code: i32) -> Result<(), RuntimeError> {
+            let message = match code {
+                1 => "Signal not found",
+                2 => "Too many signals set",
+                3 => "Signal already set",
+                4 => "Assert Failed",
+                5 => "Not enough memory",
+                6 => "Input signal array access exceeds the size",
+                _ => "Unknown error",
+            };
+            Err(RuntimeError::user(Box::new(ExitCode {
+                code: code as u32,
+                message: message.to_string(),
+            })))
+        }
         Function::new_typed(store, func)
     }
 
@@ -468,6 +1173,772 @@ mod tests {
         });
     }
 
+    #[test]
+    fn batch() {
+        let mut wtns = WitnessCalculator::new(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = |a: u32, b: u32| {
+            HashMap::from([
+                ("a".to_string(), vec![BigInt::from(a)]),
+                ("b".to_string(), vec![BigInt::from(b)]),
+            ])
+        };
+
+        let witnesses = wtns
+            .calculate_witness_batch([inputs(3, 11), inputs(5, 7)], false)
+            .unwrap();
+        let expected = |w: [u32; 4]| w.iter().map(|&x| BigInt::from(x)).collect::<Vec<_>>();
+        assert_eq!(
+            witnesses,
+            vec![expected([1, 33, 3, 11]), expected([1, 35, 5, 7])]
+        );
+    }
+
+    #[test]
+    fn compilers() {
+        let compilers = [
+            Compiler::Default,
+            #[cfg(feature = "cranelift")]
+            Compiler::Cranelift,
+            #[cfg(feature = "singlepass")]
+            Compiler::Singlepass,
+            #[cfg(feature = "llvm")]
+            Compiler::Llvm,
+        ];
+        let mut paths = vec!["test-vectors/mycircuit.wasm"];
+        if cfg!(feature = "circom-2") {
+            paths.push("test-vectors/circom2_multiplier2.wasm");
+        }
+        for path in paths {
+            let wasm = std::fs::read(root_path(path)).unwrap();
+            let witnesses = compilers
+                .iter()
+                .map(|&compiler| {
+                    let mut wtns =
+                        WitnessCalculator::from_bytes_with_compiler(&wasm, compiler).unwrap();
+                    let inputs = HashMap::from([
+                        ("a".to_string(), vec![BigInt::from(3)]),
+                        ("b".to_string(), vec![BigInt::from(11)]),
+                    ]);
+                    wtns.calculate_witness(inputs, false).unwrap()
+                })
+                .collect::<Vec<_>>();
+            for witness in &witnesses {
+                assert_eq!(witness, &witnesses[0], "{}", path);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "metering", feature = "circom-2"))]
+    fn execution_limit() {
+        let wasm = std::fs::read(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let mut wtns =
+            WitnessCalculator::from_bytes_metered(&wasm, Compiler::default(), 1_000_000).unwrap();
+        // the limit applies to each calculation separately
+        for _ in 0..3 {
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+            let witness = wtns.calculate_witness(inputs, false).unwrap();
+            assert_eq!(witness[1], BigInt::from(33));
+        }
+
+        // a circuit whose initialization never terminates
+        let wat = r#"(module
+            (func (export "getVersion") (result i32) i32.const 2)
+            (func (export "getFieldNumLen32") (result i32) i32.const 8)
+            (func (export "getRawPrime"))
+            (func (export "readSharedRWMemory") (param i32) (result i32) i32.const 1)
+            (func (export "init") (param i32) (loop br 0)))"#;
+        let mut wtns =
+            WitnessCalculator::from_bytes_metered(wat.as_bytes(), Compiler::default(), 1_000_000)
+                .unwrap();
+        let err = wtns.calculate_witness(vec![], false).unwrap_err();
+        assert!(
+            matches!(err, Error::ExecutionLimitExceeded(1_000_000)),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn witness_fr() {
+        use ark_bn254::Fr;
+
+        let mut paths = vec!["test-vectors/mycircuit.wasm"];
+        if cfg!(feature = "circom-2") {
+            paths.push("test-vectors/circom2_multiplier2.wasm");
+        }
+        for path in paths {
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            // -3, so that the output wraps around the prime
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![wtns.prime() - 3]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+
+            let witness = wtns.calculate_witness(inputs.clone(), false).unwrap();
+            let expected = witness
+                .iter()
+                .map(|w| {
+                    let (sign, bytes) = w.to_bytes_le();
+                    let w = Fr::from_le_bytes_mod_order(&bytes);
+                    if sign == num_bigint::Sign::Minus {
+                        -w
+                    } else {
+                        w
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(expected[1], -Fr::from(33u32), "{}", path);
+
+            let witness_fr = wtns.calculate_witness_fr::<Fr, _>(inputs, false).unwrap();
+            assert_eq!(witness_fr, expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn peak_allocation() {
+        let mut wtns = WitnessCalculator::new(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let start = wtns.memory.free_pos().unwrap();
+        let inputs = HashMap::from([
+            ("a".to_string(), vec![BigInt::from(3)]),
+            ("b".to_string(), vec![BigInt::from(11)]),
+        ]);
+        wtns.calculate_witness(inputs, false).unwrap();
+
+        let peak = wtns.peak_allocation();
+        assert!(peak > start, "{} <= {}", peak, start);
+        assert!(peak >= wtns.memory.free_pos().unwrap());
+        assert!(peak as usize <= wtns.memory_pages() as usize * wasmer::WASM_PAGE_SIZE);
+    }
+
+    #[test]
+    fn reusing_instance() {
+        for path in [
+            "test-vectors/mycircuit.wasm",
+            "test-vectors/circom2_multiplier2.wasm",
+        ] {
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            let inputs = |a: i32, b: i32| {
+                vec![
+                    ("a".to_string(), vec![BigInt::from(a)]),
+                    ("b".to_string(), vec![BigInt::from(b)]),
+                ]
+            };
+            let witness = wtns.calculate_witness_reusing(inputs(3, 11), true).unwrap();
+            assert_eq!(witness[..4], [1, 33, 3, 11].map(BigInt::from), "{}", path);
+            let witness = wtns.calculate_witness_reusing(inputs(5, 7), true).unwrap();
+            assert_eq!(witness[..4], [1, 35, 5, 7].map(BigInt::from), "{}", path);
+
+            // b would keep its previous value
+            let partial = vec![("a".to_string(), vec![BigInt::from(2)])];
+            let err = wtns.calculate_witness_reusing(partial, true).unwrap_err();
+            assert!(
+                matches!(&err, Error::InvalidInput(msg) if msg.contains("'b'")),
+                "{}",
+                err
+            );
+            let witness = wtns.calculate_witness_reusing(inputs(2, 2), true).unwrap();
+            assert_eq!(witness[..4], [1, 4, 2, 2].map(BigInt::from), "{}", path);
+        }
+    }
+
+    #[test]
+    fn memory_limit() {
+        let mut paths = vec!["test-vectors/mycircuit.wasm"];
+        if cfg!(feature = "circom-2") {
+            paths.push("test-vectors/circom2_multiplier2.wasm");
+        }
+        for path in paths {
+            let wasm = std::fs::read(root_path(path)).unwrap();
+            let pages = WitnessCalculator::from_bytes(&wasm).unwrap().memory_pages();
+
+            // enough for the circuit, but not for growing the memory
+            let mut wtns =
+                WitnessCalculator::from_bytes_with_memory_limit(&wasm, Compiler::default(), pages)
+                    .unwrap();
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+            let witness = wtns.calculate_witness(inputs, false).unwrap();
+            assert_eq!(witness[1], BigInt::from(33), "{}", path);
+            let err = wtns.reserve_memory(pages + 1).unwrap_err();
+            assert!(matches!(err, Error::Memory(_)), "{}: {}", path, err);
+            assert_eq!(wtns.memory_pages(), pages, "{}", path);
+
+            // the circuit needs more memory than allowed
+            let err = WitnessCalculator::from_bytes_with_memory_limit(
+                &wasm,
+                Compiler::default(),
+                pages - 1,
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, Error::Memory(_) | Error::Instantiation(_)),
+                "{}: {}",
+                path,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn serialized_module() {
+        let mut paths = vec!["test-vectors/mycircuit.wasm"];
+        if cfg!(feature = "circom-2") {
+            paths.push("test-vectors/circom2_multiplier2.wasm");
+        }
+        for path in paths {
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            let expected = wtns.calculate_witness(inputs.clone(), false).unwrap();
+
+            let wasm = std::fs::read(root_path(path)).unwrap();
+            let artifact = WitnessCalculator::compile_and_serialize(&wasm).unwrap();
+            // safe as the artifact was just serialized
+            let mut wtns = unsafe { WitnessCalculator::from_serialized_module(&artifact) }.unwrap();
+            assert_eq!(
+                wtns.calculate_witness(inputs, false).unwrap(),
+                expected,
+                "{}",
+                path
+            );
+        }
+
+        // the raw WASM isn't an artifact
+        let wasm = std::fs::read(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let err = unsafe { WitnessCalculator::from_serialized_module(&wasm) }.unwrap_err();
+        assert!(matches!(err, Error::ModuleDeserialization(_)), "{}", err);
+    }
+
+    #[test]
+    fn witness_bytes() {
+        use ark_bn254::Fr;
+
+        let mut paths = vec!["test-vectors/mycircuit.wasm"];
+        if cfg!(feature = "circom-2") {
+            paths.push("test-vectors/circom2_multiplier2.wasm");
+        }
+        for path in paths {
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![wtns.prime() - 3]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+            let expected = wtns
+                .calculate_witness_fr::<Fr, _>(inputs.clone(), false)
+                .unwrap();
+            assert_eq!(wtns.limbs_32(), 8, "{}", path);
+
+            // the field's width, and padded to 40 bytes
+            for limbs_32 in [8, 10] {
+                let bytes = wtns
+                    .calculate_witness_bytes(inputs.clone(), limbs_32, false)
+                    .unwrap();
+                assert_eq!(bytes.len(), expected.len() * limbs_32 as usize * 4);
+                let witness = bytes
+                    .chunks(limbs_32 as usize * 4)
+                    .map(|element| {
+                        assert!(element[32..].iter().all(|byte| *byte == 0));
+                        Fr::from_le_bytes_mod_order(element)
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(witness, expected, "{}", path);
+            }
+
+            let err = wtns.calculate_witness_bytes(inputs, 4, false).unwrap_err();
+            assert!(matches!(err, Error::InvalidInput(_)), "{}", err);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn unsupported_version() {
+        let wat = r#"(module (func (export "getVersion") (result i32) i32.const 3))"#;
+        let err = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedVersion(3)));
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn missing_export() {
+        let wat = r#"(module (func (export "getVersion") (result i32) i32.const 2))"#;
+        let err = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::MissingExport(name) if name == "getFieldNumLen32"));
+    }
+
+    #[test]
+    fn invalid_export_signature() {
+        let wat = r#"(module (func (export "getVersion") (result i64) i64.const 2))"#;
+        let err = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::InvalidExportSignature(name) if name == "getVersion"));
+
+        let wat = r#"(module (func (export "getFrLen")))"#;
+        let err = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap_err();
+        assert!(matches!(err, Error::InvalidExportSignature(name) if name == "getFrLen"));
+    }
+
+    #[test]
+    fn poisoned_lock() {
+        for path in [
+            "test-vectors/mycircuit.wasm",
+            "test-vectors/circom2_multiplier2.wasm",
+        ] {
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            let store = wtns.store.clone();
+            std::thread::spawn(move || {
+                let _store = store.write().unwrap();
+                panic!("poison the lock");
+            })
+            .join()
+            .unwrap_err();
+
+            let inputs = vec![("a".to_string(), vec![BigInt::from(3)])];
+            let err = wtns.calculate_witness(inputs, true).unwrap_err();
+            assert!(
+                matches!(err, Error::Memory(MemoryError::PoisonedLock)),
+                "{}: {}",
+                path,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn prime() {
+        let bn254 = BigInt::from(num_bigint::BigUint::from(ark_bn254::Fr::MODULUS));
+        let wtns = WitnessCalculator::new(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        assert_eq!(wtns.prime(), bn254);
+        let wtns =
+            WitnessCalculator::new(root_path("test-vectors/circom2_multiplier2.wasm")).unwrap();
+        assert_eq!(wtns.prime(), bn254);
+    }
+
+    #[test]
+    fn unknown_signal() {
+        for path in [
+            "test-vectors/mycircuit.wasm",
+            "test-vectors/circom2_multiplier2.wasm",
+        ] {
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            let inputs = HashMap::from([("foo".to_string(), vec![BigInt::from(3)])]);
+            let err = wtns.calculate_witness(inputs, false).unwrap_err();
+            assert!(
+                matches!(&err, Error::UnknownSignal { name, .. } if name == "foo"),
+                "{}: {}",
+                path,
+                err
+            );
+            assert!(err.to_string().starts_with("unknown input signal 'foo'"));
+        }
+    }
+
+    #[test]
+    fn inputs_by_hash() {
+        for path in [
+            "test-vectors/mycircuit.wasm",
+            "test-vectors/circom2_multiplier2.wasm",
+        ] {
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            let (a, b) = (signal_hash("a"), signal_hash("b"));
+            let inputs = |a_value: i32| {
+                vec![
+                    (a, vec![BigInt::from(a_value)]),
+                    (b, vec![BigInt::from(11)]),
+                ]
+            };
+
+            let by_name = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+            let expected = wtns.calculate_witness(by_name, false).unwrap();
+            assert_eq!(
+                wtns.calculate_witness_by_hash(inputs(3), false).unwrap(),
+                expected
+            );
+            assert_ne!(
+                wtns.calculate_witness_by_hash(inputs(4), false).unwrap(),
+                expected
+            );
+
+            let foo = signal_hash("foo");
+            let err = wtns
+                .calculate_witness_by_hash(vec![(foo, vec![BigInt::from(3)])], false)
+                .unwrap_err();
+            assert!(
+                matches!(&err, Error::UnknownSignal { hash, .. } if *hash == (foo.0 as u64) << 32 | foo.1 as u64),
+                "{}: {}",
+                path,
+                err
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn input_length() {
+        // a circuit with an `in[8]` input signal over F_7
+        let wat = r#"(module
+            (func (export "getVersion") (result i32) i32.const 2)
+            (func (export "getFieldNumLen32") (result i32) i32.const 1)
+            (func (export "getRawPrime"))
+            (func (export "readSharedRWMemory") (param i32) (result i32) i32.const 7)
+            (func (export "writeSharedRWMemory") (param i32 i32))
+            (func (export "init") (param i32))
+            (func (export "getInputSignalSize") (param i32 i32) (result i32) i32.const 8)
+            (func (export "setInputSignal") (param i32 i32 i32))
+            (func (export "getWitness") (param i32))
+            (func (export "getWitnessSize") (result i32) i32.const 0))"#;
+        let mut wtns = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap();
+
+        let inputs = vec![("in".to_string(), vec![BigInt::from(1); 6])];
+        let err = wtns.calculate_witness(inputs, false).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                Error::InputLengthMismatch { name, expected: 8, actual: 6 } if name == "in"
+            ),
+            "{}",
+            err
+        );
+
+        let inputs = vec![("in".to_string(), vec![BigInt::from(1); 8])];
+        wtns.calculate_witness(inputs, false).unwrap();
+        assert_eq!(wtns.input_signal_size("in").unwrap(), Some(8));
+
+        // older modules don't export the sizes
+        let wtns = WitnessCalculator::from_file("./test-vectors/mycircuit.wasm").unwrap();
+        assert_eq!(wtns.input_signal_size("a").unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn assert_failed() {
+        // a circuit over F_7 asserting that its `in` input isn't 0
+        let wat = r#"(module
+            (import "runtime" "exceptionHandler" (func $exception (param i32)))
+            (global $in (mut i32) (i32.const 0))
+            (func (export "getVersion") (result i32) i32.const 2)
+            (func (export "getFieldNumLen32") (result i32) i32.const 1)
+            (func (export "getRawPrime"))
+            (func (export "readSharedRWMemory") (param i32) (result i32) i32.const 7)
+            (func (export "writeSharedRWMemory") (param i32 i32)
+                local.get 1
+                global.set $in)
+            (func (export "init") (param i32))
+            (func (export "setInputSignal") (param i32 i32 i32)
+                global.get $in
+                i32.eqz
+                if
+                    i32.const 4
+                    call $exception
+                end)
+            (func (export "getWitness") (param i32))
+            (func (export "getWitnessSize") (result i32) i32.const 0))"#;
+        let mut wtns = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap();
+
+        for value in [0, 7] {
+            let inputs = vec![("in".to_string(), vec![BigInt::from(value)])];
+            let err = wtns.calculate_witness(inputs, false).unwrap_err();
+            assert!(
+                matches!(&err, Error::WitnessTrap { code: 4, message } if message == "Assert Failed"),
+                "{}",
+                err
+            );
+        }
+
+        // negative inputs are reduced rather than panicking
+        for value in [1, -1] {
+            let inputs = vec![("in".to_string(), vec![BigInt::from(value)])];
+            wtns.calculate_witness(inputs, false).unwrap();
+        }
+    }
+
+    #[test]
+    fn runtime_error() {
+        // a Circom 1 circuit over F_7 whose init fails with its error message
+        let wat = r#"(module
+            (import "env" "memory" (memory 2000))
+            (import "runtime" "error" (func $error (param i32 i32 i32 i32 i32 i32)))
+            (data (i32.const 0) "\07")
+            (data (i32.const 64) "Constraint doesn't match\00")
+            (func (export "getFrLen") (result i32) i32.const 12)
+            (func (export "getPRawPrime") (result i32) i32.const 0)
+            (func (export "init") (param i32)
+                (call $error
+                    (i32.const 7) (i32.const 64)
+                    (i32.const 0) (i32.const 0) (i32.const 0) (i32.const 0))))"#;
+        let mut wtns = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap();
+
+        let err = wtns.calculate_witness(vec![], false).unwrap_err();
+        assert!(
+            matches!(&err, Error::WitnessTrap { code: 7, message } if message == "Constraint doesn't match"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn field_size() {
+        // BN254, Circom 1 and 2
+        for path in ["mycircuit.wasm", "circom2_multiplier2.wasm"] {
+            let wtns =
+                WitnessCalculator::new(root_path(&format!("test-vectors/{}", path))).unwrap();
+            assert_eq!((wtns.limbs_32(), wtns.limbs_64), (8, 4));
+        }
+
+        // a wider field, with BLS12-381's 381-bit base field prime in the memory
+        let q = BigInt::from_str(
+            "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559787",
+        )
+        .unwrap();
+        let prime = r#"(data (i32.const 0) "\ab\aa\ff\ff\ff\ff\fe\b9\ff\ff\53\b1\fe\ff\ab\1e\24\f6\b0\f6\a0\d2\30\67\bf\12\85\f3\84\4b\77\64\d7\ac\4b\43\b6\a7\1b\4b\9a\e6\7f\39\ea\11\01\1a")"#;
+        let circom1 = |fr_len: u32| {
+            format!(
+                r#"(module
+                    (import "env" "memory" (memory 2000))
+                    {}
+                    (func (export "getFrLen") (result i32) i32.const {})
+                    (func (export "getPRawPrime") (result i32) i32.const 0))"#,
+                prime, fr_len
+            )
+        };
+        let circom2 = |limbs_32: u32| {
+            format!(
+                r#"(module
+                    (memory 1)
+                    {}
+                    (func (export "getVersion") (result i32) i32.const 2)
+                    (func (export "getFieldNumLen32") (result i32) i32.const {})
+                    (func (export "getRawPrime"))
+                    (func (export "readSharedRWMemory") (param i32) (result i32)
+                        local.get 0
+                        i32.const 4
+                        i32.mul
+                        i32.load))"#,
+                prime, limbs_32
+            )
+        };
+
+        for wat in [circom1(12 * 4 + 8), circom2(12)] {
+            let wtns = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap();
+            assert_eq!((wtns.limbs_32(), wtns.limbs_64), (12, 6));
+            assert_eq!(wtns.memory.prime, q);
+            assert_eq!(wtns.memory.fr_size(), 56);
+        }
+
+        // no limbs at all, which used to underflow for Circom 1
+        for wat in [circom2(0), circom1(4)] {
+            let err = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap_err();
+            assert!(
+                matches!(err, Error::Memory(MemoryError::InvalidValue(_))),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn read_witness() {
+        // a 2-limb field whose i-th witness value is `(2i + 1) << 32 | 2i`
+        let wat = r#"(module
+            (global $i (mut i32) (i32.const 0))
+            (func (export "getVersion") (result i32) i32.const 2)
+            (func (export "getFieldNumLen32") (result i32) i32.const 2)
+            (func (export "getRawPrime"))
+            (func (export "readSharedRWMemory") (param i32) (result i32)
+                global.get $i
+                i32.const 2
+                i32.mul
+                local.get 0
+                i32.add)
+            (func (export "init") (param i32))
+            (func (export "getWitness") (param i32)
+                local.get 0
+                global.set $i)
+            (func (export "getWitnessSize") (result i32) i32.const 1000))"#;
+        let mut wtns = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap();
+
+        let witness = wtns.calculate_witness(vec![], false).unwrap();
+        let expected = (0..1000u64)
+            .map(|i| BigInt::from(((2 * i + 1) << 32) | (2 * i)))
+            .collect::<Vec<_>>();
+        assert_eq!(witness, expected);
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn zeroize_memory() {
+        let inputs = || {
+            HashMap::from([
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ])
+        };
+        // whether any page of the module's memory holds data
+        let in_use = |wtns: &WitnessCalculator| {
+            let store = wtns.store.read().unwrap();
+            let view = wtns.wasm_memory().view(&*store);
+            view.copy_to_vec().unwrap().iter().any(|byte| *byte != 0)
+        };
+
+        for path in ["mycircuit.wasm", "circom2_multiplier2.wasm"] {
+            let mut wtns =
+                WitnessCalculator::new(root_path(&format!("test-vectors/{}", path))).unwrap();
+            let witness = wtns.calculate_witness_zeroizing(inputs(), false).unwrap();
+            assert_eq!(witness[1], BigInt::from(33));
+            assert!(in_use(&wtns));
+
+            // as done when the last clone of the calculator is dropped
+            wtns.wipe.wipe();
+            assert!(!in_use(&wtns));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn spans() {
+        use std::sync::Mutex;
+        use tracing::{span, Event, Metadata};
+
+        // records the names of the created spans
+        #[derive(Default)]
+        struct Spans(Mutex<Vec<&'static str>>);
+        impl tracing::Subscriber for Spans {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+                let mut names = self.0.lock().unwrap();
+                names.push(span.metadata().name());
+                span::Id::from_u64(names.len() as u64)
+            }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn event(&self, _: &Event<'_>) {}
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let spans = Arc::new(Spans::default());
+        tracing::subscriber::with_default(spans.clone(), || {
+            let mut wtns =
+                WitnessCalculator::new(root_path("test-vectors/mycircuit.wasm")).unwrap();
+            let inputs = vec![
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ];
+            wtns.calculate_witness(inputs, false).unwrap();
+        });
+
+        let names = spans.0.lock().unwrap();
+        for name in [
+            "compile",
+            "instantiate",
+            "calculate_witness",
+            "init",
+            "set_inputs",
+            "read_witness",
+        ] {
+            assert!(names.contains(&name), "{} not in {:?}", name, names);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn raw_prime() {
+        let bn254 = BigInt::from(num_bigint::BigUint::from(ark_bn254::Fr::MODULUS));
+        let wtns =
+            WitnessCalculator::new(root_path("test-vectors/circom2_multiplier2.wasm")).unwrap();
+        assert_eq!(wtns.instance.read_raw_prime().unwrap(), bn254);
+    }
+
+    #[test]
+    fn reserve_memory() {
+        let mut paths = vec!["test-vectors/mycircuit.wasm"];
+        if cfg!(feature = "circom-2") {
+            paths.push("test-vectors/circom2_multiplier2.wasm");
+        }
+        for path in paths {
+            let mut wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            let pages = wtns.memory_pages();
+            wtns.reserve_memory(pages + 100).unwrap();
+            assert_eq!(wtns.memory_pages(), pages + 100, "{}", path);
+            // never shrinks
+            wtns.reserve_memory(1).unwrap();
+            assert_eq!(wtns.memory_pages(), pages + 100, "{}", path);
+
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(3)]),
+                ("b".to_string(), vec![BigInt::from(11)]),
+            ]);
+            let witness = wtns.calculate_witness(inputs, false).unwrap();
+            assert_eq!(witness[1], BigInt::from(33), "{}", path);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "circom-2")]
+    fn full_version() {
+        let wat = r#"(module
+            (func (export "getVersion") (result i32) i32.const 2)
+            (func (export "getMinorVersion") (result i32) i32.const 1)
+            (func (export "getPatchVersion") (result i32) i32.const 6)
+            (func (export "getFieldNumLen32") (result i32) i32.const 1)
+            (func (export "getRawPrime"))
+            (func (export "readSharedRWMemory") (param i32) (result i32) i32.const 7))"#;
+        let wtns = WitnessCalculator::from_bytes(wat.as_bytes()).unwrap();
+        assert_eq!(wtns.circom_full_version(), Some((2, 1, 6)));
+
+        for path in [
+            "test-vectors/mycircuit.wasm",
+            "test-vectors/circom2_multiplier2.wasm",
+        ] {
+            let wtns = WitnessCalculator::new(root_path(path)).unwrap();
+            assert_eq!(wtns.circom_full_version(), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn module_instantiate() {
+        let module =
+            WitnessCalculatorModule::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        for (a, b) in [(3u32, 11u32), (5, 7)] {
+            let mut wtns = module.instantiate().unwrap();
+            let inputs = HashMap::from([
+                ("a".to_string(), vec![BigInt::from(a)]),
+                ("b".to_string(), vec![BigInt::from(b)]),
+            ]);
+            let witness = wtns.calculate_witness(inputs, false).unwrap();
+            assert_eq!(witness[1], BigInt::from(a * b));
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn witness_async() {
+        let mut wtns =
+            WitnessCalculator::new(root_path("test-vectors/circom2_multiplier2.wasm")).unwrap();
+        for (a, b) in [(3, 11), (5, 7)] {
+            let inputs = vec![
+                ("a".to_string(), vec![BigInt::from(a)]),
+                ("b".to_string(), vec![BigInt::from(b)]),
+            ];
+            let (calculator, witness) = wtns.calculate_witness_async(inputs, true).await;
+            assert_eq!(witness.unwrap()[1], BigInt::from(a * b));
+            wtns = calculator;
+        }
+    }
+
     use serde_json::Value;
     use std::str::FromStr;
 
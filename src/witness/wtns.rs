@@ -0,0 +1,230 @@
+//! Circom witness file reader and writer
+//!
+//! Each wtns file is broken into sections:
+//!  Header(1)
+//!       n8
+//!       prime
+//!       nWitness
+//!  Witness(2)
+//!       nWitness little-endian field elements of n8 bytes
+use ark_ff::PrimeField;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use num::Integer;
+use num_bigint::{BigInt, BigUint, Sign};
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+use crate::bigint_to_fr;
+
+/// Writes the witness in circom's binary `.wtns` format, reducing its elements modulo the prime.
+pub fn write_wtns<W: Write>(witness: &[BigInt], prime: &BigInt, writer: &mut W) -> io::Result<()> {
+    // field elements are padded to a multiple of 8 bytes
+    let n8 = (prime.bits().div_ceil(64) * 8) as usize;
+
+    writer.write_all(b"wtns")?;
+    writer.write_u32::<LittleEndian>(2)?;
+    writer.write_u32::<LittleEndian>(2)?;
+
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u64::<LittleEndian>(4 + n8 as u64 + 4)?;
+    writer.write_u32::<LittleEndian>(n8 as u32)?;
+    write_element(writer, prime, n8)?;
+    writer.write_u32::<LittleEndian>(witness.len() as u32)?;
+
+    writer.write_u32::<LittleEndian>(2)?;
+    writer.write_u64::<LittleEndian>((n8 * witness.len()) as u64)?;
+    for value in witness {
+        write_element(writer, &value.mod_floor(prime), n8)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a circom `.wtns` file, returning the field's prime and the witness.
+pub fn read_wtns<R: Read>(reader: &mut R) -> io::Result<(BigInt, Vec<BigInt>)> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"wtns" {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid magic number"));
+    }
+
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != 2 {
+        return Err(Error::new(ErrorKind::InvalidData, "Unsupported version"));
+    }
+
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+    let mut header = None;
+    let mut witness = None;
+    for _ in 0..num_sections {
+        let section_id = reader.read_u32::<LittleEndian>()?;
+        let section_size = reader.read_u64::<LittleEndian>()?;
+        match section_id {
+            1 => {
+                let n8 = reader.read_u32::<LittleEndian>()? as usize;
+                // checked before reading the prime, as the element size is allocated
+                if (n8 as u64).checked_add(8) != Some(section_size) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Header section size doesn't match the element size",
+                    ));
+                }
+                let prime = read_element(reader, n8)?;
+                let num_witness = reader.read_u32::<LittleEndian>()? as usize;
+                header = Some((n8, prime, num_witness));
+            }
+            2 => {
+                // the witness section can only be parsed once the element size is known
+                let (n8, _, num_witness) = header.as_ref().ok_or_else(|| {
+                    Error::new(ErrorKind::InvalidData, "Witness section before header")
+                })?;
+                if (*n8 as u64).checked_mul(*num_witness as u64) != Some(section_size) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        "Witness section size doesn't match the number of values",
+                    ));
+                }
+                let values = (0..*num_witness)
+                    .map(|_| read_element(reader, *n8))
+                    .collect::<io::Result<Vec<_>>>()?;
+                witness = Some(values);
+            }
+            _ => {
+                io::copy(&mut reader.take(section_size), &mut io::sink())?;
+            }
+        }
+    }
+
+    match (header, witness) {
+        (Some((_, prime, _)), Some(witness)) => Ok((prime, witness)),
+        _ => Err(Error::new(ErrorKind::InvalidData, "Missing wtns section")),
+    }
+}
+
+/// Reads a circom `.wtns` file into the field of the circuit, erroring with
+/// [`WitnessFieldMismatch`] if the file's prime isn't the field's modulus, i.e. if the
+/// witness was computed for a circuit over another curve.
+///
+/// [`WitnessFieldMismatch`]: crate::Error::WitnessFieldMismatch
+pub fn read_wtns_for_circuit<F: PrimeField, R: Read>(reader: &mut R) -> crate::Result<Vec<F>> {
+    let (prime, witness) = read_wtns(reader)?;
+    let modulus: BigUint = F::MODULUS.into();
+    // the prime is read as an unsigned integer
+    if prime.magnitude() != &modulus {
+        return Err(crate::Error::WitnessFieldMismatch {
+            prime: prime.into_parts().1,
+            modulus,
+        });
+    }
+    Ok(witness.iter().map(bigint_to_fr).collect())
+}
+
+fn write_element<W: Write>(writer: &mut W, value: &BigInt, n8: usize) -> io::Result<()> {
+    let mut bytes = value.magnitude().to_bytes_le();
+    bytes.resize(n8, 0);
+    writer.write_all(&bytes)
+}
+
+fn read_element<R: Read>(reader: &mut R, n8: usize) -> io::Result<BigInt> {
+    let mut bytes = vec![0u8; n8];
+    reader.read_exact(&mut bytes)?;
+    Ok(BigInt::from_biguint(
+        Sign::Plus,
+        BigUint::from_bytes_le(&bytes),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn roundtrip_snarkjs() {
+        let wtns = std::fs::read("./test-vectors/witness.wtns").unwrap();
+        let (prime, witness) = read_wtns(&mut &wtns[..]).unwrap();
+        assert_eq!(
+            prime,
+            BigInt::from_str(
+                "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            )
+            .unwrap()
+        );
+        assert_eq!(witness.len(), 20001);
+        assert_eq!(witness[0], BigInt::from(1));
+
+        let mut written = vec![];
+        write_wtns(&witness, &prime, &mut written).unwrap();
+        assert_eq!(written, wtns);
+    }
+
+    #[test]
+    fn negative_values() {
+        let prime = BigInt::from(23);
+        let mut written = vec![];
+        write_wtns(&[BigInt::from(1), BigInt::from(-2)], &prime, &mut written).unwrap();
+
+        let (read_prime, witness) = read_wtns(&mut &written[..]).unwrap();
+        assert_eq!(read_prime, prime);
+        assert_eq!(witness, vec![BigInt::from(1), BigInt::from(21)]);
+    }
+
+    #[test]
+    fn prime_mismatch() {
+        let witness = [1, 33, 3, 11].map(BigInt::from);
+        let bn254: BigInt = BigUint::from(ark_bn254::Fr::MODULUS).into();
+        let mut written = vec![];
+        write_wtns(&witness, &bn254, &mut written).unwrap();
+        let read = read_wtns_for_circuit::<ark_bn254::Fr, _>(&mut &written[..]).unwrap();
+        assert_eq!(read, [1u32, 33, 3, 11].map(ark_bn254::Fr::from));
+
+        // a BLS12-381 witness read for a BN254 circuit
+        let bls12_381: BigInt = BigUint::from(ark_bls12_381::Fr::MODULUS).into();
+        let mut written = vec![];
+        write_wtns(&witness, &bls12_381, &mut written).unwrap();
+        let err = read_wtns_for_circuit::<ark_bn254::Fr, _>(&mut &written[..]).unwrap_err();
+        match err {
+            crate::Error::WitnessFieldMismatch { prime, modulus } => {
+                assert_eq!(prime, ark_bls12_381::Fr::MODULUS.into());
+                assert_eq!(modulus, ark_bn254::Fr::MODULUS.into());
+            }
+            err => panic!("unexpected error {}", err),
+        }
+    }
+
+    #[test]
+    fn section_sizes() {
+        let prime = BigInt::from(23);
+        let mut written = vec![];
+        write_wtns(&[BigInt::from(1), BigInt::from(2)], &prime, &mut written).unwrap();
+        // the magic, version and section count, then the header section's id and size,
+        // n8 and the 8 bytes prime, then the number of values
+        let (header_size, n8, num_witness) = (16, 24, 36);
+        let witness_size = num_witness + 8;
+        let patched = |offset: usize, bytes: &[u8]| {
+            let mut wtns = written.clone();
+            wtns[offset..offset + bytes.len()].copy_from_slice(bytes);
+            read_wtns(&mut &wtns[..]).unwrap_err().kind()
+        };
+
+        // a huge element size is rejected before being allocated
+        assert_eq!(patched(n8, &u32::MAX.to_le_bytes()), ErrorKind::InvalidData);
+        assert_eq!(
+            patched(header_size, &17u64.to_le_bytes()),
+            ErrorKind::InvalidData
+        );
+        // more values than the section holds
+        assert_eq!(
+            patched(num_witness, &3u32.to_le_bytes()),
+            ErrorKind::InvalidData
+        );
+        assert_eq!(
+            patched(witness_size, &24u64.to_le_bytes()),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn invalid_magic() {
+        read_wtns(&mut &b"zkey\x02\x00\x00\x00"[..]).unwrap_err();
+    }
+}
@@ -1,4 +1,4 @@
-//! ZKey Parsing
+//! ZKey Parsing and Serialization
 //!
 //! Each ZKey file is broken into sections:
 //!  Header(1)
@@ -13,9 +13,9 @@
 //!       DomainSize  (multiple of 2
 //!       alpha1
 //!       beta1
-//!       delta1
 //!       beta2
 //!       gamma2
+//!       delta1
 //!       delta2
 //!  IC(3)
 //!  Coefs(4)
@@ -25,100 +25,729 @@
 //!  PointsC(8)
 //!  PointsH(9)
 //!  Contributions(10)
-use ark_ff::{BigInteger256, PrimeField};
+use ark_ec::{
+    bls12::{Bls12, Bls12Config},
+    bn::{Bn, BnConfig},
+    pairing::Pairing,
+    short_weierstrass::{Affine, SWCurveConfig},
+    CurveConfig,
+};
+use ark_ff::{
+    BigInt, BigInteger, Field, Fp, Fp2, Fp2Config, MontBackend, MontConfig, PrimeField, Zero,
+};
 use ark_relations::r1cs::ConstraintMatrices;
 use ark_serialize::{CanonicalDeserialize, SerializationError};
 use ark_std::log2;
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use std::{
     collections::HashMap,
-    io::{Read, Seek, SeekFrom},
+    io::{Read, Seek, SeekFrom, Write},
 };
 
-use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
 use ark_groth16::{ProvingKey, VerifyingKey};
-use num_traits::Zero;
+use num_bigint::BigUint;
 
-type IoResult<T> = Result<T, SerializationError>;
+use crate::{Error, Result};
 
-#[derive(Clone, Debug)]
-struct Section {
-    position: u64,
-    #[allow(dead_code)]
-    size: usize,
+/// The location of a section in a zkey file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZKeySection {
+    /// Byte offset of the section's contents from the start of the file
+    pub position: u64,
+    /// Length of the section's contents in bytes
+    pub size: usize,
+}
+
+/// The section table of a zkey file, as read by [`read_zkey_sections`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZKeyHeader {
+    /// The file's magic string, which is "zkey" for zkey files
+    pub ftype: String,
+    pub version: u32,
+    /// The sections by id, in the order they appear in the file
+    pub sections: HashMap<u32, Vec<ZKeySection>>,
+}
+
+/// The sections needed to read a Groth16 proving key
+const GROTH16_SECTIONS: [u32; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+impl ZKeyHeader {
+    /// Returns the first section with the given id, or an error if the zkey doesn't have it
+    pub fn section(&self, id: u32) -> Result<&ZKeySection> {
+        self.sections
+            .get(&id)
+            .and_then(|sections| sections.first())
+            .ok_or(Error::MissingSection(id))
+    }
+
+    /// Returns the ids of the sections required by [`read_zkey`] which are missing from the file
+    pub fn missing_sections(&self) -> Vec<u32> {
+        GROTH16_SECTIONS
+            .iter()
+            .copied()
+            .filter(|id| !self.sections.contains_key(id))
+            .collect()
+    }
+}
+
+/// Reads the section table of a SnarkJS ZKey file without deserializing its contents.
+pub fn read_zkey_sections<R: Read + Seek>(reader: &mut R) -> Result<ZKeyHeader> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+
+    let version = reader.read_u32::<LittleEndian>()?;
+
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+
+    let mut sections = HashMap::new();
+    for _ in 0..num_sections {
+        let section_id = reader.read_u32::<LittleEndian>()?;
+        let section_length = reader.read_u64::<LittleEndian>()?;
+
+        let section = sections.entry(section_id).or_insert_with(Vec::new);
+        section.push(ZKeySection {
+            position: reader.stream_position()?,
+            size: section_length as usize,
+        });
+
+        reader.seek(SeekFrom::Current(section_length as i64))?;
+    }
+
+    Ok(ZKeyHeader {
+        ftype: String::from_utf8_lossy(&magic).into_owned(),
+        version,
+        sections,
+    })
 }
 
 /// Reads a SnarkJS ZKey file into an Arkworks ProvingKey.
-pub fn read_zkey<R: Read + Seek>(
+///
+/// The zkey's curve is identified by the primes in its header, which must match
+/// those of the requested pairing engine.
+pub fn read_zkey<E: ZkeyEngine, R: Read + Seek>(
     reader: &mut R,
-) -> IoResult<(ProvingKey<Bn254>, ConstraintMatrices<Fr>)> {
+) -> Result<(ProvingKey<E>, ConstraintMatrices<E::ScalarField>)> {
     let mut binfile = BinFile::new(reader)?;
-    let proving_key = binfile.proving_key()?;
-    let matrices = binfile.matrices()?;
+    let proving_key = binfile.proving_key::<E>()?;
+    let matrices = binfile.matrices::<E>()?;
+    Ok((proving_key, matrices))
+}
+
+/// Same as [`read_zkey`], for sources which can't seek such as network streams. The
+/// whole zkey is buffered in memory before being parsed, which [`read_zkey`] avoids
+/// for files.
+pub fn read_zkey_from_reader<E: ZkeyEngine, R: Read>(
+    mut reader: R,
+) -> Result<(ProvingKey<E>, ConstraintMatrices<E::ScalarField>)> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    read_zkey(&mut std::io::Cursor::new(buf))
+}
+
+/// Reads the proving and verifying keys of a SnarkJS ZKey file, for proving with a
+/// [`CircomCircuit`](crate::CircomCircuit) and verifying the proofs. Unlike
+/// [`read_zkey`], the constraint matrices aren't read.
+pub fn read_zkey_keys<E: ZkeyEngine, R: Read + Seek>(
+    reader: &mut R,
+) -> Result<(ProvingKey<E>, VerifyingKey<E>)> {
+    let proving_key = BinFile::new(reader)?.proving_key::<E>()?;
+    let verifying_key = proving_key.vk.clone();
+    Ok((proving_key, verifying_key))
+}
+
+/// Reads only the verifying key of a SnarkJS ZKey file, from its header and IC
+/// sections. The proving key's point sections, which make up most of large zkeys,
+/// are skipped.
+pub fn read_verifying_key<E: ZkeyEngine, R: Read + Seek>(
+    reader: &mut R,
+) -> Result<VerifyingKey<E>> {
+    BinFile::new(reader)?.verifying_key::<E>()
+}
+
+/// A coefficient of the QAP's constraint matrices, as recorded in section 4 of a zkey
+/// and read by [`read_zkey_coefficients`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZKeyCoefficient<F> {
+    /// 0 for the A matrix, 1 for B. C isn't recorded, as the prover computes `Cz` as
+    /// `Az * Bz`.
+    pub matrix: u32,
+    /// The row of the matrix. The rows past the circuit's constraints are those snarkjs
+    /// adds for the constant one and each public signal.
+    pub constraint: u32,
+    /// The column of the matrix, i.e. the wire
+    pub signal: u32,
+    /// The coefficient, converted from snarkjs' Montgomery form
+    pub value: F,
+}
+
+/// Reads the coefficients of the constraint matrices of a SnarkJS ZKey file, in the
+/// order they are recorded, e.g. for a prover laying out the QAP differently than
+/// the matrices returned by [`read_zkey`].
+pub fn read_zkey_coefficients<E: ZkeyEngine, R: Read + Seek>(
+    reader: &mut R,
+) -> Result<Vec<ZKeyCoefficient<E::ScalarField>>> {
+    let mut coefficients = Vec::new();
+    BinFile::new(reader)?.coefficients::<E>(|coefficient| coefficients.push(coefficient))?;
+    Ok(coefficients)
+}
+
+/// The protocol id of Groth16 zkeys, the only ones this crate can read
+const GROTH16_PROTOCOL: u32 = 1;
+
+/// The curve independent fields of a zkey's headers, as read by [`read_zkey_metadata`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZKeyMetadata {
+    /// The proving system, 1 for Groth16, 2 for PLONK and 10 for FFLONK. Only Groth16
+    /// zkeys can be read by [`read_zkey`].
+    pub protocol: u32,
+    /// snarkjs' name of the curve, e.g. `bn128`, identified by its base field prime.
+    /// `None` for curves unknown to this crate.
+    pub curve: Option<&'static str>,
+    /// The base field prime
+    pub q: BigUint,
+    /// The scalar field prime, i.e. the group order
+    pub r: BigUint,
+    /// The number of wires
+    pub n_vars: usize,
+    /// The number of public signals, i.e. the outputs and public inputs
+    pub n_public: usize,
+    pub domain_size: u32,
+    /// The log2 of the domain size, i.e. the power of tau the setup needs at least
+    pub power: u32,
+}
+
+/// Reads the header fields of a SnarkJS ZKey file, without deserializing any point,
+/// so that zkeys of any curve can be read.
+pub fn read_zkey_metadata<R: Read + Seek>(reader: &mut R) -> Result<ZKeyMetadata> {
+    let header = read_zkey_sections(reader)?;
+
+    reader.seek(SeekFrom::Start(header.section(1)?.position))?;
+    let protocol = reader.read_u32::<LittleEndian>()?;
+
+    // every protocol's header starts with these fields
+    reader.seek(SeekFrom::Start(header.section(2)?.position))?;
+    let n8q = reader.read_u32::<LittleEndian>()?;
+    let q = read_prime(reader, n8q)?;
+    let n8r = reader.read_u32::<LittleEndian>()?;
+    let r = read_prime(reader, n8r)?;
+    let n_vars = reader.read_u32::<LittleEndian>()? as usize;
+    let n_public = reader.read_u32::<LittleEndian>()? as usize;
+    let domain_size = reader.read_u32::<LittleEndian>()?;
+
+    Ok(ZKeyMetadata {
+        protocol,
+        curve: curve_name(&q),
+        q,
+        r,
+        n_vars,
+        n_public,
+        domain_size,
+        power: log2(domain_size as usize),
+    })
+}
+
+fn curve_name(q: &BigUint) -> Option<&'static str> {
+    const BLS12_381_Q: &str = "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559787";
+    if *q == ark_bn254::Fq::MODULUS.into() {
+        Some("bn128")
+    } else if q.to_string() == BLS12_381_Q {
+        Some("bls12381")
+    } else {
+        None
+    }
+}
+
+/// The Phase 2 contributions to a zkey's trusted setup, as recorded in its section 10
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZKeyContributions<E: Pairing> {
+    /// The hash of the circuit the setup is for, 64 bytes of Blake2b
+    pub cs_hash: Vec<u8>,
+    /// The contributions in the order they were made
+    pub contributions: Vec<ZKeyContribution<E>>,
+}
+
+/// A contribution to a zkey's trusted setup, i.e. the multiplication of its delta by
+/// a secret `x`. The public key `(g1_s, g1_sx, g2_spx)` proves the knowledge of `x`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZKeyContribution<E: Pairing> {
+    /// delta in G1 after the contribution
+    pub delta_after: E::G1Affine,
+    /// A random point in G1
+    pub g1_s: E::G1Affine,
+    /// `g1_s` multiplied by `x`
+    pub g1_sx: E::G1Affine,
+    /// The point in G2 derived from the transcript and `g1_s`, multiplied by `x`
+    pub g2_spx: E::G2Affine,
+    /// The transcript hash the contribution was made on, 64 bytes of Blake2b
+    pub transcript: Vec<u8>,
+    /// 0 for a contribution, 1 for a random beacon
+    pub contribution_type: u32,
+    /// The contributor's name, if they gave one
+    pub name: Option<String>,
+    /// For beacons, the log2 of the number of hash iterations
+    pub num_iterations_exp: Option<u8>,
+    /// For beacons, the beacon's value
+    pub beacon_hash: Option<Vec<u8>>,
+}
+
+/// Reads the contributions section of a SnarkJS ZKey file, to verify the zkey against
+/// the transcript of its trusted setup ceremony.
+pub fn read_zkey_contributions<E: ZkeyEngine, R: Read + Seek>(
+    reader: &mut R,
+) -> Result<ZKeyContributions<E>> {
+    let section = read_zkey_sections(reader)?.section(10)?.clone();
+    reader.seek(SeekFrom::Start(section.position))?;
+
+    let mut cs_hash = vec![0u8; 64];
+    reader.read_exact(&mut cs_hash)?;
+    let n = reader.read_u32::<LittleEndian>()?;
+
+    let mut contributions = Vec::new();
+    for _ in 0..n {
+        let delta_after = E::deserialize_g1(reader)?;
+        let g1_s = E::deserialize_g1(reader)?;
+        let g1_sx = E::deserialize_g1(reader)?;
+        let g2_spx = E::deserialize_g2(reader)?;
+        let mut transcript = vec![0u8; 64];
+        reader.read_exact(&mut transcript)?;
+        let contribution_type = reader.read_u32::<LittleEndian>()?;
+
+        let mut contribution = ZKeyContribution {
+            delta_after,
+            g1_s,
+            g1_sx,
+            g2_spx,
+            transcript,
+            contribution_type,
+            name: None,
+            num_iterations_exp: None,
+            beacon_hash: None,
+        };
+
+        // the optional parameters are records of a type byte followed by their value,
+        // sorted by type. Their length is checked against the rest of the section
+        // before allocating, as a corrupt one could be up to 4 GiB
+        let params_len = reader.read_u32::<LittleEndian>()? as u64;
+        let section_end = section.position + section.size as u64;
+        if reader.stream_position()? + params_len > section_end {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "contribution parameters of {} bytes overrun the contributions section",
+                    params_len
+                ),
+            )
+            .into());
+        }
+        let mut params = vec![0u8; params_len as usize];
+        reader.read_exact(&mut params)?;
+        let mut params = &params[..];
+        let mut last_type = 0;
+        while let Ok(param_type) = params.read_u8() {
+            if param_type <= last_type {
+                return Err(SerializationError::InvalidData.into());
+            }
+            last_type = param_type;
+
+            let len = params.read_u8()?;
+            match param_type {
+                1 => {
+                    let mut name = vec![0u8; len as usize];
+                    params.read_exact(&mut name)?;
+                    contribution.name = Some(String::from_utf8_lossy(&name).into_owned());
+                }
+                // the byte is the value itself
+                2 => contribution.num_iterations_exp = Some(len),
+                3 => {
+                    let mut hash = vec![0u8; len as usize];
+                    params.read_exact(&mut hash)?;
+                    contribution.beacon_hash = Some(hash);
+                }
+                _ => return Err(SerializationError::InvalidData.into()),
+            }
+        }
+        contributions.push(contribution);
+    }
+
+    Ok(ZKeyContributions {
+        cs_hash,
+        contributions,
+    })
+}
+
+/// Reads a memory-mapped SnarkJS ZKey file into an Arkworks ProvingKey.
+///
+/// The points are parsed directly out of the mapped file, so large keys don't
+/// need to be loaded into memory before being deserialized.
+#[cfg(feature = "mmap")]
+pub fn read_zkey_mmap<E: ZkeyEngine>(
+    mmap: &memmap2::Mmap,
+) -> Result<(ProvingKey<E>, ConstraintMatrices<E::ScalarField>)> {
+    let bytes = &mmap[..];
+    let mut reader = std::io::Cursor::new(bytes);
+    let mut binfile = BinFile::new(&mut reader)?.with_bytes(bytes);
+    let proving_key = binfile.proving_key::<E>()?;
+    let matrices = binfile.matrices::<E>()?;
     Ok((proving_key, matrices))
 }
 
+/// Reads the zkey stored at `offset` in a larger file, e.g. a bundle of the zkeys of
+/// several circuits, see [`ZKeyWindow`].
+pub fn read_zkey_at<E: ZkeyEngine, R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    len: u64,
+) -> Result<(ProvingKey<E>, ConstraintMatrices<E::ScalarField>)> {
+    read_zkey(&mut ZKeyWindow::new(reader, offset, len)?)
+}
+
+/// The `len` bytes at `offset` of a reader, seen as a reader of their own: reads stop
+/// at the end of the window and seeks are relative to its start. This lets any of the
+/// zkey readers, e.g. [`read_verifying_key`], read a zkey stored in a larger file.
 #[derive(Debug)]
-struct BinFile<'a, R> {
-    #[allow(dead_code)]
-    ftype: String,
-    #[allow(dead_code)]
-    version: u32,
-    sections: HashMap<u32, Vec<Section>>,
-    reader: &'a mut R,
+pub struct ZKeyWindow<R> {
+    inner: R,
+    offset: u64,
+    len: u64,
+    // position relative to the start of the window
+    position: u64,
 }
 
-impl<'a, R: Read + Seek> BinFile<'a, R> {
-    fn new(reader: &'a mut R) -> IoResult<Self> {
-        let mut magic = [0u8; 4];
-        reader.read_exact(&mut magic)?;
+impl<R: Read + Seek> ZKeyWindow<R> {
+    /// Creates the window, positioned at its start
+    pub fn new(mut inner: R, offset: u64, len: u64) -> Result<Self> {
+        inner.seek(SeekFrom::Start(offset))?;
+        Ok(Self {
+            inner,
+            offset,
+            len,
+            position: 0,
+        })
+    }
 
-        let version = reader.read_u32::<LittleEndian>()?;
+    /// Returns the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
 
-        let num_sections = reader.read_u32::<LittleEndian>()?;
+impl<R: Read> Read for ZKeyWindow<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.position);
+        let max = remaining.min(buf.len() as u64) as usize;
+        let read = self.inner.read(&mut buf[..max])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
 
-        let mut sections = HashMap::new();
-        for _ in 0..num_sections {
-            let section_id = reader.read_u32::<LittleEndian>()?;
-            let section_length = reader.read_u64::<LittleEndian>()?;
+impl<R: Seek> Seek for ZKeyWindow<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+        }
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        // as with files, seeking past the end is allowed and reads nothing
+        self.inner.seek(SeekFrom::Start(self.offset + position))?;
+        self.position = position;
+        Ok(position)
+    }
+}
 
-            let section = sections.entry(section_id).or_insert_with(Vec::new);
-            section.push(Section {
-                position: reader.stream_position()?,
-                size: section_length as usize,
-            });
+/// Writes an Arkworks ProvingKey and its constraint matrices as a SnarkJS ZKey file.
+///
+/// The contributions section is written without any contributions and with a
+/// zeroed circuit hash, as the R1CS the key was generated from isn't known.
+///
+/// Fails with [`Error::InvalidInput`] for keys without any IC point.
+pub fn write_zkey<E: ZkeyEngine, W: Write + Seek>(
+    pk: &ProvingKey<E>,
+    matrices: &ConstraintMatrices<E::ScalarField>,
+    writer: &mut W,
+) -> Result<()> {
+    // the first IC point is the constant one's, followed by one per public signal
+    let Some(n_public) = pk.vk.gamma_abc_g1.len().checked_sub(1) else {
+        return Err(Error::InvalidInput(
+            "the verifying key has no IC points".to_string(),
+        ));
+    };
+
+    writer.write_all(b"zkey")?;
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u32::<LittleEndian>(10)?;
+
+    write_section(writer, 1, |w| {
+        // Groth16
+        w.write_u32::<LittleEndian>(1)?;
+        Ok(())
+    })?;
+    write_section(writer, 2, |w| {
+        let q = E::BaseField::MODULUS.to_bytes_le();
+        w.write_u32::<LittleEndian>(q.len() as u32)?;
+        w.write_all(&q)?;
+        let r = E::ScalarField::MODULUS.to_bytes_le();
+        w.write_u32::<LittleEndian>(r.len() as u32)?;
+        w.write_all(&r)?;
+
+        w.write_u32::<LittleEndian>(pk.a_query.len() as u32)?;
+        w.write_u32::<LittleEndian>(n_public as u32)?;
+        w.write_u32::<LittleEndian>(pk.h_query.len() as u32)?;
+
+        E::serialize_g1(&pk.vk.alpha_g1, w)?;
+        E::serialize_g1(&pk.beta_g1, w)?;
+        E::serialize_g2(&pk.vk.beta_g2, w)?;
+        E::serialize_g2(&pk.vk.gamma_g2, w)?;
+        E::serialize_g1(&pk.delta_g1, w)?;
+        E::serialize_g2(&pk.vk.delta_g2, w)?;
+        Ok(())
+    })?;
+    write_section(writer, 3, |w| {
+        serialize_g1_vec::<E, _>(&pk.vk.gamma_abc_g1, w)
+    })?;
+    write_section(writer, 4, |w| {
+        let num_coeffs = matrices.a_num_non_zero + matrices.b_num_non_zero + n_public + 1;
+        w.write_u32::<LittleEndian>(num_coeffs as u32)?;
+
+        for (matrix, constraints) in [&matrices.a, &matrices.b].iter().enumerate() {
+            for (constraint, lc) in constraints.iter().enumerate() {
+                for (value, signal) in lc {
+                    write_coeff::<E, _>(w, matrix, constraint, *signal, value)?;
+                }
+            }
+        }
+        // snarkjs adds a constraint for each public input, which Arkworks adds
+        // itself when building the QAP
+        for signal in 0..=n_public {
+            let constraint = matrices.num_constraints + signal;
+            write_coeff::<E, _>(w, 0, constraint, signal, &E::ScalarField::ONE)?;
+        }
+        Ok(())
+    })?;
+    write_section(writer, 5, |w| serialize_g1_vec::<E, _>(&pk.a_query, w))?;
+    write_section(writer, 6, |w| serialize_g1_vec::<E, _>(&pk.b_g1_query, w))?;
+    write_section(writer, 7, |w| serialize_g2_vec::<E, _>(&pk.b_g2_query, w))?;
+    write_section(writer, 8, |w| serialize_g1_vec::<E, _>(&pk.l_query, w))?;
+    write_section(writer, 9, |w| serialize_g1_vec::<E, _>(&pk.h_query, w))?;
+    write_section(writer, 10, |w| {
+        // circuit hash, followed by the number of contributions
+        w.write_all(&[0u8; 64])?;
+        w.write_u32::<LittleEndian>(0)?;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+// writes the section's id and contents, then goes back to fill in its size
+fn write_section<W: Write + Seek>(
+    writer: &mut W,
+    id: u32,
+    contents: impl FnOnce(&mut W) -> Result<()>,
+) -> Result<()> {
+    writer.write_u32::<LittleEndian>(id)?;
+    let size_position = writer.stream_position()?;
+    writer.write_u64::<LittleEndian>(0)?;
+
+    contents(writer)?;
+
+    let end = writer.stream_position()?;
+    writer.seek(SeekFrom::Start(size_position))?;
+    writer.write_u64::<LittleEndian>(end - size_position - 8)?;
+    writer.seek(SeekFrom::Start(end))?;
+    Ok(())
+}
+
+fn write_coeff<E: ZkeyEngine, W: Write>(
+    writer: &mut W,
+    matrix: usize,
+    constraint: usize,
+    signal: usize,
+    value: &E::ScalarField,
+) -> Result<()> {
+    writer.write_u32::<LittleEndian>(matrix as u32)?;
+    writer.write_u32::<LittleEndian>(constraint as u32)?;
+    writer.write_u32::<LittleEndian>(signal as u32)?;
+    E::serialize_coeff(value, writer)
+}
 
-            reader.seek(SeekFrom::Current(section_length as i64))?;
+/// Pairing engines whose zkey files can be read and written.
+pub trait ZkeyEngine: Pairing {
+    fn deserialize_g1<R: Read>(reader: &mut R) -> Result<Self::G1Affine>;
+    fn deserialize_g2<R: Read>(reader: &mut R) -> Result<Self::G2Affine>;
+    /// Reads a coefficient of the constraint matrices
+    fn deserialize_coeff<R: Read>(reader: &mut R) -> Result<Self::ScalarField>;
+
+    fn serialize_g1<W: Write>(point: &Self::G1Affine, writer: &mut W) -> Result<()>;
+    fn serialize_g2<W: Write>(point: &Self::G2Affine, writer: &mut W) -> Result<()>;
+    /// Writes a coefficient of the constraint matrices
+    fn serialize_coeff<W: Write>(coeff: &Self::ScalarField, writer: &mut W) -> Result<()>;
+}
+
+impl<P: BnConfig> ZkeyEngine for Bn<P>
+where
+    P::Fp: MontgomeryField,
+    <P::G1Config as CurveConfig>::ScalarField: MontgomeryField,
+{
+    fn deserialize_g1<R: Read>(reader: &mut R) -> Result<Self::G1Affine> {
+        deserialize_point(reader)
+    }
+
+    fn deserialize_g2<R: Read>(reader: &mut R) -> Result<Self::G2Affine> {
+        deserialize_point(reader)
+    }
+
+    fn deserialize_coeff<R: Read>(reader: &mut R) -> Result<Self::ScalarField> {
+        deserialize_field_fr(reader)
+    }
+
+    fn serialize_g1<W: Write>(point: &Self::G1Affine, writer: &mut W) -> Result<()> {
+        serialize_point(point, writer)
+    }
+
+    fn serialize_g2<W: Write>(point: &Self::G2Affine, writer: &mut W) -> Result<()> {
+        serialize_point(point, writer)
+    }
+
+    fn serialize_coeff<W: Write>(coeff: &Self::ScalarField, writer: &mut W) -> Result<()> {
+        serialize_field_fr(coeff, writer)
+    }
+}
+
+impl<P: Bls12Config> ZkeyEngine for Bls12<P>
+where
+    P::Fp: MontgomeryField,
+    <P::G1Config as CurveConfig>::ScalarField: MontgomeryField,
+{
+    fn deserialize_g1<R: Read>(reader: &mut R) -> Result<Self::G1Affine> {
+        deserialize_point(reader)
+    }
+
+    fn deserialize_g2<R: Read>(reader: &mut R) -> Result<Self::G2Affine> {
+        deserialize_point(reader)
+    }
+
+    fn deserialize_coeff<R: Read>(reader: &mut R) -> Result<Self::ScalarField> {
+        deserialize_field_fr(reader)
+    }
+
+    fn serialize_g1<W: Write>(point: &Self::G1Affine, writer: &mut W) -> Result<()> {
+        serialize_point(point, writer)
+    }
+
+    fn serialize_g2<W: Write>(point: &Self::G2Affine, writer: &mut W) -> Result<()> {
+        serialize_point(point, writer)
+    }
+
+    fn serialize_coeff<W: Write>(coeff: &Self::ScalarField, writer: &mut W) -> Result<()> {
+        serialize_field_fr(coeff, writer)
+    }
+}
+
+/// Fields whose elements are stored in zkey files in Montgomery form.
+pub trait MontgomeryField: Field {
+    fn deserialize_montgomery<R: Read>(reader: &mut R) -> Result<Self>;
+    fn serialize_montgomery<W: Write>(&self, writer: &mut W) -> Result<()>;
+}
+
+impl<P: MontConfig<N>, const N: usize> MontgomeryField for Fp<MontBackend<P, N>, N> {
+    // skips the multiplication by R because Circom points are already in Montgomery form
+    fn deserialize_montgomery<R: Read>(reader: &mut R) -> Result<Self> {
+        let bigint = BigInt::<N>::deserialize_uncompressed(reader)?;
+        // if you use Fp::new it multiplies by R
+        Ok(Fp::new_unchecked(bigint))
+    }
+
+    fn serialize_montgomery<W: Write>(&self, writer: &mut W) -> Result<()> {
+        // the inner BigInt is the Montgomery representation
+        writer.write_all(&self.0.to_bytes_le())?;
+        Ok(())
+    }
+}
+
+impl<P: Fp2Config> MontgomeryField for Fp2<P>
+where
+    P::Fp: MontgomeryField,
+{
+    fn deserialize_montgomery<R: Read>(reader: &mut R) -> Result<Self> {
+        let c0 = P::Fp::deserialize_montgomery(reader)?;
+        let c1 = P::Fp::deserialize_montgomery(reader)?;
+        Ok(Fp2::new(c0, c1))
+    }
+
+    fn serialize_montgomery<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.c0.serialize_montgomery(writer)?;
+        self.c1.serialize_montgomery(writer)
+    }
+}
+
+#[derive(Debug)]
+struct BinFile<'a, R> {
+    header: ZKeyHeader,
+    reader: &'a mut R,
+    // the whole zkey, when it's already in memory, e.g. mapped, so that the points are
+    // deserialized out of it rather than copied out of the reader first
+    bytes: Option<&'a [u8]>,
+}
+
+impl<'a, R: Read + Seek> BinFile<'a, R> {
+    fn new(reader: &'a mut R) -> Result<Self> {
+        let header = read_zkey_sections(reader)?;
+
+        // the other protocols' sections have the same ids but different contents, which
+        // would be misread as Groth16 points
+        reader.seek(SeekFrom::Start(header.section(1)?.position))?;
+        let protocol = reader.read_u32::<LittleEndian>()?;
+        if protocol != GROTH16_PROTOCOL {
+            return Err(Error::UnsupportedProtocol(protocol));
         }
 
         Ok(Self {
-            ftype: std::str::from_utf8(&magic[..]).unwrap().to_string(),
-            version,
-            sections,
+            header,
             reader,
+            bytes: None,
         })
     }
 
-    fn proving_key(&mut self) -> IoResult<ProvingKey<Bn254>> {
-        let header = self.groth_header()?;
-        let ic = self.ic(header.n_public)?;
+    #[cfg(feature = "mmap")]
+    fn with_bytes(self, bytes: &'a [u8]) -> Self {
+        Self {
+            bytes: Some(bytes),
+            ..self
+        }
+    }
 
-        let a_query = self.a_query(header.n_vars)?;
-        let b_g1_query = self.b_g1_query(header.n_vars)?;
-        let b_g2_query = self.b_g2_query(header.n_vars)?;
-        let l_query = self.l_query(header.n_vars - header.n_public - 1)?;
-        let h_query = self.h_query(header.domain_size as usize)?;
+    fn verifying_key<E: ZkeyEngine>(&mut self) -> Result<VerifyingKey<E>> {
+        let header = self.groth_header::<E>()?;
+        let ic = self.ic::<E>(header.n_public)?;
 
-        let vk = VerifyingKey::<Bn254> {
+        Ok(VerifyingKey::<E> {
             alpha_g1: header.verifying_key.alpha_g1,
             beta_g2: header.verifying_key.beta_g2,
             gamma_g2: header.verifying_key.gamma_g2,
             delta_g2: header.verifying_key.delta_g2,
             gamma_abc_g1: ic,
-        };
+        })
+    }
+
+    fn proving_key<E: ZkeyEngine>(&mut self) -> Result<ProvingKey<E>> {
+        let header = self.groth_header::<E>()?;
+        let vk = self.verifying_key::<E>()?;
 
-        let pk = ProvingKey::<Bn254> {
+        let a_query = self.a_query::<E>(header.n_vars)?;
+        let b_g1_query = self.b_g1_query::<E>(header.n_vars)?;
+        let b_g2_query = self.b_g2_query::<E>(header.n_vars)?;
+        let l_query = self.l_query::<E>(header.n_vars - header.n_public - 1)?;
+        let h_query = self.h_query::<E>(header.domain_size as usize)?;
+
+        let pk = ProvingKey::<E> {
             vk,
             beta_g1: header.verifying_key.beta_g1,
             delta_g1: header.verifying_key.delta_g1,
@@ -132,41 +761,63 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
         Ok(pk)
     }
 
-    fn get_section(&self, id: u32) -> Section {
-        self.sections.get(&id).unwrap()[0].clone()
+    fn get_section(&self, id: u32) -> Result<ZKeySection> {
+        self.header.section(id).cloned()
     }
 
-    fn groth_header(&mut self) -> IoResult<HeaderGroth> {
-        let section = self.get_section(2);
+    fn groth_header<E: ZkeyEngine>(&mut self) -> Result<HeaderGroth<E>> {
+        let section = self.get_section(2)?;
         let header = HeaderGroth::new(&mut self.reader, &section)?;
         Ok(header)
     }
 
-    fn ic(&mut self, n_public: usize) -> IoResult<Vec<G1Affine>> {
+    fn ic<E: ZkeyEngine>(&mut self, n_public: usize) -> Result<Vec<E::G1Affine>> {
         // the range is non-inclusive so we do +1 to get all inputs
-        self.g1_section(n_public + 1, 3)
+        self.g1_section::<E>(n_public + 1, 3)
     }
 
-    /// Returns the [`ConstraintMatrices`] corresponding to the zkey
-    pub fn matrices(&mut self) -> IoResult<ConstraintMatrices<Fr>> {
-        let header = self.groth_header()?;
-
-        let section = self.get_section(4);
+    // Passes each coefficient of section 4 to `visit`, without collecting them
+    fn coefficients<E: ZkeyEngine>(
+        &mut self,
+        mut visit: impl FnMut(ZKeyCoefficient<E::ScalarField>),
+    ) -> Result<()> {
+        let section = self.get_section(4)?;
         self.reader.seek(SeekFrom::Start(section.position))?;
         let num_coeffs: u32 = self.reader.read_u32::<LittleEndian>()?;
 
-        // insantiate AB
-        let mut matrices = vec![vec![vec![]; header.domain_size as usize]; 2];
-        let mut max_constraint_index = 0;
         for _ in 0..num_coeffs {
             let matrix: u32 = self.reader.read_u32::<LittleEndian>()?;
             let constraint: u32 = self.reader.read_u32::<LittleEndian>()?;
             let signal: u32 = self.reader.read_u32::<LittleEndian>()?;
 
-            let value: Fr = deserialize_field_fr(&mut self.reader)?;
+            let value = E::deserialize_coeff(&mut self.reader)?;
+            visit(ZKeyCoefficient {
+                matrix,
+                constraint,
+                signal,
+                value,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the [`ConstraintMatrices`] corresponding to the zkey
+    pub fn matrices<E: ZkeyEngine>(&mut self) -> Result<ConstraintMatrices<E::ScalarField>> {
+        let header = self.groth_header::<E>()?;
+
+        // insantiate AB
+        let mut matrices = vec![vec![vec![]; header.domain_size as usize]; 2];
+        let mut max_constraint_index = 0;
+        self.coefficients::<E>(|coefficient| {
+            let ZKeyCoefficient {
+                matrix,
+                constraint,
+                signal,
+                value,
+            } = coefficient;
             max_constraint_index = std::cmp::max(max_constraint_index, constraint);
             matrices[matrix as usize][constraint as usize].push((value, signal as usize));
-        }
+        })?;
 
         let num_constraints = max_constraint_index as usize - header.n_public;
         // Remove the public input constraints, Arkworks adds them later
@@ -180,7 +831,8 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
         let b_num_non_zero: usize = b.iter().map(|lc| lc.len()).sum();
         let matrices = ConstraintMatrices {
             num_instance_variables: header.n_public + 1,
-            num_witness_variables: header.n_vars - header.n_public,
+            // the instance variables include the constant one
+            num_witness_variables: header.n_vars - header.n_public - 1,
             num_constraints,
 
             a_num_non_zero,
@@ -195,57 +847,102 @@ impl<'a, R: Read + Seek> BinFile<'a, R> {
         Ok(matrices)
     }
 
-    fn a_query(&mut self, n_vars: usize) -> IoResult<Vec<G1Affine>> {
-        self.g1_section(n_vars, 5)
+    fn a_query<E: ZkeyEngine>(&mut self, n_vars: usize) -> Result<Vec<E::G1Affine>> {
+        self.g1_section::<E>(n_vars, 5)
     }
 
-    fn b_g1_query(&mut self, n_vars: usize) -> IoResult<Vec<G1Affine>> {
-        self.g1_section(n_vars, 6)
+    fn b_g1_query<E: ZkeyEngine>(&mut self, n_vars: usize) -> Result<Vec<E::G1Affine>> {
+        self.g1_section::<E>(n_vars, 6)
     }
 
-    fn b_g2_query(&mut self, n_vars: usize) -> IoResult<Vec<G2Affine>> {
-        self.g2_section(n_vars, 7)
+    fn b_g2_query<E: ZkeyEngine>(&mut self, n_vars: usize) -> Result<Vec<E::G2Affine>> {
+        self.g2_section::<E>(n_vars, 7)
     }
 
-    fn l_query(&mut self, n_vars: usize) -> IoResult<Vec<G1Affine>> {
-        self.g1_section(n_vars, 8)
+    fn l_query<E: ZkeyEngine>(&mut self, n_vars: usize) -> Result<Vec<E::G1Affine>> {
+        self.g1_section::<E>(n_vars, 8)
     }
 
-    fn h_query(&mut self, n_vars: usize) -> IoResult<Vec<G1Affine>> {
-        self.g1_section(n_vars, 9)
+    fn h_query<E: ZkeyEngine>(&mut self, n_vars: usize) -> Result<Vec<E::G1Affine>> {
+        self.g1_section::<E>(n_vars, 9)
     }
 
-    fn g1_section(&mut self, num: usize, section_id: usize) -> IoResult<Vec<G1Affine>> {
-        let section = self.get_section(section_id as u32);
-        self.reader.seek(SeekFrom::Start(section.position))?;
-        deserialize_g1_vec(self.reader, num as u32)
+    fn g1_section<E: ZkeyEngine>(
+        &mut self,
+        num: usize,
+        section_id: usize,
+    ) -> Result<Vec<E::G1Affine>> {
+        let point_size = 2 * base_field_size::<E>();
+        match self.seek_points(section_id as u32, num, point_size)? {
+            Some(bytes) => deserialize_points(bytes, point_size, |r| E::deserialize_g1(r)),
+            None => deserialize_g1_vec::<E, _>(self.reader, num as u32),
+        }
+    }
+
+    fn g2_section<E: ZkeyEngine>(
+        &mut self,
+        num: usize,
+        section_id: usize,
+    ) -> Result<Vec<E::G2Affine>> {
+        let point_size = 4 * base_field_size::<E>();
+        match self.seek_points(section_id as u32, num, point_size)? {
+            Some(bytes) => deserialize_points(bytes, point_size, |r| E::deserialize_g2(r)),
+            None => deserialize_g2_vec::<E, _>(self.reader, num as u32),
+        }
     }
 
-    fn g2_section(&mut self, num: usize, section_id: usize) -> IoResult<Vec<G2Affine>> {
-        let section = self.get_section(section_id as u32);
+    // Seeks to a section of `num` points, checking first that it holds exactly as many,
+    // as the parallel reader allocates them all up front: a corrupt count in the header
+    // would otherwise make it allocate far more than the file's size. Returns the
+    // section's bytes if the zkey is in memory.
+    fn seek_points(
+        &mut self,
+        section_id: u32,
+        num: usize,
+        point_size: usize,
+    ) -> Result<Option<&'a [u8]>> {
+        let section = self.get_section(section_id)?.clone();
+        if num.checked_mul(point_size) != Some(section.size) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "zkey section {} has {} bytes, not {} points of {} bytes",
+                    section_id, section.size, num, point_size
+                ),
+            )
+            .into());
+        }
+        if let Some(bytes) = self.bytes {
+            let start = section.position as usize;
+            return bytes
+                .get(start..)
+                .and_then(|bytes| bytes.get(..section.size))
+                .map(Some)
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
         self.reader.seek(SeekFrom::Start(section.position))?;
-        deserialize_g2_vec(self.reader, num as u32)
+        Ok(None)
     }
 }
 
-#[derive(Default, Clone, Debug, CanonicalDeserialize)]
-pub struct ZVerifyingKey {
-    alpha_g1: G1Affine,
-    beta_g1: G1Affine,
-    beta_g2: G2Affine,
-    gamma_g2: G2Affine,
-    delta_g1: G1Affine,
-    delta_g2: G2Affine,
+#[derive(Clone, Debug)]
+pub struct ZVerifyingKey<E: Pairing> {
+    alpha_g1: E::G1Affine,
+    beta_g1: E::G1Affine,
+    beta_g2: E::G2Affine,
+    gamma_g2: E::G2Affine,
+    delta_g1: E::G1Affine,
+    delta_g2: E::G2Affine,
 }
 
-impl ZVerifyingKey {
-    fn new<R: Read>(reader: &mut R) -> IoResult<Self> {
-        let alpha_g1 = deserialize_g1(reader)?;
-        let beta_g1 = deserialize_g1(reader)?;
-        let beta_g2 = deserialize_g2(reader)?;
-        let gamma_g2 = deserialize_g2(reader)?;
-        let delta_g1 = deserialize_g1(reader)?;
-        let delta_g2 = deserialize_g2(reader)?;
+impl<E: ZkeyEngine> ZVerifyingKey<E> {
+    fn new<R: Read>(reader: &mut R) -> Result<Self> {
+        let alpha_g1 = E::deserialize_g1(reader)?;
+        let beta_g1 = E::deserialize_g1(reader)?;
+        let beta_g2 = E::deserialize_g2(reader)?;
+        let gamma_g2 = E::deserialize_g2(reader)?;
+        let delta_g1 = E::deserialize_g1(reader)?;
+        let delta_g2 = E::deserialize_g2(reader)?;
 
         Ok(Self {
             alpha_g1,
@@ -259,15 +956,15 @@ impl ZVerifyingKey {
 }
 
 #[derive(Clone, Debug)]
-struct HeaderGroth {
+struct HeaderGroth<E: Pairing> {
     #[allow(dead_code)]
     n8q: u32,
     #[allow(dead_code)]
-    q: BigInteger256,
+    q: BigUint,
     #[allow(dead_code)]
     n8r: u32,
     #[allow(dead_code)]
-    r: BigInteger256,
+    r: BigUint,
 
     n_vars: usize,
     n_public: usize,
@@ -276,32 +973,33 @@ struct HeaderGroth {
     #[allow(dead_code)]
     power: u32,
 
-    verifying_key: ZVerifyingKey,
+    verifying_key: ZVerifyingKey<E>,
 }
 
-impl HeaderGroth {
-    fn new<R: Read + Seek>(reader: &mut R, section: &Section) -> IoResult<Self> {
+impl<E: ZkeyEngine> HeaderGroth<E> {
+    fn new<R: Read + Seek>(reader: &mut R, section: &ZKeySection) -> Result<Self> {
         reader.seek(SeekFrom::Start(section.position))?;
         Self::read(reader)
     }
 
-    fn read<R: Read>(mut reader: &mut R) -> IoResult<Self> {
-        // TODO: Impl From<u32> in Arkworks
-        let n8q: u32 = u32::deserialize_uncompressed(&mut reader)?;
-        // group order r of Bn254
-        let q = BigInteger256::deserialize_uncompressed(&mut reader)?;
+    fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        let n8q = reader.read_u32::<LittleEndian>()?;
+        // base field modulus
+        let q = read_prime(reader, n8q)?;
+        check_prime("base field prime q", &q, E::BaseField::MODULUS.into())?;
 
-        let n8r: u32 = u32::deserialize_uncompressed(&mut reader)?;
-        // Prime field modulus
-        let r = BigInteger256::deserialize_uncompressed(&mut reader)?;
+        let n8r = reader.read_u32::<LittleEndian>()?;
+        // group order r, i.e. the scalar field modulus
+        let r = read_prime(reader, n8r)?;
+        check_prime("scalar field prime r", &r, E::ScalarField::MODULUS.into())?;
 
-        let n_vars = u32::deserialize_uncompressed(&mut reader)? as usize;
-        let n_public = u32::deserialize_uncompressed(&mut reader)? as usize;
+        let n_vars = reader.read_u32::<LittleEndian>()? as usize;
+        let n_public = reader.read_u32::<LittleEndian>()? as usize;
 
-        let domain_size: u32 = u32::deserialize_uncompressed(&mut reader)?;
+        let domain_size = reader.read_u32::<LittleEndian>()?;
         let power = log2(domain_size as usize);
 
-        let verifying_key = ZVerifyingKey::new(&mut reader)?;
+        let verifying_key = ZVerifyingKey::new(reader)?;
 
         Ok(Self {
             n8q,
@@ -317,61 +1015,162 @@ impl HeaderGroth {
     }
 }
 
+fn read_prime<R: Read>(reader: &mut R, n8: u32) -> Result<BigUint> {
+    let mut buf = vec![0u8; n8 as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(BigUint::from_bytes_le(&buf))
+}
+
+// a mismatched prime means the zkey was generated for a different curve, in
+// which case the points and coefficients would be read into the wrong fields
+fn check_prime(name: &'static str, prime: &BigUint, modulus: BigUint) -> Result<()> {
+    if *prime != modulus {
+        return Err(Error::FieldMismatch {
+            name,
+            prime: prime.clone(),
+            modulus,
+        });
+    }
+    Ok(())
+}
+
 // need to divide by R, since snarkjs outputs the zkey with coefficients
 // multiplieid by R^2
-fn deserialize_field_fr<R: Read>(reader: &mut R) -> IoResult<Fr> {
-    let bigint = BigInteger256::deserialize_uncompressed(reader)?;
-    Ok(Fr::new_unchecked(Fr::new_unchecked(bigint).into_bigint()))
+fn deserialize_field_fr<F: PrimeField + MontgomeryField, R: Read>(reader: &mut R) -> Result<F> {
+    let fr = F::deserialize_montgomery(reader)?;
+    F::deserialize_montgomery(&mut &fr.into_bigint().to_bytes_le()[..])
 }
 
-// skips the multiplication by R because Circom points are already in Montgomery form
-fn deserialize_field<R: Read>(reader: &mut R) -> IoResult<Fq> {
-    let bigint = BigInteger256::deserialize_uncompressed(reader)?;
-    // if you use Fq::new it multiplies by R
-    Ok(Fq::new_unchecked(bigint))
+// multiplies by R twice to match deserialize_field_fr
+fn serialize_field_fr<F: PrimeField + MontgomeryField, W: Write>(
+    fr: &F,
+    writer: &mut W,
+) -> Result<()> {
+    let mut bytes = vec![];
+    fr.serialize_montgomery(&mut bytes)?;
+    F::from_le_bytes_mod_order(&bytes).serialize_montgomery(writer)
 }
 
-pub fn deserialize_field2<R: Read>(reader: &mut R) -> IoResult<Fq2> {
-    let c0 = deserialize_field(reader)?;
-    let c1 = deserialize_field(reader)?;
-    Ok(Fq2::new(c0, c1))
+fn serialize_point<P: SWCurveConfig, W: Write>(point: &Affine<P>, writer: &mut W) -> Result<()>
+where
+    P::BaseField: MontgomeryField,
+{
+    // the point at infinity is stored as (0, 0)
+    let zero = P::BaseField::ZERO;
+    let (x, y) = if point.infinity {
+        (&zero, &zero)
+    } else {
+        (&point.x, &point.y)
+    };
+    x.serialize_montgomery(writer)?;
+    y.serialize_montgomery(writer)
 }
 
-fn deserialize_g1<R: Read>(reader: &mut R) -> IoResult<G1Affine> {
-    let x = deserialize_field(reader)?;
-    let y = deserialize_field(reader)?;
+fn deserialize_point<P: SWCurveConfig, R: Read>(reader: &mut R) -> Result<Affine<P>>
+where
+    P::BaseField: MontgomeryField,
+{
+    let x = P::BaseField::deserialize_montgomery(reader)?;
+    let y = P::BaseField::deserialize_montgomery(reader)?;
     let infinity = x.is_zero() && y.is_zero();
     if infinity {
-        Ok(G1Affine::identity())
+        Ok(Affine::identity())
     } else {
-        Ok(G1Affine::new(x, y))
+        Ok(Affine::new(x, y))
     }
 }
 
-fn deserialize_g2<R: Read>(reader: &mut R) -> IoResult<G2Affine> {
-    let f1 = deserialize_field2(reader)?;
-    let f2 = deserialize_field2(reader)?;
-    let infinity = f1.is_zero() && f2.is_zero();
-    if infinity {
-        Ok(G2Affine::identity())
-    } else {
-        Ok(G2Affine::new(f1, f2))
-    }
+fn deserialize_g1_vec<E: ZkeyEngine, R: Read>(
+    reader: &mut R,
+    n_vars: u32,
+) -> Result<Vec<E::G1Affine>> {
+    #[cfg(feature = "parallel")]
+    let points = par_deserialize_vec(reader, n_vars, 2 * base_field_size::<E>(), |r| {
+        E::deserialize_g1(r)
+    });
+    #[cfg(not(feature = "parallel"))]
+    let points = deserialize_vec(reader, n_vars, E::deserialize_g1);
+    points
+}
+
+fn deserialize_g2_vec<E: ZkeyEngine, R: Read>(
+    reader: &mut R,
+    n_vars: u32,
+) -> Result<Vec<E::G2Affine>> {
+    #[cfg(feature = "parallel")]
+    let points = par_deserialize_vec(reader, n_vars, 4 * base_field_size::<E>(), |r| {
+        E::deserialize_g2(r)
+    });
+    #[cfg(not(feature = "parallel"))]
+    let points = deserialize_vec(reader, n_vars, E::deserialize_g2);
+    points
 }
 
-fn deserialize_g1_vec<R: Read>(reader: &mut R, n_vars: u32) -> IoResult<Vec<G1Affine>> {
-    (0..n_vars).map(|_| deserialize_g1(reader)).collect()
+fn serialize_g1_vec<E: ZkeyEngine, W: Write>(points: &[E::G1Affine], writer: &mut W) -> Result<()> {
+    points.iter().try_for_each(|p| E::serialize_g1(p, writer))
 }
 
-fn deserialize_g2_vec<R: Read>(reader: &mut R, n_vars: u32) -> IoResult<Vec<G2Affine>> {
-    (0..n_vars).map(|_| deserialize_g2(reader)).collect()
+fn serialize_g2_vec<E: ZkeyEngine, W: Write>(points: &[E::G2Affine], writer: &mut W) -> Result<()> {
+    points.iter().try_for_each(|p| E::serialize_g2(p, writer))
+}
+
+#[cfg_attr(feature = "parallel", allow(dead_code))]
+fn deserialize_vec<T, R: Read>(
+    reader: &mut R,
+    n_vars: u32,
+    deserialize: impl Fn(&mut R) -> Result<T>,
+) -> Result<Vec<T>> {
+    (0..n_vars).map(|_| deserialize(reader)).collect()
+}
+
+// points are fixed-size records, so the whole section is read at once and
+// split into chunks which are deserialized on the rayon thread pool
+#[cfg(feature = "parallel")]
+fn par_deserialize_vec<T: Send, R: Read>(
+    reader: &mut R,
+    n_vars: u32,
+    point_size: usize,
+    deserialize: impl Fn(&mut &[u8]) -> Result<T> + Sync,
+) -> Result<Vec<T>> {
+    let mut bytes = vec![0u8; n_vars as usize * point_size];
+    reader.read_exact(&mut bytes)?;
+    deserialize_points(&bytes, point_size, deserialize)
+}
+
+// Deserializes the points of a section which is in memory, on the rayon thread pool
+// with the parallel feature
+fn deserialize_points<T: Send>(
+    bytes: &[u8],
+    point_size: usize,
+    deserialize: impl Fn(&mut &[u8]) -> Result<T> + Sync,
+) -> Result<Vec<T>> {
+    #[cfg(feature = "parallel")]
+    let points = {
+        use rayon::prelude::*;
+        bytes
+            .par_chunks_exact(point_size)
+            .map(|mut chunk| deserialize(&mut chunk))
+            .collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let points = bytes
+        .chunks_exact(point_size)
+        .map(|mut chunk| deserialize(&mut chunk))
+        .collect();
+    points
+}
+
+/// Size in bytes of a base field element, i.e. the zkey's n8q
+fn base_field_size<E: Pairing>() -> usize {
+    E::BaseField::MODULUS.as_ref().len() * 8
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ark_bn254::{G1Projective, G2Projective};
+    use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
     use ark_crypto_primitives::snark::SNARK;
+    use ark_ff::BigInteger256;
     use num_bigint::BigUint;
     use serde_json::Value;
     use std::fs::File;
@@ -464,7 +1263,7 @@ mod tests {
     #[test]
     fn can_deser_fq() {
         let buf = fq_buf();
-        let fq = deserialize_field(&mut &buf[..]).unwrap();
+        let fq = Fq::deserialize_montgomery(&mut &buf[..]).unwrap();
         assert_eq!(fq, Fq::one());
     }
 
@@ -472,7 +1271,7 @@ mod tests {
     fn can_deser_g1() {
         let buf = g1_buf();
         assert_eq!(buf.len(), 64);
-        let g1 = deserialize_g1(&mut &buf[..]).unwrap();
+        let g1 = Bn254::deserialize_g1(&mut &buf[..]).unwrap();
         let expected = g1_one();
         assert_eq!(g1, expected);
     }
@@ -487,7 +1286,7 @@ mod tests {
             .collect::<Vec<_>>();
         let expected = vec![g1_one(); n_vars];
 
-        let de = deserialize_g1_vec(&mut &buf[..], n_vars as u32).unwrap();
+        let de = deserialize_g1_vec::<Bn254, _>(&mut &buf[..], n_vars as u32).unwrap();
         assert_eq!(expected, de);
     }
 
@@ -495,7 +1294,7 @@ mod tests {
     fn can_deser_g2() {
         let buf = g2_buf();
         assert_eq!(buf.len(), 128);
-        let g2 = deserialize_g2(&mut &buf[..]).unwrap();
+        let g2 = Bn254::deserialize_g2(&mut &buf[..]).unwrap();
 
         let expected = g2_one();
         assert_eq!(g2, expected);
@@ -511,7 +1310,7 @@ mod tests {
             .collect::<Vec<_>>();
         let expected = vec![g2_one(); n_vars];
 
-        let de = deserialize_g2_vec(&mut &buf[..], n_vars as u32).unwrap();
+        let de = deserialize_g2_vec::<Bn254, _>(&mut &buf[..], n_vars as u32).unwrap();
         assert_eq!(expected, de);
     }
 
@@ -534,7 +1333,7 @@ mod tests {
         let path = "./test-vectors/test.zkey";
         let mut file = File::open(path).unwrap();
         let mut binfile = BinFile::new(&mut file).unwrap();
-        let header = binfile.groth_header().unwrap();
+        let header = binfile.groth_header::<Bn254>().unwrap();
         assert_eq!(header.n_vars, 4);
         assert_eq!(header.n_public, 1);
         assert_eq!(header.domain_size, 4);
@@ -545,11 +1344,11 @@ mod tests {
     fn deser_key() {
         let path = "./test-vectors/test.zkey";
         let mut file = File::open(path).unwrap();
-        let (params, _matrices) = read_zkey(&mut file).unwrap();
+        let (params, _matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
 
         // Check IC
         let expected = vec![
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     11, 205, 205, 176, 2, 105, 129, 243, 153, 58, 137, 89, 61, 95, 99, 161, 133,
                     201, 153, 192, 119, 19, 113, 136, 43, 105, 47, 206, 166, 55, 81, 22, 154, 77,
@@ -558,7 +1357,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     118, 135, 198, 156, 63, 190, 210, 98, 194, 59, 169, 168, 204, 168, 76, 208,
                     109, 170, 24, 193, 57, 31, 184, 88, 234, 218, 118, 58, 107, 129, 90, 36, 230,
@@ -572,7 +1371,7 @@ mod tests {
 
         // Check A Query
         let expected = vec![
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     240, 165, 110, 187, 72, 39, 218, 59, 128, 85, 50, 174, 229, 1, 86, 58, 125,
                     244, 145, 205, 248, 253, 120, 2, 165, 140, 154, 55, 220, 253, 14, 19, 212, 106,
@@ -581,7 +1380,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     93, 53, 177, 82, 50, 5, 123, 116, 91, 35, 14, 196, 43, 180, 54, 15, 88, 144,
                     197, 105, 57, 167, 54, 5, 188, 109, 17, 89, 9, 223, 80, 1, 39, 193, 211, 168,
@@ -590,7 +1389,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     177, 47, 21, 237, 244, 73, 76, 98, 80, 10, 10, 142, 80, 145, 40, 254, 100, 214,
                     103, 33, 38, 84, 238, 248, 252, 181, 75, 32, 109, 16, 93, 23, 135, 157, 206,
@@ -599,7 +1398,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -612,7 +1411,7 @@ mod tests {
 
         // B G1 Query
         let expected = vec![
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -620,7 +1419,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -628,7 +1427,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -636,7 +1435,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     177, 47, 21, 237, 244, 73, 76, 98, 80, 10, 10, 142, 80, 145, 40, 254, 100, 214,
                     103, 33, 38, 84, 238, 248, 252, 181, 75, 32, 109, 16, 93, 23, 192, 95, 174, 93,
@@ -650,7 +1449,7 @@ mod tests {
 
         // B G2 Query
         let expected = vec![
-            deserialize_g2(
+            Bn254::deserialize_g2(
                 &mut &[
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -660,7 +1459,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g2(
+            Bn254::deserialize_g2(
                 &mut &[
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -670,7 +1469,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g2(
+            Bn254::deserialize_g2(
                 &mut &[
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
                     0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -680,7 +1479,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g2(
+            Bn254::deserialize_g2(
                 &mut &[
                     240, 25, 157, 232, 164, 49, 152, 204, 244, 190, 178, 178, 29, 133, 205, 175,
                     172, 28, 12, 123, 139, 202, 196, 13, 67, 165, 204, 42, 74, 40, 6, 36, 112, 104,
@@ -698,7 +1497,7 @@ mod tests {
 
         // Check L Query
         let expected = vec![
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     146, 142, 29, 235, 9, 162, 84, 255, 6, 119, 86, 214, 154, 18, 12, 190, 202, 19,
                     168, 45, 29, 76, 174, 130, 6, 59, 146, 15, 229, 82, 81, 40, 50, 25, 124, 247,
@@ -707,7 +1506,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     26, 32, 112, 226, 161, 84, 188, 236, 141, 226, 119, 169, 235, 218, 253, 176,
                     157, 184, 108, 243, 73, 122, 239, 217, 39, 190, 239, 105, 147, 190, 80, 47,
@@ -721,7 +1520,7 @@ mod tests {
 
         // Check H Query
         let expected = vec![
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     21, 76, 104, 34, 28, 236, 135, 204, 218, 16, 160, 115, 185, 44, 19, 62, 43, 24,
                     57, 99, 207, 105, 10, 139, 195, 60, 17, 57, 85, 244, 167, 10, 166, 166, 165,
@@ -730,7 +1529,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     144, 175, 205, 119, 119, 192, 11, 10, 148, 224, 87, 161, 157, 231, 101, 208,
                     55, 15, 13, 16, 24, 59, 9, 22, 63, 215, 255, 30, 77, 188, 71, 37, 84, 227, 59,
@@ -739,7 +1538,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     129, 169, 52, 179, 66, 88, 123, 199, 222, 69, 24, 17, 219, 235, 118, 195, 156,
                     210, 14, 21, 76, 155, 178, 210, 223, 4, 233, 5, 8, 18, 156, 24, 82, 68, 183,
@@ -748,7 +1547,7 @@ mod tests {
                 ][..],
             )
             .unwrap(),
-            deserialize_g1(
+            Bn254::deserialize_g1(
                 &mut &[
                     207, 61, 229, 214, 21, 61, 103, 165, 93, 145, 54, 138, 143, 214, 5, 83, 183,
                     22, 174, 87, 108, 59, 99, 96, 19, 20, 25, 139, 114, 238, 198, 40, 182, 88, 1,
@@ -765,7 +1564,7 @@ mod tests {
     fn deser_vk() {
         let path = "./test-vectors/test.zkey";
         let mut file = File::open(path).unwrap();
-        let (params, _matrices) = read_zkey(&mut file).unwrap();
+        let (params, _matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
 
         let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
         let json: Value = serde_json::from_str(&json).unwrap();
@@ -777,6 +1576,539 @@ mod tests {
         assert_eq!(json_to_g1_vec(&json, "IC"), params.vk.gamma_abc_g1);
     }
 
+    #[test]
+    fn deser_key_from_reader() {
+        let path = "./test-vectors/test.zkey";
+        // chaining makes the file a reader which can't seek
+        let reader = File::open(path).unwrap().chain(std::io::empty());
+        let (params, matrices) = read_zkey_from_reader::<Bn254, _>(reader).unwrap();
+
+        let mut file = File::open(path).unwrap();
+        let (expected_params, expected_matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
+        assert_eq!(params, expected_params);
+        assert_eq!(matrices, expected_matrices);
+    }
+
+    #[test]
+    fn deser_vk_only() {
+        let path = "./test-vectors/test.zkey";
+        let mut file = File::open(path).unwrap();
+        let vk = read_verifying_key::<Bn254, _>(&mut file).unwrap();
+
+        let mut file = File::open(path).unwrap();
+        let (params, _matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
+        assert_eq!(vk, params.vk);
+
+        // the proving key's sections aren't read, so invalid points aren't noticed
+        let mut zkey = std::fs::read(path).unwrap();
+        let header = read_zkey_sections(&mut std::io::Cursor::new(&zkey)).unwrap();
+        let points_a = header.section(5).unwrap();
+        zkey[points_a.position as usize..][..points_a.size].fill(0xff);
+        let vk = read_verifying_key::<Bn254, _>(&mut std::io::Cursor::new(&zkey)).unwrap();
+        assert_eq!(vk, params.vk);
+    }
+
+    #[test]
+    fn keys() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (pk, vk) = read_zkey_keys::<Bn254, _>(&mut file).unwrap();
+
+        // exported with `snarkjs zkey export verificationkey`
+        let json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(vk, crate::snarkjs::verifying_key_from_json(&json).unwrap());
+
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let (params, _matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
+        assert_eq!(pk, params);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn parallel_points() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let header = read_zkey_sections(&mut file).unwrap();
+        let n_vars = (header.section(5).unwrap().size / 64) as u32;
+
+        for id in [3, 5, 6, 8, 9] {
+            let section = header.section(id).unwrap();
+            let n_points = (section.size / 64) as u32;
+            file.seek(SeekFrom::Start(section.position)).unwrap();
+            let sequential = deserialize_vec(&mut file, n_points, Bn254::deserialize_g1);
+            file.seek(SeekFrom::Start(section.position)).unwrap();
+            let parallel =
+                par_deserialize_vec(&mut file, n_points, 64, |r| Bn254::deserialize_g1(r));
+            assert_eq!(sequential.unwrap(), parallel.unwrap());
+        }
+
+        let section = header.section(7).unwrap();
+        file.seek(SeekFrom::Start(section.position)).unwrap();
+        let sequential = deserialize_vec(&mut file, n_vars, Bn254::deserialize_g2);
+        file.seek(SeekFrom::Start(section.position)).unwrap();
+        let parallel = deserialize_g2_vec::<Bn254, _>(&mut file, n_vars);
+        assert_eq!(sequential.unwrap(), parallel.unwrap());
+    }
+
+    #[test]
+    fn write_roundtrip() {
+        let original = std::fs::read("./test-vectors/test.zkey").unwrap();
+        let (params, matrices) =
+            read_zkey::<Bn254, _>(&mut std::io::Cursor::new(&original)).unwrap();
+
+        let mut zkey = std::io::Cursor::new(vec![]);
+        write_zkey(&params, &matrices, &mut zkey).unwrap();
+        let zkey = zkey.into_inner();
+        let (written_params, written_matrices) =
+            read_zkey::<Bn254, _>(&mut std::io::Cursor::new(&zkey)).unwrap();
+        assert_eq!(written_params, params);
+        assert_eq!(written_matrices, matrices);
+
+        // only the contributions differ from the snarkjs output
+        let original_header = read_zkey_sections(&mut std::io::Cursor::new(&original)).unwrap();
+        let header = read_zkey_sections(&mut std::io::Cursor::new(&zkey)).unwrap();
+        let contents = |zkey: &[u8], header: &ZKeyHeader, id| {
+            let section = header.section(id).unwrap();
+            let start = section.position as usize;
+            zkey[start..start + section.size].to_vec()
+        };
+        for id in 1..=9 {
+            assert_eq!(
+                contents(&zkey, &header, id),
+                contents(&original, &original_header, id),
+                "section {id}"
+            );
+        }
+    }
+
+    #[test]
+    fn write_without_ic() {
+        let (mut params, matrices) =
+            read_zkey::<Bn254, _>(&mut File::open("./test-vectors/test.zkey").unwrap()).unwrap();
+        params.vk.gamma_abc_g1.clear();
+
+        let mut zkey = std::io::Cursor::new(vec![]);
+        let err = write_zkey(&params, &matrices, &mut zkey).unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)));
+    }
+
+    #[test]
+    fn bundle() {
+        let zkey = std::fs::read("./test-vectors/test.zkey").unwrap();
+        let expected =
+            read_zkey::<Bn254, _>(&mut File::open("./test-vectors/test.zkey").unwrap()).unwrap();
+
+        // two copies of the zkey, after a made up table of contents
+        let toc = b"circuits: a, b\n";
+        let bundle = [&toc[..], &zkey, &zkey].concat();
+        let len = zkey.len() as u64;
+        let mut reader = std::io::Cursor::new(&bundle);
+        for offset in [toc.len() as u64, toc.len() as u64 + len] {
+            let (pk, matrices) = read_zkey_at::<Bn254, _>(&mut reader, offset, len).unwrap();
+            assert_eq!(pk, expected.0);
+            assert_eq!(matrices, expected.1);
+
+            let mut window = ZKeyWindow::new(&mut reader, offset, len).unwrap();
+            let vk = read_verifying_key::<Bn254, _>(&mut window).unwrap();
+            assert_eq!(vk, expected.0.vk);
+            assert_eq!(window.seek(SeekFrom::End(0)).unwrap(), len);
+            assert_eq!(window.read(&mut [0; 8]).unwrap(), 0);
+        }
+
+        // a window cut short doesn't read into the next zkey
+        let offset = toc.len() as u64;
+        assert!(read_zkey_at::<Bn254, _>(&mut reader, offset, len / 2).is_err());
+    }
+
+    #[test]
+    fn sections() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let header = read_zkey_sections(&mut file).unwrap();
+        assert_eq!(header.ftype, "zkey");
+        assert_eq!(header.version, 1);
+        assert!(header.missing_sections().is_empty());
+        // the header section starts after the magic, version, count and section 1
+        let groth_header = header.section(2).unwrap();
+        assert_eq!(groth_header.position, 12 + 12 + 4 + 12);
+
+        let file_len = file.metadata().unwrap().len();
+        let end = header
+            .sections
+            .values()
+            .flatten()
+            .map(|section| section.position + section.size as u64)
+            .max();
+        assert_eq!(end, Some(file_len));
+    }
+
+    #[test]
+    fn points_count_mismatch() {
+        let original = std::fs::read("./test-vectors/test.zkey").unwrap();
+        let header = read_zkey_sections(&mut std::io::Cursor::new(&original)).unwrap();
+        // n_vars follows n8q, q, n8r and r in the Groth16 header
+        let offset = header.section(2).unwrap().position as usize + 4 + 32 + 4 + 32;
+
+        // a huge count is rejected before allocating the points
+        for n_vars in [5, u32::MAX] {
+            let mut zkey = original.clone();
+            zkey[offset..offset + 4].copy_from_slice(&n_vars.to_le_bytes());
+            let err = read_zkey::<Bn254, _>(&mut std::io::Cursor::new(zkey)).unwrap_err();
+            assert!(
+                matches!(&err, Error::Io(err) if err.kind() == std::io::ErrorKind::InvalidData),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn coefficients() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let coefficients = read_zkey_coefficients::<Bn254, _>(&mut file).unwrap();
+
+        // the count the section starts with
+        file.rewind().unwrap();
+        let section = read_zkey_sections(&mut file)
+            .unwrap()
+            .section(4)
+            .unwrap()
+            .clone();
+        file.seek(SeekFrom::Start(section.position)).unwrap();
+        let num_coeffs = file.read_u32::<LittleEndian>().unwrap();
+        assert_eq!(coefficients.len(), num_coeffs as usize);
+        // each record is 3 u32s followed by an element of the scalar field
+        assert_eq!(section.size, 4 + coefficients.len() * (12 + 32));
+
+        // the rows of the circuit's constraints are those of the matrices
+        file.rewind().unwrap();
+        let (_, matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
+        let mut a = vec![vec![]; matrices.num_constraints];
+        let mut b = vec![vec![]; matrices.num_constraints];
+        for c in &coefficients {
+            assert!(c.matrix < 2 && (c.signal as usize) < 4);
+            let rows = if c.matrix == 0 { &mut a } else { &mut b };
+            if let Some(row) = rows.get_mut(c.constraint as usize) {
+                row.push((c.value, c.signal as usize));
+            }
+        }
+        assert_eq!((a, b), (matrices.a.clone(), matrices.b.clone()));
+
+        // snarkjs' rows of the constant one and the public signal, in A
+        let extra = coefficients
+            .iter()
+            .filter(|c| c.constraint as usize >= matrices.num_constraints)
+            .map(|c| (c.matrix, c.signal, c.value))
+            .collect::<Vec<_>>();
+        assert_eq!(extra, [(0, 0, Fr::from(1u32)), (0, 1, Fr::from(1u32))]);
+    }
+
+    #[test]
+    fn metadata() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let metadata = read_zkey_metadata(&mut file).unwrap();
+        assert_eq!(metadata.protocol, 1);
+        assert_eq!(metadata.curve, Some("bn128"));
+        assert_eq!(metadata.q, Fq::MODULUS.into());
+        assert_eq!(metadata.r, Fr::MODULUS.into());
+        assert_eq!((metadata.n_vars, metadata.n_public), (4, 1));
+        assert_eq!((metadata.domain_size, metadata.power), (4, 2));
+
+        // only the headers of a BLS12-381 zkey
+        use ark_bls12_381::{Fq as BlsFq, Fr as BlsFr};
+        let groth_header = [
+            &48u32.to_le_bytes()[..],
+            &BlsFq::MODULUS.to_bytes_le(),
+            &32u32.to_le_bytes(),
+            &BlsFr::MODULUS.to_bytes_le(),
+            &10u32.to_le_bytes(),
+            &2u32.to_le_bytes(),
+            &16u32.to_le_bytes(),
+        ]
+        .concat();
+        let mut zkey = std::io::Cursor::new(b"zkey".to_vec());
+        zkey.set_position(4);
+        zkey.write_u32::<LittleEndian>(1).unwrap();
+        zkey.write_u32::<LittleEndian>(2).unwrap();
+        write_section(&mut zkey, 1, |w| Ok(w.write_u32::<LittleEndian>(1)?)).unwrap();
+        write_section(&mut zkey, 2, |w| Ok(w.write_all(&groth_header)?)).unwrap();
+
+        zkey.set_position(0);
+        let metadata = read_zkey_metadata(&mut zkey).unwrap();
+        assert_eq!(metadata.curve, Some("bls12381"));
+        assert_eq!(metadata.q, BlsFq::MODULUS.into());
+        assert_eq!(metadata.r, BlsFr::MODULUS.into());
+        assert_eq!((metadata.n_vars, metadata.n_public), (10, 2));
+        assert_eq!((metadata.domain_size, metadata.power), (16, 4));
+    }
+
+    #[test]
+    fn unsupported_protocol() {
+        let original = std::fs::read("./test-vectors/test.zkey").unwrap();
+        let header = read_zkey_sections(&mut std::io::Cursor::new(&original)).unwrap();
+        let protocol = header.section(1).unwrap().position as usize;
+        assert_eq!(
+            original[protocol..protocol + 4],
+            GROTH16_PROTOCOL.to_le_bytes()
+        );
+
+        // PLONK and FFLONK zkeys
+        for id in [2u32, 10] {
+            let mut zkey = original.clone();
+            zkey[protocol..protocol + 4].copy_from_slice(&id.to_le_bytes());
+
+            let reader = || std::io::Cursor::new(&zkey);
+            assert_eq!(read_zkey_metadata(&mut reader()).unwrap().protocol, id);
+            let err = read_zkey::<Bn254, _>(&mut reader()).unwrap_err();
+            assert!(
+                matches!(err, Error::UnsupportedProtocol(p) if p == id),
+                "{}",
+                err
+            );
+            let err = read_verifying_key::<Bn254, _>(&mut reader()).unwrap_err();
+            assert!(
+                matches!(err, Error::UnsupportedProtocol(p) if p == id),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn contributions() {
+        let mut file = File::open("./test-vectors/test.zkey").unwrap();
+        let contributions = read_zkey_contributions::<Bn254, _>(&mut file).unwrap();
+        assert_eq!(contributions.cs_hash.len(), 64);
+        assert!(contributions.contributions.is_empty());
+
+        // a named contribution followed by a beacon, laid out as by snarkjs
+        let (g1, g2) = (g1_one(), g2_one());
+        let p = |k: u64| G1Affine::from(g1 * Fr::from(k));
+        let mut zkey = std::io::Cursor::new(b"zkey".to_vec());
+        zkey.set_position(4);
+        zkey.write_u32::<LittleEndian>(1).unwrap();
+        zkey.write_u32::<LittleEndian>(1).unwrap();
+        write_section(&mut zkey, 10, |w| {
+            w.write_all(&[7u8; 64])?;
+            w.write_u32::<LittleEndian>(2)?;
+
+            for (i, params) in [
+                &[1u8, 5, b'a', b'l', b'i', b'c', b'e'][..],
+                &[2, 10, 3, 2, 0xab, 0xcd],
+            ]
+            .iter()
+            .enumerate()
+            {
+                for point in [p(2 + i as u64), p(3), p(15)] {
+                    Bn254::serialize_g1(&point, w)?;
+                }
+                Bn254::serialize_g2(&g2, w)?;
+                w.write_all(&[i as u8; 64])?;
+                w.write_u32::<LittleEndian>(i as u32)?;
+                w.write_u32::<LittleEndian>(params.len() as u32)?;
+                w.write_all(params)?;
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        zkey.set_position(0);
+        let contributions = read_zkey_contributions::<Bn254, _>(&mut zkey).unwrap();
+        assert_eq!(contributions.cs_hash, [7; 64]);
+        let [first, beacon] = &contributions.contributions[..] else {
+            panic!("expected 2 contributions");
+        };
+        assert_eq!(first.delta_after, p(2));
+        assert_eq!((first.g1_s, first.g1_sx, first.g2_spx), (p(3), p(15), g2));
+        assert_eq!(first.transcript, [0; 64]);
+        assert_eq!(first.contribution_type, 0);
+        assert_eq!(first.name.as_deref(), Some("alice"));
+        assert_eq!(
+            (first.num_iterations_exp, &first.beacon_hash),
+            (None, &None)
+        );
+
+        assert_eq!(beacon.delta_after, p(3));
+        assert_eq!(beacon.contribution_type, 1);
+        assert_eq!(beacon.name, None);
+        assert_eq!(beacon.num_iterations_exp, Some(10));
+        assert_eq!(beacon.beacon_hash.as_deref(), Some(&[0xab, 0xcd][..]));
+
+        // a corrupt parameters length is rejected before allocating it
+        let mut zkey = zkey.into_inner();
+        let params_len = 24 + 68 + 3 * 64 + 128 + 64 + 4;
+        zkey[params_len..params_len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err =
+            read_zkey_contributions::<Bn254, _>(&mut std::io::Cursor::new(&zkey)).unwrap_err();
+        assert!(
+            matches!(&err, Error::Io(err) if err.kind() == std::io::ErrorKind::InvalidData),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn missing_section() {
+        let mut zkey = b"zkey".to_vec();
+        zkey.extend_from_slice(&1u32.to_le_bytes());
+        zkey.extend_from_slice(&1u32.to_le_bytes());
+        zkey.extend_from_slice(&1u32.to_le_bytes());
+        zkey.extend_from_slice(&4u64.to_le_bytes());
+        zkey.extend_from_slice(&1u32.to_le_bytes());
+
+        let header = read_zkey_sections(&mut std::io::Cursor::new(&zkey)).unwrap();
+        assert_eq!(header.missing_sections(), vec![2, 3, 4, 5, 6, 7, 8, 9]);
+
+        let err = read_zkey::<Bn254, _>(&mut std::io::Cursor::new(&zkey)).unwrap_err();
+        assert!(matches!(err, Error::MissingSection(2)));
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn deser_key_mmap() {
+        let path = "./test-vectors/test.zkey";
+        let file = File::open(path).unwrap();
+        let mmap = unsafe { memmap2::Mmap::map(&file).unwrap() };
+        let (params, matrices) = read_zkey_mmap::<Bn254>(&mmap).unwrap();
+
+        let mut file = File::open(path).unwrap();
+        let (expected_params, expected_matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
+        assert_eq!(params, expected_params);
+        assert_eq!(matrices, expected_matrices);
+    }
+
+    #[test]
+    fn mismatched_scalar_prime() {
+        let mut zkey = std::fs::read("./test-vectors/test.zkey").unwrap();
+        let r = Fr::MODULUS.to_bytes_le();
+        let pos = zkey.windows(r.len()).position(|w| w == r).unwrap();
+        zkey[pos] ^= 1;
+
+        let err = read_zkey::<Bn254, _>(&mut std::io::Cursor::new(zkey)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FieldMismatch {
+                name: "scalar field prime r",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn deser_key_bls12_381() {
+        use ark_bls12_381::{Bls12_381, Fq as BlsFq, Fr as BlsFr};
+        use ark_std::UniformRand;
+
+        let rng = &mut ark_std::test_rng();
+        let (n_vars, n_public, domain_size) = (4, 1, 4);
+        fn rand_vec<T: UniformRand>(rng: &mut impl ark_std::rand::Rng, n: usize) -> Vec<T> {
+            (0..n).map(|_| T::rand(rng)).collect()
+        }
+        let pk = ProvingKey::<Bls12_381> {
+            vk: VerifyingKey {
+                alpha_g1: UniformRand::rand(rng),
+                beta_g2: UniformRand::rand(rng),
+                gamma_g2: UniformRand::rand(rng),
+                delta_g2: UniformRand::rand(rng),
+                gamma_abc_g1: rand_vec(rng, n_public + 1),
+            },
+            beta_g1: UniformRand::rand(rng),
+            delta_g1: UniformRand::rand(rng),
+            a_query: rand_vec(rng, n_vars),
+            b_g1_query: rand_vec(rng, n_vars),
+            b_g2_query: rand_vec(rng, n_vars),
+            h_query: rand_vec(rng, domain_size),
+            l_query: rand_vec(rng, n_vars - n_public - 1),
+        };
+        // a single a * b constraint, followed by the public input constraints
+        let coeffs = [
+            (0, 0, 2, BlsFr::from(3)),
+            (1, 0, 3, BlsFr::from(5)),
+            (0, 1, 0, BlsFr::from(1)),
+            (0, 2, 1, BlsFr::from(1)),
+        ];
+
+        let zkey = bls12_381_zkey(&pk, n_public, domain_size, &coeffs);
+        let (params, matrices) =
+            read_zkey::<Bls12_381, _>(&mut std::io::Cursor::new(zkey)).unwrap();
+        assert_eq!(params, pk);
+        assert_eq!(matrices.num_constraints, 1);
+        assert_eq!(matrices.a, vec![vec![(BlsFr::from(3), 2)]]);
+        assert_eq!(matrices.b, vec![vec![(BlsFr::from(5), 3)]]);
+
+        // the BLS12-381 key cannot be read as a BN254 one
+        let zkey = bls12_381_zkey(&pk, n_public, domain_size, &coeffs);
+        let err = read_zkey::<Bn254, _>(&mut std::io::Cursor::new(zkey)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FieldMismatch {
+                name: "base field prime q",
+                ..
+            }
+        ));
+
+        // Encodes the key in the zkey layout, with all field elements in Montgomery form
+        fn bls12_381_zkey(
+            pk: &ProvingKey<Bls12_381>,
+            n_public: usize,
+            domain_size: usize,
+            coeffs: &[(u32, u32, u32, BlsFr)],
+        ) -> Vec<u8> {
+            let fq = |f: &BlsFq| f.0.to_bytes_le();
+            let g1 = |p: &ark_bls12_381::G1Affine| [fq(&p.x), fq(&p.y)].concat();
+            let g2 = |p: &ark_bls12_381::G2Affine| {
+                [fq(&p.x.c0), fq(&p.x.c1), fq(&p.y.c0), fq(&p.y.c1)].concat()
+            };
+            let u32_bytes = |n: usize| (n as u32).to_le_bytes().to_vec();
+
+            let header = [
+                u32_bytes(48),
+                BlsFq::MODULUS.to_bytes_le(),
+                u32_bytes(32),
+                BlsFr::MODULUS.to_bytes_le(),
+                u32_bytes(pk.a_query.len()),
+                u32_bytes(n_public),
+                u32_bytes(domain_size),
+                g1(&pk.vk.alpha_g1),
+                g1(&pk.beta_g1),
+                g2(&pk.vk.beta_g2),
+                g2(&pk.vk.gamma_g2),
+                g1(&pk.delta_g1),
+                g2(&pk.vk.delta_g2),
+            ]
+            .concat();
+            // coefficients are stored multiplied by R^2
+            let r = BlsFr::from_bigint(BlsFr::R).unwrap();
+            let mut coeffs_section = u32_bytes(coeffs.len());
+            for (matrix, constraint, signal, value) in coeffs {
+                coeffs_section.extend_from_slice(&matrix.to_le_bytes());
+                coeffs_section.extend_from_slice(&constraint.to_le_bytes());
+                coeffs_section.extend_from_slice(&signal.to_le_bytes());
+                coeffs_section.extend_from_slice(&(*value * r).0.to_bytes_le());
+            }
+
+            let sections = [
+                u32_bytes(1),
+                header,
+                pk.vk.gamma_abc_g1.iter().flat_map(g1).collect(),
+                coeffs_section,
+                pk.a_query.iter().flat_map(g1).collect(),
+                pk.b_g1_query.iter().flat_map(g1).collect(),
+                pk.b_g2_query.iter().flat_map(g2).collect(),
+                pk.l_query.iter().flat_map(g1).collect(),
+                pk.h_query.iter().flat_map(g1).collect(),
+            ];
+
+            let mut zkey = b"zkey".to_vec();
+            zkey.extend_from_slice(&1u32.to_le_bytes());
+            zkey.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+            for (i, section) in sections.iter().enumerate() {
+                zkey.extend_from_slice(&(i as u32 + 1).to_le_bytes());
+                zkey.extend_from_slice(&(section.len() as u64).to_le_bytes());
+                zkey.extend_from_slice(section);
+            }
+            zkey
+        }
+    }
+
     fn json_to_g1(json: &Value, key: &str) -> G1Affine {
         let els: Vec<String> = json
             .get(key)
@@ -846,7 +2178,7 @@ mod tests {
     fn verify_proof_with_zkey_with_r1cs() {
         let path = "./test-vectors/test.zkey";
         let mut file = File::open(path).unwrap();
-        let (params, _matrices) = read_zkey(&mut file).unwrap(); // binfile.proving_key().unwrap();
+        let (params, _matrices) = read_zkey::<Bn254, _>(&mut file).unwrap(); // binfile.proving_key().unwrap();
 
         let cfg = CircomConfig::<Fr>::new(
             "./test-vectors/mycircuit.wasm",
@@ -875,7 +2207,7 @@ mod tests {
     fn verify_proof_with_zkey_without_r1cs() {
         let path = "./test-vectors/test.zkey";
         let mut file = File::open(path).unwrap();
-        let (params, matrices) = read_zkey(&mut file).unwrap();
+        let (params, matrices) = read_zkey::<Bn254, _>(&mut file).unwrap();
 
         let mut wtns = WitnessCalculator::new("./test-vectors/mycircuit.wasm").unwrap();
         let mut inputs: HashMap<String, Vec<num_bigint::BigInt>> = HashMap::new();
@@ -1,6 +1,10 @@
-use ark_circom::{CircomBuilder, CircomConfig};
+use ark_circom::{
+    read_zkey, snarkjs, CircomBuilder, CircomConfig, CircomReduction, WitnessCalculator,
+};
 use ark_std::rand::thread_rng;
 use color_eyre::Result;
+use num_bigint::BigInt;
+use std::fs::File;
 
 use ark_bn254::{Bn254, Fr};
 use ark_crypto_primitives::snark::SNARK;
@@ -107,3 +111,349 @@ fn witness_generation_circom2() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn groth16_proof_seeded() -> Result<()> {
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let mut builder = CircomBuilder::new(cfg);
+    builder.push_input("a", 3);
+    builder.push_input("b", 11);
+
+    let circom = builder.setup();
+    let mut rng = thread_rng();
+    let params = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+        circom, &mut rng,
+    )?;
+
+    let circom = builder.build()?;
+    let inputs = circom.get_public_inputs().unwrap();
+
+    let proof = circom.clone().prove_with_seed(&params, 42)?;
+    assert_eq!(proof, circom.clone().prove_with_seed(&params, 42)?);
+    assert_ne!(proof, circom.prove_with_seed(&params, 43)?);
+
+    let pvk = GrothBn::process_vk(&params.vk).unwrap();
+    assert!(GrothBn::verify_with_processed_vk(&pvk, &inputs, &proof)?);
+
+    Ok(())
+}
+
+#[test]
+fn groth16_proof_snarkjs_zkey() -> Result<()> {
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, _) = read_zkey(&mut file)?;
+    let json = serde_json::from_reader(File::open("./test-vectors/verification_key.json")?)?;
+    let vk = snarkjs::verifying_key_from_json(&json)?;
+    assert_eq!(vk, params.vk);
+
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let mut builder = CircomBuilder::new(cfg);
+    builder.push_input("a", 3);
+    builder.push_input("b", 11);
+    let circom = builder.build()?;
+    let inputs = circom.get_public_inputs().unwrap();
+
+    // the zkey's H query is only compatible with snarkjs' witness map
+    let proof = circom.clone().prove_with_seed(&params, 0)?;
+    assert!(GrothBn::verify(&vk, &inputs, &proof)?);
+
+    let mut rng = thread_rng();
+    let proof = GrothBn::prove(&params, circom, &mut rng)?;
+    assert!(!GrothBn::verify(&vk, &inputs, &proof)?);
+
+    Ok(())
+}
+
+#[test]
+fn groth16_prove_verify() -> Result<()> {
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, _) = read_zkey(&mut file)?;
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+
+    let inputs = vec![
+        ("a".to_string(), vec![BigInt::from(3)]),
+        ("b".to_string(), vec![BigInt::from(11)]),
+    ];
+    let (proof, public_inputs) = ark_circom::prove(cfg, inputs, &params)?;
+    assert_eq!(public_inputs, vec![Fr::from(33u32)]);
+    assert!(ark_circom::verify(&params.vk, &proof, &public_inputs)?);
+    assert!(!ark_circom::verify(&params.vk, &proof, &[Fr::from(34u32)])?);
+
+    let pvk = ark_circom::prepare_verifying_key(&params.vk);
+    assert!(ark_circom::verify_with_prepared(
+        &pvk,
+        &proof,
+        &public_inputs
+    )?);
+    assert!(!ark_circom::verify_with_prepared(
+        &pvk,
+        &proof,
+        &[Fr::from(34u32)]
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn groth16_generated_test_keys() -> Result<()> {
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let mut builder = CircomBuilder::new(cfg);
+    builder.push_input("a", 3);
+    builder.push_input("b", 11);
+
+    let (pk, vk) = ark_circom::generate_test_keys(&builder.setup(), &mut thread_rng())?;
+    assert_eq!(vk, pk.vk);
+
+    let circom = builder.build()?;
+    let inputs = circom.get_public_inputs().unwrap();
+    let proof = circom.prove(&pk, &mut thread_rng())?;
+    assert!(ark_circom::verify(&vk, &proof, &inputs)?);
+    assert!(!ark_circom::verify(&vk, &proof, &[Fr::from(34u32)])?);
+
+    Ok(())
+}
+
+#[test]
+fn groth16_proof_roundtrip() -> Result<()> {
+    use ark_serialize::Compress;
+
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, _) = read_zkey(&mut file)?;
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let inputs = vec![
+        ("a".to_string(), vec![BigInt::from(3)]),
+        ("b".to_string(), vec![BigInt::from(11)]),
+    ];
+    let (proof, public_inputs) = ark_circom::prove(cfg, inputs, &params)?;
+
+    for (compress, size) in [(Compress::Yes, 128), (Compress::No, 256)] {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        ark_circom::write_proof(&proof, &mut cursor, compress)?;
+        assert_eq!(cursor.get_ref().len(), size);
+
+        cursor.set_position(0);
+        let read = ark_circom::read_proof(&mut cursor, compress)?;
+        assert_eq!(read, proof);
+        assert!(ark_circom::verify(&params.vk, &read, &public_inputs)?);
+
+        // a truncated proof
+        let bytes = &cursor.get_ref()[..size - 1];
+        assert!(ark_circom::read_proof(&mut &bytes[..], compress).is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn zkey_matches_r1cs() -> Result<()> {
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, zkey_matrices) = read_zkey::<Bn254, _>(&mut file)?;
+    ark_circom::check_zkey_matches_r1cs(&params, &zkey_matrices)?;
+    assert_eq!(
+        zkey_matrices.num_instance_variables + zkey_matrices.num_witness_variables,
+        params.a_query.len()
+    );
+
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let mut matrices = CircomBuilder::new(cfg).setup().matrices();
+    ark_circom::check_zkey_matches_r1cs(&params, &matrices)?;
+
+    // keys generated by Arkworks have a shorter H query
+    let mut rng = thread_rng();
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let circom = CircomBuilder::new(cfg).setup();
+    let ark_params = Groth16::<Bn254, CircomReduction>::generate_random_parameters_with_reduction(
+        circom, &mut rng,
+    )?;
+    ark_circom::check_zkey_matches_r1cs(&ark_params, &matrices)?;
+
+    // the zkey of mycircuit with the R1CS of another circuit
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/complex-circuit/complex-circuit-10000-10000.wasm",
+        "./test-vectors/complex-circuit/complex-circuit-10000-10000.r1cs",
+    )?;
+    let complex = CircomBuilder::new(cfg).setup().matrices();
+    let err = ark_circom::check_zkey_matches_r1cs(&params, &complex).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ark_circom::Error::ProvingKeyMismatch { pk_wires: 4, .. }
+        ),
+        "{}",
+        err
+    );
+
+    // as many wires, but more constraints than the domain holds
+    for _ in 0..2 {
+        matrices.a.push(vec![]);
+        matrices.b.push(vec![]);
+        matrices.c.push(vec![]);
+        matrices.num_constraints += 1;
+    }
+    let err = ark_circom::check_zkey_matches_r1cs(&params, &matrices).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ark_circom::Error::DomainSizeMismatch {
+                pk_domain_size: 4,
+                domain_size: 8
+            }
+        ),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+#[test]
+fn groth16_verify_from_zkey() -> Result<()> {
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, _) = read_zkey(&mut file)?;
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+
+    let inputs = vec![
+        ("a".to_string(), vec![BigInt::from(3)]),
+        ("b".to_string(), vec![BigInt::from(11)]),
+    ];
+    let (proof, public_inputs) = ark_circom::prove(cfg, inputs, &params)?;
+
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    assert!(ark_circom::verify_from_zkey(
+        &mut file,
+        &proof,
+        &public_inputs
+    )?);
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    assert!(!ark_circom::verify_from_zkey(
+        &mut file,
+        &proof,
+        &[Fr::from(34u32)]
+    )?);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn groth16_prove_parallel() -> Result<()> {
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, _) = read_zkey(&mut file)?;
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+    let inputs = vec![
+        ("a".to_string(), vec![BigInt::from(3)]),
+        ("b".to_string(), vec![BigInt::from(11)]),
+    ];
+
+    // a single thread runs the MSMs and FFTs sequentially
+    for threads in [1, 4] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?;
+        let (proof, public_inputs) =
+            pool.install(|| ark_circom::prove(cfg.clone(), inputs.clone(), &params))?;
+        assert!(ark_circom::verify(&params.vk, &proof, &public_inputs)?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn groth16_batch_verify() -> Result<()> {
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, _) = read_zkey(&mut file)?;
+    let cfg = CircomConfig::<Fr>::new(
+        "./test-vectors/mycircuit.wasm",
+        "./test-vectors/mycircuit.r1cs",
+    )?;
+
+    let mut proofs = vec![];
+    for (a, b) in [(3, 11), (5, 7), (2, 2), (13, 17), (1, 1)] {
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(a)]),
+            ("b".to_string(), vec![BigInt::from(b)]),
+        ];
+        proofs.push(ark_circom::prove(cfg.clone(), inputs, &params)?);
+    }
+    assert!(ark_circom::batch_verify(&params.vk, &proofs)?);
+    assert_eq!(ark_circom::find_invalid_proof(&params.vk, &proofs)?, None);
+
+    // a valid proof for other public inputs
+    proofs[3].1 = proofs[1].1.clone();
+    assert!(!ark_circom::batch_verify(&params.vk, &proofs)?);
+    assert_eq!(
+        ark_circom::find_invalid_proof(&params.vk, &proofs)?,
+        Some(3)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn groth16_prove_without_r1cs() -> Result<()> {
+    let mut file = File::open("./test-vectors/test.zkey")?;
+    let (params, matrices) = read_zkey(&mut file)?;
+    let mut wtns = WitnessCalculator::new("./test-vectors/mycircuit.wasm")?;
+
+    let inputs = vec![
+        ("a".to_string(), vec![BigInt::from(3)]),
+        ("b".to_string(), vec![BigInt::from(11)]),
+    ];
+    let (proof, public_inputs) =
+        ark_circom::prove_with_matrices(&mut wtns, inputs, &params, &matrices)?;
+    assert_eq!(public_inputs, vec![Fr::from(33u32)]);
+    assert!(ark_circom::verify(&params.vk, &proof, &public_inputs)?);
+
+    // matrices of another circuit, with more public signals than the witness has wires
+    let mut other = matrices.clone();
+    other.num_instance_variables = 10;
+    let inputs = vec![
+        ("a".to_string(), vec![BigInt::from(3)]),
+        ("b".to_string(), vec![BigInt::from(11)]),
+    ];
+    let err =
+        ark_circom::prove_with_matrices(&mut wtns, inputs.clone(), &params, &other).unwrap_err();
+    assert!(
+        matches!(err, ark_circom::Error::ProvingKeyMismatch { .. }),
+        "{}",
+        err
+    );
+    let mut other = matrices;
+    other.num_instance_variables = 0;
+    other.num_witness_variables = 4;
+    let err = ark_circom::prove_with_matrices(&mut wtns, inputs, &params, &other).unwrap_err();
+    assert!(
+        matches!(err, ark_circom::Error::ProvingKeyMismatch { .. }),
+        "{}",
+        err
+    );
+
+    Ok(())
+}